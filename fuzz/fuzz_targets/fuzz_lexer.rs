@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use viv_script::fuzzing::tokenize;
+
+fuzz_target!(|data: &[u8]| {
+    // The compiler only ever sees valid UTF-8, since source files are read into a String
+    if let Ok(code) = std::str::from_utf8(data) {
+        if let Err((location, _)) = tokenize(code) {
+            // The error has to point at something that can be shown to the user
+            location.get_line_highlights(code);
+        }
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use std::path::Path;
+
+use libfuzzer_sys::fuzz_target;
+use viv_script::fuzzing::{parse_tokens, SourceLocation, Token, TokenValue};
+
+fuzz_target!(|values: Vec<TokenValue>| {
+    let tokens = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| Token {
+            value,
+            source_location: SourceLocation::new(1, index + 1, index + 1),
+            doc_comment: None,
+        })
+        .collect();
+
+    // Any token stream has to either parse or produce an error, never panic
+    let _ = parse_tokens(tokens, Path::new(""));
+});
@@ -0,0 +1,173 @@
+use crate::{
+    ast::{self, Comparison, Expression, LiteralType, PrefixOprator},
+    types::TypeInformation,
+};
+
+/// Replaces comparison chains whose result follows from comparing an expression with itself,
+/// `x == x` is always true and `x > y > x` is always false.
+/// Only chains without side effects are replaced, so function calls always run.
+pub struct AlgebraicSimplifier;
+
+impl AlgebraicSimplifier {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl super::Analyzer for AlgebraicSimplifier {
+    fn visit_expression(&mut self, expression: &mut Expression) -> crate::CompilerResult<()> {
+        if let Expression::ComparisonChain {
+            first_element,
+            comparisons,
+            metadata,
+        } = expression
+        {
+            let elements: Vec<&Expression> = std::iter::once(first_element.as_ref())
+                .chain(comparisons.iter().map(|(_, element)| element))
+                .collect();
+            let operators: Vec<Comparison> = comparisons.iter().map(|(op, _)| *op).collect();
+
+            if !elements.iter().all(|element| is_pure(element)) {
+                return Ok(());
+            }
+
+            if let Some(value) = simplify_chain(&elements, &operators) {
+                *expression = Expression::Literal(
+                    ast::ExpressionMetadata {
+                        location: metadata.location,
+                        type_information: Some(TypeInformation::Boolean),
+                    },
+                    LiteralType::Boolean(value),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `elements[i] operators[i] elements[i + 1]` for every `i`, all of them have to hold
+fn simplify_chain(elements: &[&Expression], operators: &[Comparison]) -> Option<bool> {
+    // x < x
+    let compares_with_itself = |i: usize| same_expression(elements[i], elements[i + 1]);
+    if (0..operators.len()).any(|i| compares_with_itself(i) && is_irreflexive(operators[i])) {
+        return Some(false);
+    }
+
+    // x > y > x
+    let contradiction = operators.windows(2).enumerate().any(|(i, pair)| {
+        same_expression(elements[i], elements[i + 2]) && contradicts(pair[0], pair[1])
+    });
+    if contradiction {
+        return Some(false);
+    }
+
+    // x == x <= x
+    if (0..operators.len()).all(|i| compares_with_itself(i) && is_reflexive(operators[i])) {
+        return Some(true);
+    }
+
+    None
+}
+
+fn is_reflexive(op: Comparison) -> bool {
+    matches!(
+        op,
+        Comparison::Equal | Comparison::GreaterThanEqual | Comparison::LessThanEqual
+    )
+}
+
+fn is_irreflexive(op: Comparison) -> bool {
+    matches!(
+        op,
+        Comparison::NotEqual | Comparison::GreaterThan | Comparison::LessThan
+    )
+}
+
+/// Whether `x first y second x` can never hold
+fn contradicts(first: Comparison, second: Comparison) -> bool {
+    use Comparison::{Equal, GreaterThan, GreaterThanEqual, LessThan, LessThanEqual, NotEqual};
+
+    matches!(
+        (first, second),
+        (Equal, NotEqual)
+            | (NotEqual, Equal)
+            | (GreaterThan, GreaterThan | GreaterThanEqual)
+            | (GreaterThanEqual, GreaterThan)
+            | (LessThan, LessThan | LessThanEqual)
+            | (LessThanEqual, LessThan)
+    )
+}
+
+/// Expressions that always produce the same value when evaluated twice in a row
+fn is_pure(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(_, _) | Expression::Var(_, _) => true,
+        Expression::Binary { left, right, .. } => is_pure(left) && is_pure(right),
+        // Every `&` of a temporary points at a new copy
+        Expression::PrefixExpression { op, expression, .. } => {
+            *op != PrefixOprator::AddressOf && is_pure(expression)
+        }
+        Expression::TupleIndex { tuple, .. } => is_pure(tuple),
+        Expression::Array(_, elements) | Expression::Tuple(_, elements) => {
+            elements.iter().all(is_pure)
+        }
+        Expression::ComparisonChain {
+            first_element,
+            comparisons,
+            ..
+        } => is_pure(first_element) && comparisons.iter().all(|(_, element)| is_pure(element)),
+        Expression::FunctionCall { .. } => false,
+    }
+}
+
+/// Compares the structure of two expressions, ignoring their locations
+fn same_expression(left: &Expression, right: &Expression) -> bool {
+    match (left, right) {
+        (Expression::Literal(_, left), Expression::Literal(_, right)) => left == right,
+        (Expression::Var(_, left), Expression::Var(_, right)) => left == right,
+        (
+            Expression::Binary {
+                left: left_a,
+                operator: op_a,
+                right: right_a,
+                ..
+            },
+            Expression::Binary {
+                left: left_b,
+                operator: op_b,
+                right: right_b,
+                ..
+            },
+        ) => op_a == op_b && same_expression(left_a, left_b) && same_expression(right_a, right_b),
+        (
+            Expression::PrefixExpression {
+                op: op_a,
+                expression: a,
+                ..
+            },
+            Expression::PrefixExpression {
+                op: op_b,
+                expression: b,
+                ..
+            },
+        ) => op_a == op_b && same_expression(a, b),
+        (
+            Expression::TupleIndex {
+                tuple: a,
+                index: index_a,
+                ..
+            },
+            Expression::TupleIndex {
+                tuple: b,
+                index: index_b,
+                ..
+            },
+        ) => index_a == index_b && same_expression(a, b),
+        (Expression::Array(_, a), Expression::Array(_, b))
+        | (Expression::Tuple(_, a), Expression::Tuple(_, b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_expression(a, b))
+        }
+        _ => false,
+    }
+}
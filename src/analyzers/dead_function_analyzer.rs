@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use crate::{ast, diagnostics::CompilerWarning, SourceLocation};
+
+/// Finds functions that are never called, `main` and `pub` functions are used from outside
+pub struct DeadFunctionAnalyzer {
+    defined_functions: Vec<(String, SourceLocation)>,
+    called_functions: HashSet<String>,
+}
+
+impl DeadFunctionAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            defined_functions: Vec::new(),
+            called_functions: HashSet::new(),
+        }
+    }
+
+    /// Should be called after the whole file has been visited
+    pub fn warnings(self) -> Vec<CompilerWarning> {
+        self.defined_functions
+            .into_iter()
+            .filter(|(name, _)| !self.called_functions.contains(name))
+            .map(|(name, location)| CompilerWarning::UnusedFunction { location, name })
+            .collect()
+    }
+}
+
+impl super::Analyzer for DeadFunctionAnalyzer {
+    fn pre_visit_toplevel(
+        &mut self,
+        statement: &mut ast::TopLevelStatement,
+    ) -> crate::CompilerResult<()> {
+        match statement {
+            ast::TopLevelStatement::FunctionDefinition {
+                function_name,
+                function_name_location,
+                is_public,
+                ..
+            } => {
+                if function_name != "main" && !*is_public {
+                    self.defined_functions
+                        .push((function_name.clone(), *function_name_location));
+                }
+            }
+            ast::TopLevelStatement::TestFunction { .. }
+            | ast::TopLevelStatement::ExternFunction { .. }
+            | ast::TopLevelStatement::BitStruct { .. }
+            | ast::TopLevelStatement::TypeAlias { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    fn visit_expression(&mut self, expression: &mut ast::Expression) -> crate::CompilerResult<()> {
+        if let ast::Expression::FunctionCall { name, .. } = expression {
+            self.called_functions.insert(name.clone());
+        }
+
+        Ok(())
+    }
+}
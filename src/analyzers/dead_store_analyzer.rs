@@ -0,0 +1,89 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{ast, diagnostics::CompilerWarning, SourceLocation};
+
+/// Finds assignments that are overwritten before the variable is read.
+/// Only straight line code is checked, entering or leaving a block forgets the unread assignments,
+/// and variables that had their address taken are never reported since they can be read through
+/// the pointer. Has to run after the type analyzer so shadowed variables have their own names.
+pub struct DeadStoreAnalyzer {
+    /// Where each variable was last assigned, if it has not been read since
+    unread_stores: HashMap<String, SourceLocation>,
+    address_taken: HashSet<String>,
+    pub warnings: Vec<CompilerWarning>,
+}
+
+impl DeadStoreAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            unread_stores: HashMap::new(),
+            address_taken: HashSet::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl super::Analyzer for DeadStoreAnalyzer {
+    fn pre_visit_toplevel(
+        &mut self,
+        _statement: &mut ast::TopLevelStatement,
+    ) -> crate::CompilerResult<()> {
+        self.unread_stores.clear();
+        self.address_taken.clear();
+
+        Ok(())
+    }
+
+    fn enter_codebody(&mut self) {
+        self.unread_stores.clear();
+    }
+
+    fn exit_codebody(&mut self) {
+        self.unread_stores.clear();
+    }
+
+    fn visit_expression(&mut self, expression: &mut ast::Expression) -> crate::CompilerResult<()> {
+        match expression {
+            ast::Expression::Var(_, name) => {
+                self.unread_stores.remove(name);
+            }
+            ast::Expression::PrefixExpression {
+                op: ast::PrefixOprator::AddressOf,
+                expression,
+                ..
+            } => {
+                if let ast::Expression::Var(_, name) = expression.as_ref() {
+                    self.address_taken.insert(name.clone());
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn visit_stmt(&mut self, statement: &mut ast::Statement) -> crate::CompilerResult<()> {
+        if let ast::Statement::Assignment {
+            expression_location,
+            var_name,
+            ..
+        } = statement
+        {
+            if self.address_taken.contains(var_name) {
+                return Ok(());
+            }
+
+            if let Some(location) = self
+                .unread_stores
+                .insert(var_name.clone(), *expression_location)
+            {
+                // Shadowing variables are stored as `name#1`, the warning should use the real name
+                let name = var_name.split('#').next().unwrap().to_string();
+                self.warnings
+                    .push(CompilerWarning::DeadStore { location, name });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,38 +1,432 @@
+use std::collections::HashMap;
+
 use crate::ast;
-use crate::types::TypeInformation;
+use crate::diagnostics::ErrorCode;
+use crate::llvm_generator::RUNTIME_FUNCTIONS;
+use crate::types::{Ownership, TypeInformation};
+
+/// What a function takes and returns, used to type check calls to it
+#[derive(Debug, Clone)]
+pub struct FunctionSignature {
+    pub parameters: Vec<TypeInformation>,
+    pub return_type: TypeInformation,
+    /// Calls are warned about, see `FunctionMetadata::deprecated`
+    pub deprecated: Option<String>,
+    /// Takes any number of arguments after `parameters`
+    pub is_variadic: bool,
+}
 
-pub struct DefinitionAnalyzer {}
+/// Bit structs can be at most as wide as the largest integer register
+const MAX_BIT_STRUCT_WIDTH: usize = 64;
+
+pub struct DefinitionAnalyzer {
+    pub functions: HashMap<String, FunctionSignature>,
+    /// The types declared with `struct`, by name
+    pub bit_structs: HashMap<String, TypeInformation>,
+    /// The types declared with `type Name = Type;`, by name
+    pub type_aliases: HashMap<String, TypeInformation>,
+    /// The target and its location of every alias, collected up front since aliases can be used
+    /// before they are declared. Moved into `type_aliases` once resolved
+    alias_targets: HashMap<String, (String, crate::SourceLocation)>,
+    /// Builtin constants like `Num::MAX`, by their full name
+    pub constants: HashMap<String, (TypeInformation, ast::LiteralType)>,
+    /// Where each function name was first defined
+    locations: HashMap<String, crate::SourceLocation>,
+}
 
 impl DefinitionAnalyzer {
     pub     fn new() -> Self {
-        Self {}
+        Self {
+            functions: HashMap::new(),
+            bit_structs: HashMap::new(),
+            type_aliases: HashMap::new(),
+            alias_targets: HashMap::new(),
+            constants: HashMap::from([
+                (
+                    "Num::MIN".to_string(),
+                    (TypeInformation::Number, ast::LiteralType::Number(i32::MIN)),
+                ),
+                (
+                    "Num::MAX".to_string(),
+                    (TypeInformation::Number, ast::LiteralType::Number(i32::MAX)),
+                ),
+            ]),
+            locations: HashMap::new(),
+        }
     }
 
-    fn get_type(type_name: &str) -> Option<TypeInformation> {
+    pub fn get_type(
+        type_name: &str,
+        bit_structs: &HashMap<String, TypeInformation>,
+        type_aliases: &HashMap<String, TypeInformation>,
+    ) -> Option<TypeInformation> {
+        if let Some(pointee) = type_name.strip_prefix('&') {
+            // The pointer does not own what it points to
+            return DefinitionAnalyzer::get_type(pointee, bit_structs, type_aliases)
+                .map(|type_| TypeInformation::Pointer(Box::new(type_.mark_borrowed())));
+        }
+
+        if let Some(array) = type_name
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let (element, size) = array.rsplit_once(';')?;
+            let element =
+                DefinitionAnalyzer::get_type(element.trim(), bit_structs, type_aliases)?;
+            return Some(TypeInformation::Array {
+                element: Box::new(element),
+                size: size.trim().parse().ok()?,
+            });
+        }
+
+        if let Some(elements) = type_name
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return DefinitionAnalyzer::split_tuple_elements(elements)
+                .into_iter()
+                .map(|element| {
+                    DefinitionAnalyzer::get_type(element, bit_structs, type_aliases)
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(TypeInformation::Tuple);
+        }
+
         match type_name {
             "Num" => Some(TypeInformation::Number),
             // This would be different in different contexts, but owned can be for all...
-            "String" => Some(TypeInformation::String(true)),
+            "String" => Some(TypeInformation::String(Ownership::Owned)),
+            "Str" => Some(TypeInformation::StringSlice),
             "Bool" => Some(TypeInformation::Boolean),
-            _ => None,
+            _ => bit_structs
+                .get(type_name)
+                .or_else(|| type_aliases.get(type_name))
+                .cloned(),
+        }
+    }
+
+    /// Finds every `type` declaration, so aliases can be used before they are declared
+    pub fn collect_type_aliases(&mut self, file: &ast::File) -> crate::CompilerResult<()> {
+        for statement in &file.0 {
+            let ast::TopLevelStatement::TypeAlias {
+                name,
+                name_location,
+                target_type,
+                target_location,
+            } = statement
+            else {
+                continue;
+            };
+
+            // Builtin names are checked first, so the alias could never be used
+            let none = HashMap::new();
+            if name == "Void" || DefinitionAnalyzer::get_type(name, &none, &none).is_some() {
+                return Err((
+                    *name_location,
+                    ErrorCode::InvalidType.tag(format!("{name} is already a builtin type")),
+                ));
+            }
+            let target = (target_type.clone(), *target_location);
+            if self.alias_targets.insert(name.clone(), target).is_some() {
+                return Err((
+                    *name_location,
+                    ErrorCode::InvalidType.tag(format!("Type alias {name} is defined twice")),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves an alias after the aliases its target uses, `resolving` are the aliases waiting
+    /// on this one, finding the alias in there means it refers to itself
+    fn resolve_alias(
+        &mut self,
+        name: &str,
+        resolving: &mut Vec<String>,
+    ) -> crate::CompilerResult<()> {
+        if self.type_aliases.contains_key(name) {
+            return Ok(());
+        }
+
+        let (target, location) = self.alias_targets[name].clone();
+        let is_cycle = resolving.iter().any(|alias| alias == name);
+        resolving.push(name.to_string());
+        if is_cycle {
+            return Err((
+                location,
+                ErrorCode::CyclicTypeAlias.tag(format!(
+                    "Type alias {name} refers to itself, {}",
+                    resolving.join(" -> ")
+                )),
+            ));
+        }
+
+        for alias in self.unresolved_aliases(&target) {
+            self.resolve_alias(&alias, resolving)?;
+        }
+        resolving.pop();
+
+        let type_ = DefinitionAnalyzer::get_type(&target, &self.bit_structs, &self.type_aliases)
+            .ok_or_else(|| (location, ErrorCode::InvalidType.tag("Invalid type name")))?;
+        self.type_aliases.insert(name.to_string(), type_);
+
+        Ok(())
+    }
+
+    /// The aliases used in a type name that have not been resolved yet
+    fn unresolved_aliases(&self, type_name: &str) -> Vec<String> {
+        type_name
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| {
+                self.alias_targets.contains_key(*word) && !self.type_aliases.contains_key(*word)
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Splits `Num, (Num, Bool)` on the commas that are not inside a nested tuple
+    fn split_tuple_elements(elements: &str) -> Vec<&str> {
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (index, char) in elements.char_indices() {
+            match char {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(elements[start..index].trim());
+                    start = index + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(elements[start..].trim());
+        parts
+    }
+
+    /// Remembers where the function is defined, erroring if it already was
+    fn define(
+        &mut self,
+        name: &str,
+        location: crate::SourceLocation,
+    ) -> crate::CompilerResult<()> {
+        if let Some(original) = self.locations.insert(name.to_string(), location) {
+            return Err((
+                location,
+                ErrorCode::DuplicateFunction.tag(format!(
+                    "Function {} is already defined on line {}",
+                    name, original.line_start
+                )),
+            ));
         }
+
+        Ok(())
+    }
+
+    /// Errors if a function defined in the code would replace a C function the runtime uses
+    fn check_reserved(name: &str, location: crate::SourceLocation) -> crate::CompilerResult<()> {
+        if RUNTIME_FUNCTIONS.contains(&name) {
+            return Err((
+                location,
+                ErrorCode::ReservedName.tag(format!(
+                    "{name} is a C function used by the compiled code, pick another name"
+                )),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn analyze_bit_struct(
+        name: &str,
+        location: crate::SourceLocation,
+        fields: &[ast::BitField],
+    ) -> crate::CompilerResult<TypeInformation> {
+        let mut field_types: Vec<(String, TypeInformation, usize)> = Vec::new();
+        for field in fields {
+            let type_ = match field.type_name.as_str() {
+                "Num" => TypeInformation::Number,
+                "Bool" => TypeInformation::Boolean,
+                _ => {
+                    return Err((
+                        field.type_location,
+                        ErrorCode::InvalidType.tag("Bit struct fields have to be Num or Bool"),
+                    ))
+                }
+            };
+
+            let max_width = if type_ == TypeInformation::Number {
+                32
+            } else {
+                MAX_BIT_STRUCT_WIDTH
+            };
+            if field.width == 0 || field.width > max_width {
+                return Err((
+                    field.type_location,
+                    ErrorCode::InvalidType.tag(format!(
+                        "Field {} has to be between 1 and {} bits wide",
+                        field.name, max_width
+                    )),
+                ));
+            }
+            if field_types.iter().any(|(other, _, _)| *other == field.name) {
+                return Err((
+                    field.type_location,
+                    ErrorCode::InvalidType.tag(format!("Field {} is defined twice", field.name)),
+                ));
+            }
+
+            field_types.push((field.name.clone(), type_, field.width));
+        }
+
+        let width: usize = field_types.iter().map(|(_, _, width)| width).sum();
+        if width == 0 {
+            return Err((
+                location,
+                ErrorCode::InvalidType.tag(format!("Bit struct {name} has no fields")),
+            ));
+        }
+        if width > MAX_BIT_STRUCT_WIDTH {
+            return Err((
+                location,
+                ErrorCode::InvalidType.tag(format!(
+                    "Bit struct {name} is {width} bits wide, the limit is {MAX_BIT_STRUCT_WIDTH}"
+                )),
+            ));
+        }
+
+        Ok(TypeInformation::BitStruct {
+            name: name.to_string(),
+            fields: field_types,
+        })
+    }
+
+    fn resolve_type(
+        &mut self,
+        type_name: &str,
+        location: crate::SourceLocation,
+    ) -> crate::CompilerResult<TypeInformation> {
+        for alias in self.unresolved_aliases(type_name) {
+            self.resolve_alias(&alias, &mut Vec::new())?;
+        }
+
+        DefinitionAnalyzer::get_type(type_name, &self.bit_structs, &self.type_aliases)
+            .ok_or_else(|| (location, ErrorCode::InvalidType.tag("Invalid type name")))
     }
 }
 
 impl super::Analyzer for DefinitionAnalyzer {
     fn visit_toplevel(&mut self, statement: &mut ast::TopLevelStatement) -> crate::CompilerResult<()> {
+        let is_extern = matches!(statement, ast::TopLevelStatement::ExternFunction { .. });
         match statement {
             ast::TopLevelStatement::FunctionDefinition {
+                function_name,
+                function_name_location,
+                parameters,
                 return_type_name,
                 return_type_location,
                 metadata,
                 ..
+            }
+            | ast::TopLevelStatement::ExternFunction {
+                function_name,
+                function_name_location,
+                parameters,
+                return_type_name,
+                return_type_location,
+                metadata,
             } => {
-                let return_type = match DefinitionAnalyzer::get_type(return_type_name) {
-                    Some(type_) => type_,
-                    None => return Err((*return_type_location, "Invalid type name".to_string())),
+                if !is_extern {
+                    DefinitionAnalyzer::check_reserved(function_name, *function_name_location)?;
+                }
+                self.define(function_name, *function_name_location)?;
+
+                // Only allowed as a return type, so `get_type` does not know it
+                let mut return_type = if return_type_name == "Void" {
+                    TypeInformation::Void
+                } else {
+                    self.resolve_type(return_type_name, *return_type_location)?
                 };
+                if is_extern {
+                    // Strings from C are not ours to free
+                    return_type = return_type.mark_borrowed();
+                }
+                let is_variadic = parameters
+                    .last()
+                    .map_or(false, |parameter| parameter.type_name == "...");
+                let mut parameter_types = Vec::new();
+                for parameter in &parameters[..parameters.len() - usize::from(is_variadic)] {
+                    parameter_types
+                        .push(self.resolve_type(&parameter.type_name, parameter.type_location)?);
+                }
+
+                self.functions.insert(
+                    function_name.clone(),
+                    FunctionSignature {
+                        parameters: parameter_types.clone(),
+                        return_type: return_type.clone(),
+                        deprecated: metadata.deprecated.clone(),
+                        is_variadic,
+                    },
+                );
+                // A function from the code gets the number of rest arguments as its last
+                // parameter, C functions like printf do not
+                if is_variadic && !is_extern {
+                    parameter_types.push(TypeInformation::VarArgs);
+                }
                 metadata.return_type.replace(return_type);
+                metadata.parameter_types = parameter_types;
+                metadata.is_variadic = is_variadic;
+            }
+            ast::TopLevelStatement::BitStruct {
+                name,
+                name_location,
+                fields,
+            } => {
+                DefinitionAnalyzer::check_reserved(name, *name_location)?;
+                self.define(name, *name_location)?;
+                let bit_struct =
+                    DefinitionAnalyzer::analyze_bit_struct(name, *name_location, fields)?;
+
+                // `Name()` creates a zeroed value
+                self.functions.insert(
+                    name.clone(),
+                    FunctionSignature {
+                        parameters: Vec::new(),
+                        return_type: bit_struct.clone(),
+                        deprecated: None,
+                        is_variadic: false,
+                    },
+                );
+                self.bit_structs.insert(name.clone(), bit_struct);
+            }
+            // Resolved here even when unused, so mistakes in the target are still reported
+            ast::TopLevelStatement::TypeAlias { name, .. } => {
+                self.resolve_alias(name, &mut Vec::new())?;
+            }
+            // Not added to `functions`, tests can not be called
+            ast::TopLevelStatement::TestFunction {
+                name,
+                name_location,
+                metadata,
+                ..
+            } => {
+                DefinitionAnalyzer::check_reserved(name, *name_location)?;
+                self.define(name, *name_location)?;
+                metadata.return_type.replace(TypeInformation::Void);
+            }
+        }
+
+        if let ast::TopLevelStatement::FunctionDefinition {
+            named_returns,
+            metadata,
+            ..
+        } = statement
+        {
+            for named in &*named_returns {
+                let type_ = self.resolve_type(&named.type_name, named.type_location)?;
+                metadata.named_returns.push((named.name.clone(), type_));
             }
         }
 
@@ -0,0 +1,53 @@
+use crate::{ast, types::TypeInformation, SourceLocation};
+
+/// Finds the smallest expression containing a position, used for hovering in the language server.
+/// Has to run after the type analyzer, expressions without a type are ignored.
+pub struct ExpressionFinder {
+    line: usize,
+    column: usize,
+    pub found: Option<(SourceLocation, TypeInformation)>,
+}
+
+impl ExpressionFinder {
+    /// `line` and `column` are 1-indexed, like `SourceLocation`
+    pub fn new(line: usize, column: usize) -> Self {
+        Self {
+            line,
+            column,
+            found: None,
+        }
+    }
+
+    fn contains(&self, location: &SourceLocation) -> bool {
+        let after_start = (self.line, self.column) >= (location.line_start, location.char_start);
+        let before_end = (self.line, self.column) <= (location.line_end, location.char_end);
+        after_start && before_end
+    }
+}
+
+/// Lines first, so an expression spanning two lines is always bigger than one on a single line
+fn size(location: &SourceLocation) -> (usize, usize) {
+    (
+        location.line_end - location.line_start,
+        location.char_end.abs_diff(location.char_start),
+    )
+}
+
+impl super::Analyzer for ExpressionFinder {
+    fn visit_expression(&mut self, expression: &mut ast::Expression) -> crate::CompilerResult<()> {
+        let metadata = expression.metadata();
+        let Some(type_information) = &metadata.type_information else {
+            return Ok(());
+        };
+
+        let smaller = self
+            .found
+            .as_ref()
+            .map_or(true, |(found, _)| size(&metadata.location) < size(found));
+        if self.contains(&metadata.location) && smaller {
+            self.found = Some((metadata.location, type_information.clone()));
+        }
+
+        Ok(())
+    }
+}
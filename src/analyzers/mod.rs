@@ -1,7 +1,26 @@
+mod algebraic_simplifier;
+mod dead_function_analyzer;
+mod dead_store_analyzer;
 mod definition_analyzer;
+mod expression_finder;
+mod return_check_analyzer;
+mod small_int_analyzer;
 mod types_analyzer;
 
-use crate::{ast, CompilerResult};
+use crate::{
+    ast, diagnostics::CompilerWarning, types::TypeInformation, CompilerConfig, CompilerResult,
+    SourceLocation,
+};
+
+/// Names of the analysis passes in the order they run, used by `--print-ast-after-pass`
+pub const ANALYSIS_PASSES: [&str; 6] = [
+    "definitions",
+    "types",
+    "return_check",
+    "dead_stores",
+    "simplify",
+    "dead_functions",
+];
 
 trait Analyzer {
     fn visit_expression(&mut self, _expression: &mut ast::Expression) -> CompilerResult<()> {
@@ -13,6 +32,16 @@ trait Analyzer {
     fn visit_toplevel(&mut self, _statement: &mut ast::TopLevelStatement) -> CompilerResult<()> {
         Ok(())
     }
+    /// Called for the variable of a `for` loop, before the body is visited
+    fn define_loop_variable(
+        &mut self,
+        _var: &mut String,
+        _array: &ast::Expression,
+    ) -> CompilerResult<()> {
+        Ok(())
+    }
+    fn enter_codebody(&mut self) {}
+    fn exit_codebody(&mut self) {}
     fn pre_visit_toplevel(
         &mut self,
         _statement: &mut ast::TopLevelStatement,
@@ -42,6 +71,17 @@ trait Analyzer {
                 }
             },
             ast::Expression::PrefixExpression { expression, ..} => self._visit_expression(expression)?,
+            ast::Expression::FunctionCall { arguments, .. } => {
+                for argument in arguments {
+                    self._visit_expression(argument)?;
+                }
+            }
+            ast::Expression::Array(_, elements) | ast::Expression::Tuple(_, elements) => {
+                for element in elements {
+                    self._visit_expression(element)?;
+                }
+            }
+            ast::Expression::TupleIndex { tuple, .. } => self._visit_expression(tuple)?,
             ast::Expression::Var(_, _) | ast::Expression::Literal(_, _) => {}
         }
 
@@ -50,38 +90,142 @@ trait Analyzer {
 
     fn _visit_stmt(&mut self, statement: &mut ast::Statement) -> CompilerResult<()> {
         match statement {
-            ast::Statement::Print(expr)
-            | ast::Statement::Assert(expr)
+            ast::Statement::Print(expressions) | ast::Statement::PrintErr(expressions) => {
+                for expr in expressions {
+                    self._visit_expression(expr)?;
+                }
+            }
+            ast::Statement::Assert(expr, _)
             | ast::Statement::Assignment {
                 expression_location: _,
                 var_name: _,
                 expression: expr,
             }
-            | ast::Statement::Return(expr)
-            | ast::Statement::Test(_, expr) => self._visit_expression(expr)?,
-            ast::Statement::If { condition, then, otherwise } => {
+            | ast::Statement::Return(Some(expr), _)
+            | ast::Statement::Test(_, expr)
+            | ast::Statement::Discard(expr)
+            | ast::Statement::AssertType {
+                expression: expr, ..
+            } => self._visit_expression(expr)?,
+            ast::Statement::AssertEq {
+                expected, actual, ..
+            } => {
+                self._visit_expression(expected)?;
+                self._visit_expression(actual)?;
+            }
+            ast::Statement::If {
+                condition,
+                then,
+                else_if_chains,
+                otherwise,
+            } => {
                 self._visit_expression(condition)?;
                 self._visit_codebody(then)?;
+                for (condition, body) in else_if_chains {
+                    self._visit_expression(condition)?;
+                    self._visit_codebody(body)?;
+                }
                 self._visit_codebody(otherwise)?;
             }
+            ast::Statement::While {
+                condition,
+                body,
+                else_body,
+            } => {
+                self._visit_expression(condition)?;
+                self._visit_codebody(body)?;
+                if let Some(else_body) = else_body {
+                    self._visit_codebody(else_body)?;
+                }
+            }
+            ast::Statement::WhileAssign {
+                assignment,
+                condition,
+                body,
+            } => {
+                // The assignment has to be analyzed first so the condition can use the variable
+                self._visit_stmt(assignment)?;
+                self._visit_expression(condition)?;
+                self._visit_codebody(body)?;
+            }
+            ast::Statement::ForEach { var, array, body } => {
+                self._visit_expression(array)?;
+
+                // The loop variable lives in its own scope around the body
+                self.enter_codebody();
+                self.define_loop_variable(var, array)?;
+                self._visit_codebody(body)?;
+                self.exit_codebody();
+            }
+            ast::Statement::Switch {
+                scrutinee,
+                arms,
+                default,
+            } => {
+                self._visit_expression(scrutinee)?;
+                for (_, _, body) in arms {
+                    self._visit_codebody(body)?;
+                }
+                if let Some(default) = default {
+                    self._visit_codebody(default)?;
+                }
+            }
+            ast::Statement::Repeat { count, body } => {
+                self._visit_expression(count)?;
+                self._visit_codebody(body)?;
+            }
+            ast::Statement::Bench {
+                iterations, body, ..
+            } => {
+                self._visit_expression(iterations)?;
+                self._visit_expression(body)?;
+            }
+            ast::Statement::DoWhile { body, condition } => {
+                self._visit_codebody(body)?;
+                self._visit_expression(condition)?;
+            }
+            ast::Statement::CStyleFor {
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                // Variables from `init` live in their own scope around the body
+                self.enter_codebody();
+                self._visit_stmt(init)?;
+                self._visit_expression(condition)?;
+                self._visit_codebody(body)?;
+                self._visit_stmt(update)?;
+                self.exit_codebody();
+            }
+            ast::Statement::Defer { body, .. } => self._visit_stmt(body)?,
+            ast::Statement::Break | ast::Statement::Return(None, _) => {}
         }
 
         self.visit_stmt(statement)
     }
 
     fn _visit_codebody(&mut self, body: &mut ast::CodeBody) -> CompilerResult<()> {
+        self.enter_codebody();
         body.0
             .iter_mut()
-            .try_for_each(|stmt| self._visit_stmt(stmt))
+            .try_for_each(|stmt| self._visit_stmt(stmt))?;
+        self.exit_codebody();
+
+        Ok(())
     }
 
     fn _visit_toplevel(&mut self, statement: &mut ast::TopLevelStatement) -> CompilerResult<()> {
         self.pre_visit_toplevel(statement)?;
 
         match statement {
-            ast::TopLevelStatement::FunctionDefinition { body, .. } => {
+            ast::TopLevelStatement::FunctionDefinition { body, .. }
+            | ast::TopLevelStatement::TestFunction { body, .. } => {
                 self._visit_codebody(body)?;
             }
+            ast::TopLevelStatement::ExternFunction { .. }
+            | ast::TopLevelStatement::BitStruct { .. }
+            | ast::TopLevelStatement::TypeAlias { .. } => {}
         }
 
         self.visit_toplevel(statement)
@@ -96,12 +240,67 @@ trait Analyzer {
     }
 }
 
-pub fn apply_analyzer(code: &mut ast::File) -> CompilerResult<()> {
-    let mut type_analyzer = types_analyzer::TypeAnalyzer::new();
+pub fn apply_analyzer(
+    code: &mut ast::File,
+    config: &CompilerConfig,
+) -> CompilerResult<Vec<CompilerWarning>> {
     let mut definition_analyzer = definition_analyzer::DefinitionAnalyzer::new();
-
+    definition_analyzer.collect_type_aliases(code)?;
     definition_analyzer.visit_file(code)?;
+    print_ast_after("definitions", code, config);
+
+    let mut type_analyzer = types_analyzer::TypeAnalyzer::new(
+        definition_analyzer.functions,
+        definition_analyzer.bit_structs,
+        definition_analyzer.type_aliases,
+        definition_analyzer.constants,
+        config.warn_shadowing,
+        config.small_ints,
+    );
     type_analyzer.visit_file(code)?;
+    print_ast_after("types", code, config);
+    return_check_analyzer::ReturnCheckAnalyzer::new().visit_file(code)?;
+    print_ast_after("return_check", code, config);
+
+    let mut warnings = type_analyzer.warnings;
+    // Before the simplifier, which can remove reads
+    if config.warn_dead_stores {
+        let mut dead_store_analyzer = dead_store_analyzer::DeadStoreAnalyzer::new();
+        dead_store_analyzer.visit_file(code)?;
+        print_ast_after("dead_stores", code, config);
+        warnings.extend(dead_store_analyzer.warnings);
+    }
+
+    if config.optimize {
+        algebraic_simplifier::AlgebraicSimplifier::new().visit_file(code)?;
+        print_ast_after("simplify", code, config);
+    }
 
-    Ok(())
+    if config.warn_dead_functions {
+        let mut dead_function_analyzer = dead_function_analyzer::DeadFunctionAnalyzer::new();
+        dead_function_analyzer.visit_file(code)?;
+        print_ast_after("dead_functions", code, config);
+        warnings.extend(dead_function_analyzer.warnings());
+    }
+
+    Ok(warnings)
+}
+
+/// The smallest expression containing the 1-indexed position and its type,
+/// the code has to be analyzed already
+pub fn find_expression_type(
+    code: &mut ast::File,
+    line: usize,
+    column: usize,
+) -> Option<(SourceLocation, TypeInformation)> {
+    let mut finder = expression_finder::ExpressionFinder::new(line, column);
+    finder.visit_file(code).ok()?;
+    finder.found
+}
+
+/// Passes that are skipped because of the config never print anything
+fn print_ast_after(pass: &str, code: &ast::File, config: &CompilerConfig) {
+    if config.print_ast_after_pass.as_deref() == Some(pass) {
+        eprintln!("AST after {pass}:\n{code:#?}");
+    }
 }
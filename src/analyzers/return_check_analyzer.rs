@@ -0,0 +1,98 @@
+use crate::{ast, diagnostics::ErrorCode, types::TypeInformation};
+
+/// Makes sure functions returning a value can not reach the end of their body,
+/// and marks the calls a function returns directly to itself as tail calls
+pub struct ReturnCheckAnalyzer {
+    /// The function being visited, `None` in tests
+    function_name: Option<String>,
+}
+
+impl ReturnCheckAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            function_name: None,
+        }
+    }
+
+    fn always_returns(body: &ast::CodeBody) -> bool {
+        body.0.iter().any(|stmt| match stmt {
+            ast::Statement::Return(..) => true,
+            ast::Statement::Switch { arms, default, .. } => {
+                arms.iter().all(|(_, _, body)| Self::always_returns(body))
+                    && default.as_ref().map_or(false, Self::always_returns)
+            }
+            // The body always runs at least once
+            ast::Statement::DoWhile { body, .. } => Self::always_returns(body),
+            ast::Statement::If {
+                then,
+                else_if_chains,
+                otherwise,
+                ..
+            } => {
+                Self::always_returns(then)
+                    && else_if_chains
+                        .iter()
+                        .all(|(_, body)| Self::always_returns(body))
+                    && Self::always_returns(otherwise)
+            }
+            _ => false,
+        })
+    }
+}
+
+impl super::Analyzer for ReturnCheckAnalyzer {
+    fn pre_visit_toplevel(
+        &mut self,
+        statement: &mut ast::TopLevelStatement,
+    ) -> crate::CompilerResult<()> {
+        self.function_name =
+            if let ast::TopLevelStatement::FunctionDefinition { function_name, .. } = statement {
+                Some(function_name.clone())
+            } else {
+                None
+            };
+        Ok(())
+    }
+
+    fn visit_stmt(&mut self, statement: &mut ast::Statement) -> crate::CompilerResult<()> {
+        if let ast::Statement::Return(
+            Some(ast::Expression::FunctionCall {
+                name, is_tail_call, ..
+            }),
+            _,
+        ) = statement
+        {
+            *is_tail_call = self.function_name.as_ref() == Some(name);
+        }
+
+        Ok(())
+    }
+
+    fn visit_toplevel(
+        &mut self,
+        statement: &mut ast::TopLevelStatement,
+    ) -> crate::CompilerResult<()> {
+        if let ast::TopLevelStatement::FunctionDefinition {
+            function_name,
+            body,
+            body_end_location,
+            metadata,
+            ..
+        } = statement
+        {
+            // Void functions return at the end of their body
+            let returns_void = metadata.return_type == Some(TypeInformation::Void);
+            if !returns_void && !Self::always_returns(body) {
+                // The end of the body is where the value is missing
+                return Err((
+                    *body_end_location,
+                    ErrorCode::MissingReturn.tag(format!(
+                        "Function {function_name} can reach the end without returning a value"
+                    )),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use super::Analyzer;
+use crate::{ast, types::TypeInformation};
+
+/// The smallest and biggest number literal assigned to a variable,
+/// and the size when the literals are in an array
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct LiteralRange {
+    array_size: Option<usize>,
+    min: i64,
+    max: i64,
+}
+
+impl LiteralRange {
+    fn of(expression: &ast::Expression) -> Option<Self> {
+        let value = match expression {
+            ast::Expression::Literal(_, ast::LiteralType::Number(value)) => i64::from(*value),
+            ast::Expression::PrefixExpression {
+                op: ast::PrefixOprator::Negate,
+                expression,
+                ..
+            } => match expression.as_ref() {
+                ast::Expression::Literal(_, ast::LiteralType::Number(value)) => -i64::from(*value),
+                _ => return None,
+            },
+            ast::Expression::Array(_, elements) => {
+                let (first, rest) = elements.split_first()?;
+                let range = rest.iter().try_fold(Self::of(first)?, |range, element| {
+                    range.merge(Self::of(element)?)
+                })?;
+                // Arrays of arrays stay Num
+                if range.array_size.is_some() {
+                    return None;
+                }
+                return Some(Self {
+                    array_size: Some(elements.len()),
+                    ..range
+                });
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            array_size: None,
+            min: value,
+            max: value,
+        })
+    }
+
+    fn merge(self, other: Self) -> Option<Self> {
+        (self.array_size == other.array_size).then_some(Self {
+            array_size: self.array_size,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        })
+    }
+
+    /// `None` when the literals only fit in a Num
+    fn small_type(self) -> Option<TypeInformation> {
+        let fits = |min: i64, max: i64| min <= self.min && self.max <= max;
+        let number = if fits(i8::MIN.into(), i8::MAX.into()) {
+            TypeInformation::Number8
+        } else if fits(i16::MIN.into(), i16::MAX.into()) {
+            TypeInformation::Number16
+        } else {
+            return None;
+        };
+
+        Some(match self.array_size {
+            Some(size) => TypeInformation::Array {
+                element: Box::new(number),
+                size,
+            },
+            None => number,
+        })
+    }
+}
+
+/// Finds the variables of a function that are only ever assigned number literals, or arrays of
+/// them, for `--small-ints`. Runs before the type analyzer renames shadowing variables, so a name
+/// only counts as small if every variable with that name is.
+///
+/// Reading a small number widens it to a Num, but the smaller type would leak out through the
+/// address of a variable, or an array that is used as a value. Those variables stay Num.
+#[derive(Default)]
+pub struct SmallIntAnalyzer {
+    /// `None` once a variable is assigned anything else
+    ranges: HashMap<String, Option<LiteralRange>>,
+    /// Variables that have their address taken
+    escaping: HashSet<String>,
+    /// How often each variable is read at all, and only looped over, printed or passed to `len`
+    reads: HashMap<String, usize>,
+    contained_reads: HashMap<String, usize>,
+}
+
+impl SmallIntAnalyzer {
+    /// The type to store each variable in, for the variables that fit in less than a Num
+    pub fn small_types(
+        body: &mut ast::CodeBody,
+        parameters: &[ast::Parameter],
+    ) -> HashMap<String, TypeInformation> {
+        let mut analyzer = Self::default();
+        // Parameters already have a type
+        for parameter in parameters {
+            analyzer.ranges.insert(parameter.name.clone(), None);
+        }
+        analyzer
+            ._visit_codebody(body)
+            .expect("Collecting the literals can not fail");
+
+        let escapes = |name: &String, range: &LiteralRange| {
+            let reads = analyzer.reads.get(name).copied().unwrap_or_default();
+            let contained_reads = analyzer.contained_reads.get(name).copied().unwrap_or_default();
+            analyzer.escaping.contains(name)
+                || (range.array_size.is_some() && reads > contained_reads)
+        };
+        analyzer
+            .ranges
+            .iter()
+            .filter_map(|(name, range)| {
+                let range = range.filter(|range| !escapes(name, range))?;
+                Some((name.clone(), range.small_type()?))
+            })
+            .collect()
+    }
+
+    /// Counts a read of the variable that can not leak its type
+    fn contain(&mut self, expression: &ast::Expression) {
+        if let ast::Expression::Var(_, name) = expression {
+            *self.contained_reads.entry(name.clone()).or_default() += 1;
+        }
+    }
+
+    fn add(&mut self, name: &str, range: Option<LiteralRange>) {
+        let merged = match self.ranges.get(name) {
+            Some(existing) => existing.zip(range).and_then(|(a, b)| a.merge(b)),
+            None => range,
+        };
+        self.ranges.insert(name.to_string(), merged);
+    }
+}
+
+impl Analyzer for SmallIntAnalyzer {
+    fn visit_expression(&mut self, expression: &mut ast::Expression) -> crate::CompilerResult<()> {
+        match expression {
+            ast::Expression::Var(_, name) => {
+                *self.reads.entry(name.clone()).or_default() += 1;
+            }
+            ast::Expression::PrefixExpression {
+                op: ast::PrefixOprator::AddressOf,
+                expression,
+                ..
+            } => {
+                if let ast::Expression::Var(_, name) = expression.as_ref() {
+                    self.escaping.insert(name.clone());
+                }
+            }
+            ast::Expression::FunctionCall {
+                name, arguments, ..
+            } if name == "len" => {
+                for argument in &*arguments {
+                    self.contain(argument);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn visit_stmt(&mut self, statement: &mut ast::Statement) -> crate::CompilerResult<()> {
+        match statement {
+            ast::Statement::Assignment {
+                var_name,
+                expression,
+                ..
+            } => self.add(var_name, LiteralRange::of(expression)),
+            ast::Statement::Print(expressions) | ast::Statement::PrintErr(expressions) => {
+                for expression in &*expressions {
+                    self.contain(expression);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Loop variables get the element type of the array
+    fn define_loop_variable(
+        &mut self,
+        var: &mut String,
+        array: &ast::Expression,
+    ) -> crate::CompilerResult<()> {
+        self.add(var, None);
+        self.contain(array);
+        Ok(())
+    }
+}
@@ -1,18 +1,242 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{ast, types::TypeInformation, SourceLocation};
+use super::definition_analyzer::{DefinitionAnalyzer, FunctionSignature};
+use super::small_int_analyzer::SmallIntAnalyzer;
+use crate::{
+    ast,
+    builtins::{Builtin, Signature},
+    diagnostics::{self, CompilerWarning, ErrorCode},
+    types::{Ownership, TypeInformation},
+    SourceLocation,
+};
+
+/// The most elements `range` can make, they are all stored as a constant array
+const MAX_RANGE_SIZE: i64 = 1 << 16;
+
+/// The variables visible at the current point of a function, with the innermost block last.
+/// Each scope maps the name used in the code to the name the variable is stored under,
+/// a shadowing variable gets a new name so it does not clash with the variable it hides.
+struct ScopeStack(Vec<HashMap<String, String>>);
+
+impl ScopeStack {
+    fn new() -> Self {
+        Self(vec![HashMap::new()])
+    }
+
+    fn push(&mut self) {
+        self.0.push(HashMap::new());
+    }
+
+    fn pop(&mut self) -> HashMap<String, String> {
+        self.0.pop().unwrap()
+    }
+
+    fn clear(&mut self) {
+        self.0 = vec![HashMap::new()];
+    }
+
+    /// Finds the stored name of a variable, searching from the innermost scope outward
+    fn lookup(&self, name: &str) -> Option<&String> {
+        self.0.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// How many blocks deep the variable stored under `stored_name` is defined
+    fn depth_of(&self, stored_name: &str) -> Option<usize> {
+        self.0
+            .iter()
+            .rposition(|scope| scope.values().any(|name| name == stored_name))
+    }
+
+    fn is_in_current_scope(&self, name: &str) -> bool {
+        self.0.last().unwrap().contains_key(name)
+    }
+
+    fn define(&mut self, name: String, stored_name: String) {
+        self.0.last_mut().unwrap().insert(name, stored_name);
+    }
+
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().flat_map(|scope| scope.keys().map(String::as_str))
+    }
+}
 
 pub struct TypeAnalyzer {
+    /// Types of every variable in the function, by stored name
     var_types: HashMap<String, TypeInformation>,
+    scopes: ScopeStack,
+    functions: HashMap<String, FunctionSignature>,
+    bit_structs: HashMap<String, TypeInformation>,
+    type_aliases: HashMap<String, TypeInformation>,
+    constants: HashMap<String, (TypeInformation, ast::LiteralType)>,
     return_type: TypeInformation,
+    /// The named return values of the function, returned by a bare `return`
+    named_returns: Vec<String>,
+    warn_shadowing: bool,
+    small_ints: bool,
+    /// Variables of the function stored in fewer bits, by the name used in the code
+    small_types: HashMap<String, TypeInformation>,
+    /// The String variable each `Str` variable points into, both by stored name
+    slice_sources: HashMap<String, String>,
+    pub warnings: Vec<CompilerWarning>,
 }
 
 impl TypeAnalyzer {
-    pub fn new() -> Self {
+    pub fn new(
+        functions: HashMap<String, FunctionSignature>,
+        bit_structs: HashMap<String, TypeInformation>,
+        type_aliases: HashMap<String, TypeInformation>,
+        constants: HashMap<String, (TypeInformation, ast::LiteralType)>,
+        warn_shadowing: bool,
+        small_ints: bool,
+    ) -> Self {
         Self {
             var_types: HashMap::new(),
+            scopes: ScopeStack::new(),
+            functions,
+            bit_structs,
+            type_aliases,
+            constants,
             return_type: TypeInformation::Number, // Temp value,
+            named_returns: Vec::new(),
+            warn_shadowing,
+            small_ints,
+            small_types: HashMap::new(),
+            slice_sources: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Name to store a new variable under, different from every variable already in the function
+    fn new_stored_name(&self, name: &str) -> String {
+        if !self.var_types.contains_key(name) {
+            return name.to_string();
+        }
+
+        // `#` can not be part of an identifier, so this never clashes with a name in the code
+        (1..)
+            .map(|n| format!("{name}#{n}"))
+            .find(|stored_name| !self.var_types.contains_key(stored_name))
+            .unwrap()
+    }
+
+    /// Void is only allowed where the value is thrown away
+    fn expect_value(expression: &ast::Expression) -> crate::CompilerResult<()> {
+        if *expression.type_info() == TypeInformation::Void {
+            return Err((
+                *expression.location(),
+                ErrorCode::TypeMismatch.tag("Expected a value, got Void"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The String variable a `Str` expression points into, by stored name
+    fn slice_source(&self, expression: &ast::Expression) -> Option<String> {
+        match expression {
+            ast::Expression::FunctionCall {
+                name, arguments, ..
+            } if !self.functions.contains_key(name)
+                && Builtin::from_name(name) == Some(Builtin::Substr) =>
+            {
+                match arguments.first() {
+                    Some(ast::Expression::Var(_, source))
+                        if self.var_types.contains_key(source) =>
+                    {
+                        Some(source.clone())
+                    }
+                    _ => None,
+                }
+            }
+            ast::Expression::Var(_, name) => self.slice_sources.get(name).cloned(),
+            _ => None,
+        }
+    }
+
+    fn analyze_assignment(
+        &mut self,
+        location: SourceLocation,
+        var_name: &mut String,
+        expression: &mut ast::Expression,
+    ) -> crate::CompilerResult<()> {
+        if let Some(small_type) = self.small_types.get(var_name.as_str()) {
+            narrow_literals(expression, small_type);
+        }
+
+        TypeAnalyzer::expect_value(expression)?;
+        let expression_type = expression.type_info().clone();
+        let existing = self
+            .scopes
+            .lookup(var_name)
+            .map(|stored_name| (stored_name.clone(), self.var_types[stored_name].clone()));
+
+        match existing {
+            Some((stored_name, type_)) if TypeInformation::same_type(&type_, &expression_type) => {
+                *var_name = stored_name;
+            }
+            Some((_, type_)) if self.scopes.is_in_current_scope(var_name) => {
+                return Err((
+                    *expression.location(),
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "expected {:?}, but got {:?}",
+                        type_, expression_type
+                    )),
+                ));
+            }
+            existing => {
+                // Assigning a new type to a variable from an outer block shadows it
+                if existing.is_some() && self.warn_shadowing {
+                    self.warnings.push(CompilerWarning::Shadowing {
+                        location,
+                        name: var_name.clone(),
+                    });
+                }
+
+                let stored_name = self.new_stored_name(var_name);
+                self.var_types
+                    .insert(stored_name.clone(), expression_type.mark_borrowed());
+                self.scopes.define(var_name.clone(), stored_name.clone());
+                *var_name = stored_name;
+            }
+        }
+
+        // Changing a String frees or moves the buffer its slices point into
+        if let Some(slice) = self
+            .slice_sources
+            .iter()
+            .find_map(|(slice, source)| (source == var_name).then_some(slice))
+        {
+            return Err((
+                location,
+                ErrorCode::TypeMismatch.tag(format!(
+                    "Can not assign to {} while the Str {} points into it",
+                    code_name(var_name),
+                    code_name(slice)
+                )),
+            ));
         }
+
+        match self.slice_source(expression) {
+            Some(source) => {
+                // The source is freed at the end of its block, the slice has to be gone by then
+                if self.scopes.depth_of(var_name) < self.scopes.depth_of(&source) {
+                    return Err((
+                        location,
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "The Str {} would outlive {}, which is freed at the end of its block",
+                            code_name(var_name),
+                            code_name(&source)
+                        )),
+                    ));
+                }
+                self.slice_sources.insert(var_name.clone(), source);
+            }
+            None => {
+                self.slice_sources.remove(var_name.as_str());
+            }
+        }
+
+        Ok(())
     }
 
     fn analyze_binary(
@@ -21,8 +245,8 @@ impl TypeAnalyzer {
         operator: ast::Operator,
         right_expression: &mut ast::Expression,
     ) -> crate::CompilerResult<()> {
-        let left_type = *left_expression.type_info();
-        let right_type = *right_expression.type_info();
+        let left_type = left_expression.type_info();
+        let right_type = right_expression.type_info();
 
         let source_location =
             SourceLocation::combine(left_expression.location(), right_expression.location());
@@ -30,10 +254,10 @@ impl TypeAnalyzer {
         if !TypeInformation::same_type(left_type, right_type) {
             return Err((
                 source_location,
-                format!(
+                ErrorCode::TypeMismatch.tag(format!(
                     "Expected left and right to have same type, got {:?} and {:?}",
                     left_type, right_type
-                ),
+                )),
             ));
         }
 
@@ -47,13 +271,32 @@ impl TypeAnalyzer {
             TypeInformation::Boolean => {
                 return Err((
                     source_location,
-                    format!("Unsupported operator for boolean {:?}", operator),
+                    ErrorCode::UnsupportedOperator.tag(format!(
+                        "Unsupported operator for boolean {:?}", operator
+                    )),
                 ))
             }
-            TypeInformation::String(_) => {
+            TypeInformation::String(_) | TypeInformation::StringSlice => {
+                return Err((
+                    source_location,
+                    ErrorCode::UnsupportedOperator.tag(format!(
+                        "Unsupported operator for String {:?}", operator
+                    )),
+                ))
+            }
+            TypeInformation::Number8
+            | TypeInformation::Number16
+            | TypeInformation::Pointer(_)
+            | TypeInformation::Array { .. }
+            | TypeInformation::Tuple(_)
+            | TypeInformation::BitStruct { .. }
+            | TypeInformation::Void
+            | TypeInformation::VarArgs => {
                 return Err((
                     source_location,
-                    format!("Unsupported operator for String {:?}", operator),
+                    ErrorCode::UnsupportedOperator.tag(format!(
+                        "Unsupported operator for {:?} {:?}", left_type, operator
+                    )),
                 ))
             }
         };
@@ -68,7 +311,7 @@ impl TypeAnalyzer {
         first: &ast::Expression,
         chains: &Vec<(ast::Comparison, ast::Expression)>,
     ) -> crate::CompilerResult<()> {
-        let type_ = *first.type_info();
+        let type_ = first.type_info();
 
         let valid_comparisons = match type_ {
             TypeInformation::Number => vec![
@@ -79,23 +322,40 @@ impl TypeAnalyzer {
                 ast::Comparison::LessThan,
                 ast::Comparison::LessThanEqual,
             ],
-            TypeInformation::Boolean => vec![],
-            TypeInformation::String(_) => vec![],
+            TypeInformation::Boolean => vec![ast::Comparison::Equal, ast::Comparison::NotEqual],
+            TypeInformation::String(_) => {
+                vec![ast::Comparison::Contains, ast::Comparison::NotContains]
+            }
+            TypeInformation::Number8
+            | TypeInformation::Number16
+            | TypeInformation::StringSlice
+            | TypeInformation::Pointer(_)
+            | TypeInformation::Array { .. }
+            | TypeInformation::Tuple(_)
+            | TypeInformation::BitStruct { .. }
+            | TypeInformation::Void
+            | TypeInformation::VarArgs => vec![],
         };
 
         for (comp, value) in chains {
-            let value_type = *value.type_info();
+            let value_type = value.type_info();
             if !TypeInformation::same_type(type_, value_type) {
                 return Err((
                     SourceLocation::combine(first.location(), value.location()),
-                    format!("Expected all expression in comparison chain to have same type, got {:?} and {:?}", type_, value_type)
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "Expected all expression in comparison chain to have same type, got {:?} and {:?}",
+                        type_,
+                        value_type,
+                    ))
                 ));
             }
 
             if !valid_comparisons.contains(comp) {
                 return Err((
                     metadata.location,
-                    format!("Not a valid comparison for {type_:?}, valid comps are {valid_comparisons:?}")
+                    ErrorCode::InvalidComparison.tag(format!(
+                        "Not a valid comparison for {type_:?}, valid comps are {valid_comparisons:?}"
+                    ))
                 ));
             }
         }
@@ -104,15 +364,660 @@ impl TypeAnalyzer {
 
         Ok(())
     }
+
+    fn analyze_array(
+        metadata: &mut ast::ExpressionMetadata,
+        elements: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        let Some(first) = elements.first() else {
+            return Err((
+                metadata.location,
+                ErrorCode::TypeMismatch.tag("Can not infer the element type of an empty array"),
+            ));
+        };
+
+        let element_type = first.type_info();
+        if !matches!(
+            element_type,
+            TypeInformation::Number | TypeInformation::Boolean | TypeInformation::Array { .. }
+        ) {
+            return Err((
+                *first.location(),
+                ErrorCode::TypeMismatch.tag(format!(
+                    "Arrays can only hold Num, Bool and array values, got {:?}", element_type
+                )),
+            ));
+        }
+
+        for element in elements {
+            if !TypeInformation::same_type(element_type, element.type_info()) {
+                return Err((
+                    *element.location(),
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "Expected all array elements to have the same type, got {:?} and {:?}",
+                        element_type,
+                        element.type_info()
+                    )),
+                ));
+            }
+        }
+
+        metadata.type_information = Some(TypeInformation::Array {
+            element: Box::new(element_type.clone()),
+            size: elements.len(),
+        });
+
+        Ok(())
+    }
+
+    fn analyze_tuple(
+        metadata: &mut ast::ExpressionMetadata,
+        elements: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        for element in elements {
+            TypeAnalyzer::expect_value(element)?;
+            // Nothing would free the string once it is inside the tuple
+            if let TypeInformation::String(_) = element.type_info() {
+                return Err((
+                    *element.location(),
+                    ErrorCode::TypeMismatch.tag("Tuples can not hold Strings"),
+                ));
+            }
+        }
+
+        metadata.type_information = Some(TypeInformation::Tuple(
+            elements.iter().map(|element| element.type_info().clone()).collect(),
+        ));
+
+        Ok(())
+    }
+
+    /// What a bare `return` returns in a function with named return values, the variables
+    /// themselves even when the body shadows them
+    fn named_return_value(
+        &self,
+        location: crate::SourceLocation,
+    ) -> crate::CompilerResult<ast::Expression> {
+        let mut values: Vec<ast::Expression> = self
+            .named_returns
+            .iter()
+            .map(|name| {
+                ast::Expression::Var(
+                    ast::ExpressionMetadata {
+                        location,
+                        type_information: Some(self.var_types[name].clone().widened()),
+                    },
+                    name.clone(),
+                )
+            })
+            .collect();
+        if values.len() == 1 {
+            return Ok(values.pop().unwrap());
+        }
+
+        let mut metadata = ast::ExpressionMetadata::from(location);
+        TypeAnalyzer::analyze_tuple(&mut metadata, &values)?;
+        Ok(ast::Expression::Tuple(metadata, values))
+    }
+
+    fn analyze_tuple_index(
+        metadata: &mut ast::ExpressionMetadata,
+        tuple: &ast::Expression,
+        index: usize,
+    ) -> crate::CompilerResult<()> {
+        let TypeInformation::Tuple(elements) = tuple.type_info() else {
+            return Err((
+                *tuple.location(),
+                ErrorCode::TypeMismatch.tag(format!(
+                    "Expected a tuple, got {:?}",
+                    tuple.type_info()
+                )),
+            ));
+        };
+
+        let Some(element) = elements.get(index) else {
+            return Err((
+                metadata.location,
+                ErrorCode::TypeMismatch.tag(format!(
+                    "Tuple {:?} has no element {}",
+                    tuple.type_info(),
+                    index
+                )),
+            ));
+        };
+
+        metadata.type_information = Some(element.clone());
+
+        Ok(())
+    }
+
+    fn analyze_len(
+        metadata: &mut ast::ExpressionMetadata,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        let [array] = arguments else {
+            return Err((
+                metadata.location,
+                ErrorCode::WrongArgumentCount.tag(format!(
+                    "len expects 1 arguments, got {}",
+                    arguments.len()
+                )),
+            ));
+        };
+
+        if !matches!(
+            array.type_info(),
+            TypeInformation::Array { .. } | TypeInformation::String(_) | TypeInformation::VarArgs
+        ) {
+            return Err((
+                *array.location(),
+                ErrorCode::TypeMismatch.tag(format!(
+                    "expected an array, a String or a rest parameter, got {:?}",
+                    array.type_info()
+                )),
+            ));
+        }
+
+        metadata.type_information = Some(TypeInformation::Number);
+
+        Ok(())
+    }
+
+    /// The offset and width of `get_bits` and `set_bits` have to fit in a Num when they are
+    /// literals, other values are clamped when the code runs
+    fn check_bit_range(arguments: &[ast::Expression]) -> crate::CompilerResult<()> {
+        let out_of_range = |argument: &ast::Expression, message: String| {
+            Err((*argument.location(), ErrorCode::TypeMismatch.tag(message)))
+        };
+
+        let offset = arguments.get(1).map(|offset| (offset, offset.number_literal()));
+        if let Some((argument, Some(offset))) = offset {
+            if !(0..32).contains(&offset) {
+                return out_of_range(argument, format!("bit offset {offset} is not in 0 to 31"));
+            }
+        }
+        let width = arguments.get(2).map(|width| (width, width.number_literal()));
+        if let Some((argument, Some(width))) = width {
+            if !(0..=32).contains(&width) {
+                return out_of_range(argument, format!("bit width {width} is not in 0 to 32"));
+            }
+            if let Some((_, Some(offset))) = offset {
+                if offset + width > 32 {
+                    return out_of_range(
+                        argument,
+                        format!("{width} bits after bit {offset} do not fit in a Num"),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn analyze_range(
+        metadata: &mut ast::ExpressionMetadata,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        let [start, end] = arguments else {
+            return Err((
+                metadata.location,
+                ErrorCode::WrongArgumentCount.tag(format!(
+                    "range expects 2 arguments, got {}",
+                    arguments.len()
+                )),
+            ));
+        };
+
+        // The bounds have to be known at compile time to know the size of the array
+        let literal = |bound: &ast::Expression| {
+            bound.number_literal().ok_or((
+                *bound.location(),
+                ErrorCode::TypeMismatch.tag("expected the bounds of range as number literals"),
+            ))
+        };
+        let (start_value, end_value) = (literal(start)?, literal(end)?);
+        if end_value <= start_value {
+            return Err((
+                metadata.location,
+                ErrorCode::TypeMismatch.tag(format!(
+                    "range({start_value}, {end_value}) is empty, the end has to be after the start"
+                )),
+            ));
+        }
+        // Every element is a constant in the binary
+        if end_value - start_value > MAX_RANGE_SIZE {
+            return Err((
+                metadata.location,
+                ErrorCode::TypeMismatch.tag(format!(
+                    "range({start_value}, {end_value}) has {} elements, at most {MAX_RANGE_SIZE} \
+                     are allowed",
+                    end_value - start_value
+                )),
+            ));
+        }
+
+        metadata.type_information = Some(TypeInformation::Array {
+            element: Box::new(TypeInformation::Number),
+            size: usize::try_from(end_value - start_value).unwrap(),
+        });
+
+        Ok(())
+    }
+
+    fn analyze_typeof_str(
+        metadata: &mut ast::ExpressionMetadata,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        if arguments.len() != 1 {
+            return Err((
+                metadata.location,
+                ErrorCode::WrongArgumentCount.tag(format!(
+                    "typeof_str expects 1 arguments, got {}",
+                    arguments.len()
+                )),
+            ));
+        }
+
+        metadata.type_information = Some(TypeInformation::String(Ownership::Borrowed));
+
+        Ok(())
+    }
+
+    fn analyze_format(
+        metadata: &mut ast::ExpressionMetadata,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        let Some((format_string, values)) = arguments.split_first() else {
+            return Err((
+                metadata.location,
+                ErrorCode::WrongArgumentCount.tag("format expects at least 1 argument, got 0"),
+            ));
+        };
+
+        if !matches!(format_string.type_info(), TypeInformation::String(_)) {
+            return Err((
+                *format_string.location(),
+                ErrorCode::TypeMismatch.tag(format!(
+                    "expected a String format, got {:?}",
+                    format_string.type_info()
+                )),
+            ));
+        }
+
+        for value in values {
+            if !matches!(
+                value.type_info(),
+                TypeInformation::Number | TypeInformation::Boolean | TypeInformation::String(_)
+            ) {
+                return Err((
+                    *value.location(),
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "format can only take Num, Bool and String values, got {:?}",
+                        value.type_info()
+                    )),
+                ));
+            }
+        }
+
+        // printf reads whatever the specifier asks for, so a mismatch would read garbage
+        if let ast::Expression::Literal(_, ast::LiteralType::String(format)) = format_string {
+            let conversions = format_conversions(format).map_err(|message| {
+                (*format_string.location(), ErrorCode::TypeMismatch.tag(message))
+            })?;
+            if conversions.len() != values.len() {
+                return Err((
+                    metadata.location,
+                    ErrorCode::WrongArgumentCount.tag(format!(
+                        "the format string takes {} values, got {}",
+                        conversions.len(),
+                        values.len()
+                    )),
+                ));
+            }
+            for (conversion, value) in conversions.into_iter().zip(values) {
+                let matches = match conversion {
+                    's' => matches!(value.type_info(), TypeInformation::String(_)),
+                    _ => !matches!(value.type_info(), TypeInformation::String(_)),
+                };
+                if !matches {
+                    let expected = if conversion == 's' { "a String" } else { "a Num or Bool" };
+                    return Err((
+                        *value.location(),
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "%{conversion} expects {expected}, got {:?}",
+                            value.type_info()
+                        )),
+                    ));
+                }
+            }
+        }
+
+        metadata.type_information = Some(TypeInformation::String(Ownership::Owned));
+
+        Ok(())
+    }
+
+    /// `get_field(value, "field")` and `set_field(value, "field", new_value)`
+    fn analyze_bit_field_access(
+        metadata: &mut ast::ExpressionMetadata,
+        builtin: Builtin,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        let (name, expected_arguments) = match builtin {
+            Builtin::SetField => ("set_field", 3),
+            _ => ("get_field", 2),
+        };
+        if arguments.len() != expected_arguments {
+            return Err((
+                metadata.location,
+                ErrorCode::WrongArgumentCount.tag(format!(
+                    "{} expects {} arguments, got {}",
+                    name,
+                    expected_arguments,
+                    arguments.len()
+                )),
+            ));
+        }
+
+        let struct_type = arguments[0].type_info();
+        let TypeInformation::BitStruct {
+            name: struct_name,
+            fields,
+        } = struct_type
+        else {
+            return Err((
+                *arguments[0].location(),
+                ErrorCode::TypeMismatch.tag(format!(
+                    "expected a bit struct, got {:?}",
+                    struct_type
+                )),
+            ));
+        };
+
+        // The field has to be known at compile time to know which bits to use
+        let ast::Expression::Literal(_, ast::LiteralType::String(field_name)) = &arguments[1]
+        else {
+            return Err((
+                *arguments[1].location(),
+                ErrorCode::TypeMismatch.tag("expected the field name as a string literal"),
+            ));
+        };
+        let Some((_, field_type, _)) = struct_type.bit_field(field_name) else {
+            return Err((
+                *arguments[1].location(),
+                ErrorCode::TypeMismatch.tag(diagnostics::with_suggestion(
+                    format!("Bit struct {struct_name} has no field {field_name}"),
+                    field_name,
+                    fields.iter().map(|(name, _, _)| name.as_str()),
+                )),
+            ));
+        };
+
+        let result_type = if builtin == Builtin::SetField {
+            let value_type = arguments[2].type_info();
+            if !TypeInformation::same_type(field_type, value_type) {
+                return Err((
+                    *arguments[2].location(),
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "expected {:?}, got {:?}",
+                        field_type, value_type
+                    )),
+                ));
+            }
+            struct_type.clone()
+        } else {
+            field_type.clone()
+        };
+        metadata.type_information = Some(result_type);
+
+        Ok(())
+    }
+
+    /// The builtins with a `Custom` signature
+    fn analyze_custom_builtin(
+        metadata: &mut ast::ExpressionMetadata,
+        builtin: Builtin,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        match builtin {
+            Builtin::Len => TypeAnalyzer::analyze_len(metadata, arguments),
+            Builtin::TypeofStr => TypeAnalyzer::analyze_typeof_str(metadata, arguments),
+            Builtin::Range => TypeAnalyzer::analyze_range(metadata, arguments),
+            Builtin::Format => TypeAnalyzer::analyze_format(metadata, arguments),
+            Builtin::GetField | Builtin::SetField => {
+                TypeAnalyzer::analyze_bit_field_access(metadata, builtin, arguments)
+            }
+            _ => Err((
+                metadata.location,
+                ErrorCode::InternalError.tag(format!("{builtin:?} has no type check of its own")),
+            )),
+        }
+    }
+
+    /// What the `Fixed` signature of a builtin can not express, checked before the types
+    fn check_builtin_arguments(
+        builtin: Builtin,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        match builtin {
+            Builtin::GetBits | Builtin::SetBits => TypeAnalyzer::check_bit_range(arguments),
+            // The slice points into the string, and a temporary one is gone after the call
+            Builtin::Substr => match arguments
+                .first()
+                .filter(|string| *string.type_info() == TypeInformation::String(Ownership::Owned))
+            {
+                Some(string) => Err((
+                    *string.location(),
+                    ErrorCode::TypeMismatch.tag(
+                        "Can not slice a temporary String, store it in a variable first",
+                    ),
+                )),
+                None => Ok(()),
+            },
+            // Other strings can be constants or temporaries
+            Builtin::SetChar => match arguments
+                .first()
+                .filter(|string| !matches!(string, ast::Expression::Var(..)))
+            {
+                Some(string) => Err((
+                    *string.location(),
+                    ErrorCode::TypeMismatch.tag("set_char can only change a String variable"),
+                )),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn analyze_condition(condition: &ast::Expression) -> crate::CompilerResult<()> {
+        let condition_type = condition.type_info();
+        if TypeInformation::same_type(condition_type, &TypeInformation::Boolean) {
+            return Ok(());
+        }
+
+        // Point C programmers at the comparison they most likely meant
+        let name = match condition {
+            ast::Expression::Var(_, name) => code_name(name),
+            _ => "x",
+        };
+        let message = match condition_type {
+            TypeInformation::Number => {
+                format!("Expected condition to be Bool, got Num, did you mean `{name} != 0`?")
+            }
+            TypeInformation::String(_) => format!(
+                "Expected condition to be Bool, got String, did you mean `len({name}) != 0`?"
+            ),
+            _ => format!("Expected condition to be Bool, got {condition_type:?}"),
+        };
+
+        Err((
+            *condition.location(),
+            ErrorCode::NonBooleanCondition.tag(message),
+        ))
+    }
+
+    fn analyze_function_call(
+        &mut self,
+        metadata: &mut ast::ExpressionMetadata,
+        name: &str,
+        arguments: &[ast::Expression],
+    ) -> crate::CompilerResult<()> {
+        // Functions defined in the code take priority over builtins with the same name
+        let (parameters, return_type, is_variadic) = if let Some(signature) =
+            self.functions.get(name)
+        {
+            (
+                signature.parameters.clone(),
+                signature.return_type.clone(),
+                signature.is_variadic,
+            )
+        } else if let Some(builtin) = Builtin::from_name(name) {
+            let Signature::Fixed(parameters, return_type) = builtin.signature() else {
+                return TypeAnalyzer::analyze_custom_builtin(metadata, builtin, arguments);
+            };
+            TypeAnalyzer::check_builtin_arguments(builtin, arguments)?;
+            (parameters, return_type, false)
+        } else {
+            return Err((
+                metadata.location,
+                ErrorCode::UndefinedFunction.tag(diagnostics::with_suggestion(
+                    format!("Function {} not defined", name),
+                    name,
+                    Builtin::names().chain(self.functions.keys().map(String::as_str)),
+                )),
+            ));
+        };
+
+        let too_few = arguments.len() < parameters.len();
+        if too_few || (!is_variadic && arguments.len() != parameters.len()) {
+            let expected = if is_variadic {
+                format!("at least {}", parameters.len())
+            } else {
+                parameters.len().to_string()
+            };
+            return Err((
+                metadata.location,
+                ErrorCode::WrongArgumentCount.tag(format!(
+                    "{} expects {} arguments, got {}",
+                    name,
+                    expected,
+                    arguments.len()
+                )),
+            ));
+        }
+
+        for (parameter, argument) in parameters.into_iter().zip(arguments) {
+            let argument_type = argument.type_info();
+            if !TypeInformation::same_type(&parameter, argument_type) {
+                return Err((
+                    *argument.location(),
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "expected {:?}, got {:?}", parameter, argument_type
+                    )),
+                ));
+            }
+        }
+
+        // Rest arguments are passed as they are, the function reads them as Num
+        for argument in &arguments[parameters.len()..] {
+            TypeAnalyzer::expect_value(argument)?;
+            let argument_type = argument.type_info();
+            if !matches!(argument_type, TypeInformation::Number | TypeInformation::Boolean) {
+                self.warnings.push(CompilerWarning::VariadicUnsafe {
+                    location: *argument.location(),
+                    name: name.to_string(),
+                    type_name: argument_type.name(),
+                });
+            }
+        }
+
+        metadata.type_information = Some(return_type);
+
+        Ok(())
+    }
+}
+
+/// The name a variable has in the code, without the suffix a shadowing variable is stored with
+fn code_name(stored_name: &str) -> &str {
+    stored_name.split('#').next().unwrap()
+}
+
+/// The conversion characters of the `printf` specifiers in a format string, in order
+///
+/// Only the conversions for 32 bit numbers and strings are allowed, with flags, a width and
+/// a precision. `%%` takes no value so it is not included.
+fn format_conversions(format: &str) -> Result<Vec<char>, String> {
+    let mut conversions = Vec::new();
+    let mut chars = format.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            continue;
+        }
+
+        while chars.next_if(|char| "-+ #0".contains(*char)).is_some() {}
+        while chars.next_if(char::is_ascii_digit).is_some() {}
+        if chars.next_if_eq(&'.').is_some() {
+            while chars.next_if(char::is_ascii_digit).is_some() {}
+        }
+
+        match chars.next() {
+            Some('%') => {}
+            Some(conversion @ ('d' | 'i' | 'u' | 'x' | 'X' | 'o' | 'c' | 's')) => {
+                conversions.push(conversion);
+            }
+            Some(other) => return Err(format!("unsupported format specifier %{other}")),
+            None => return Err("the format string ends in the middle of a specifier".to_string()),
+        }
+    }
+
+    Ok(conversions)
+}
+
+fn narrow_literals(expression: &mut ast::Expression, type_: &TypeInformation) {
+    let metadata = match expression {
+        ast::Expression::Array(metadata, elements) => {
+            if let TypeInformation::Array { element, .. } = type_ {
+                for value in elements {
+                    narrow_literals(value, element);
+                }
+            }
+            metadata
+        }
+        ast::Expression::PrefixExpression {
+            expression,
+            metadata,
+            ..
+        } => {
+            narrow_literals(expression, type_);
+            metadata
+        }
+        ast::Expression::Literal(metadata, _) => metadata,
+        _ => return,
+    };
+    metadata.type_information = Some(type_.clone());
 }
 
 impl super::Analyzer for TypeAnalyzer {
     fn visit_expression(&mut self, expr: &mut crate::ast::Expression) -> crate::CompilerResult<()> {
+        // Constants are folded into their value
+        if let ast::Expression::Var(metadata, name) = expr {
+            if let Some((type_, value)) = self.constants.get(name) {
+                *expr = ast::Expression::Literal(
+                    ast::ExpressionMetadata {
+                        location: metadata.location,
+                        type_information: Some(type_.clone()),
+                    },
+                    value.clone(),
+                );
+                return Ok(());
+            }
+        }
+
         match expr {
             ast::Expression::Literal(metadata, literal) => {
                 metadata.type_information = Some(match literal {
                     ast::LiteralType::Number(_) => TypeInformation::Number,
-                    ast::LiteralType::String(_) => TypeInformation::String(false),
+                    ast::LiteralType::String(_) => TypeInformation::String(Ownership::Borrowed),
                     ast::LiteralType::Boolean(_) => TypeInformation::Boolean,
                 });
             }
@@ -129,9 +1034,54 @@ impl super::Analyzer for TypeAnalyzer {
                 comparisons,
                 metadata,
             } => TypeAnalyzer::analyze_comparison(metadata, first_element, comparisons)?,
-            ast::Expression::Var(metadata, var_name) => match self.var_types.get(var_name) {
-                Some(type_) => metadata.type_information = Some(*type_),
-                None => return Err((metadata.location, format!("Name {} not defined", var_name))),
+            ast::Expression::FunctionCall {
+                name,
+                arguments,
+                metadata,
+                ..
+            } => {
+                self.analyze_function_call(metadata, name, arguments)?;
+                let deprecated = self
+                    .functions
+                    .get(name)
+                    .and_then(|signature| signature.deprecated.clone());
+                if let Some(message) = deprecated {
+                    self.warnings.push(CompilerWarning::DeprecatedFunction {
+                        location: metadata.location,
+                        name: name.clone(),
+                        message,
+                    });
+                }
+            }
+            ast::Expression::Array(metadata, elements) => {
+                TypeAnalyzer::analyze_array(metadata, elements)?;
+            }
+            ast::Expression::Tuple(metadata, elements) => {
+                TypeAnalyzer::analyze_tuple(metadata, elements)?;
+            }
+            ast::Expression::TupleIndex {
+                tuple,
+                index,
+                metadata,
+            } => TypeAnalyzer::analyze_tuple_index(metadata, tuple, *index)?,
+            ast::Expression::Var(metadata, var_name) => match self.scopes.lookup(var_name) {
+                Some(stored_name) => {
+                    metadata.type_information =
+                        Some(self.var_types[stored_name].clone().widened());
+                    *var_name = stored_name.clone();
+                }
+                None => {
+                    return Err((
+                        metadata.location,
+                        ErrorCode::UndefinedVariable.tag(diagnostics::with_suggestion(
+                            format!("Name {} not defined", var_name),
+                            var_name,
+                            self.scopes
+                                .names()
+                                .chain(self.constants.keys().map(String::as_str)),
+                        )),
+                    ))
+                }
             },
             ast::Expression::PrefixExpression {
                 op,
@@ -140,13 +1090,35 @@ impl super::Analyzer for TypeAnalyzer {
             } => {
                 let type_ = match (op, expression.type_info()) {
                     (ast::PrefixOprator::Not, TypeInformation::Boolean) => TypeInformation::Boolean,
+                    (ast::PrefixOprator::Negate, TypeInformation::Number) => TypeInformation::Number,
+                    // Nothing would free the string once it is only reachable through the pointer
+                    (ast::PrefixOprator::AddressOf, TypeInformation::String(Ownership::Owned)) => {
+                        return Err((
+                            *expression.location(),
+                            ErrorCode::UnsupportedOperator.tag(
+                                "Can not take the address of a temporary String, \
+                                 store it in a variable first",
+                            ),
+                        ))
+                    }
+                    (ast::PrefixOprator::AddressOf, type_) if *type_ != TypeInformation::Void => {
+                        // Points at how the variable is stored, not the widened value
+                        let type_ = match expression.as_ref() {
+                            ast::Expression::Var(_, name) => &self.var_types[name],
+                            _ => type_,
+                        };
+                        TypeInformation::Pointer(Box::new(type_.clone()))
+                    }
+                    (ast::PrefixOprator::Deref, TypeInformation::Pointer(type_)) => {
+                        type_.as_ref().clone().widened()
+                    }
                     _ => {
                         return Err((
                             *expression.location(),
-                            format!(
+                            ErrorCode::UnsupportedOperator.tag(format!(
                                 "Invalid prefix operator for {:?}",
                                 expression.type_info()
-                            ),
+                            )),
                         ))
                     }
                 };
@@ -159,58 +1131,202 @@ impl super::Analyzer for TypeAnalyzer {
 
     fn visit_stmt(&mut self, stmt: &mut ast::Statement) -> crate::CompilerResult<()> {
         match stmt {
-            ast::Statement::Print(_) => {}
-            ast::Statement::Assert(expr) | ast::Statement::Test(_, expr) => {
-                let expr_type = *expr.type_info();
-                if expr_type != TypeInformation::Boolean {
+            ast::Statement::Print(expressions) | ast::Statement::PrintErr(expressions) => {
+                for expression in expressions {
+                    TypeAnalyzer::expect_value(expression)?;
+                    if let TypeInformation::Tuple(_) = expression.type_info() {
+                        return Err((
+                            *expression.location(),
+                            ErrorCode::UnsupportedOperator.tag("Tuples can not be printed"),
+                        ));
+                    }
+                    if let TypeInformation::VarArgs = expression.type_info() {
+                        return Err((
+                            *expression.location(),
+                            ErrorCode::UnsupportedOperator.tag(
+                                "The rest parameter can not be printed, loop over it instead",
+                            ),
+                        ));
+                    }
+                    if let TypeInformation::BitStruct { .. } = expression.type_info() {
+                        return Err((
+                            *expression.location(),
+                            ErrorCode::UnsupportedOperator.tag(
+                                "Bit structs can not be printed, print the fields with get_field",
+                            ),
+                        ));
+                    }
+                }
+            }
+            ast::Statement::Discard(_) => {}
+            ast::Statement::Assert(expr, _) | ast::Statement::Test(_, expr) => {
+                let expr_type = expr.type_info();
+                if *expr_type != TypeInformation::Boolean {
                     return Err((
                         *expr.location(),
-                        format!("Expected Boolean, got {:?}", expr_type),
+                        ErrorCode::NonBooleanCondition.tag(format!(
+                            "Expected Boolean, got {:?}", expr_type
+                        )),
                     ));
                 }
             }
             ast::Statement::Assignment {
+                expression_location,
                 var_name,
                 expression,
+            } => self.analyze_assignment(*expression_location, var_name, expression)?,
+            ast::Statement::Return(return_expression, location) => {
+                if return_expression.is_none() && !self.named_returns.is_empty() {
+                    return_expression.replace(self.named_return_value(*location)?);
+                }
+                let (returned_type, location) = match return_expression {
+                    Some(expression) => (expression.type_info(), *expression.location()),
+                    None => (&TypeInformation::Void, *location),
+                };
+                // Borrowed Strings are copied when they are returned
+                if !TypeInformation::same_type(&self.return_type, returned_type) {
+                    return Err((
+                        location,
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "expected {:?}, got {:?}",
+                            self.return_type, returned_type
+                        )),
+                    ));
+                }
+                // Slices are not copied, and every String variable is freed on return
+                if let Some(source) = return_expression
+                    .as_ref()
+                    .and_then(|expression| self.slice_source(expression))
+                {
+                    let message = format!(
+                        "Can not return a Str pointing into {}, it is freed on return",
+                        code_name(&source)
+                    );
+                    return Err((location, ErrorCode::TypeMismatch.tag(message)));
+                }
+            }
+            ast::Statement::If {
+                condition,
+                else_if_chains,
                 ..
-            } => match self.var_types.get(var_name) {
-                None => {
-                    let type_ = expression.type_info();
-                    let type_ = type_.mark_borrowed();
-                    self.var_types.insert(var_name.clone(), type_);
+            } => {
+                let conditions = std::iter::once(&*condition)
+                    .chain(else_if_chains.iter().map(|(condition, _)| condition));
+                for condition in conditions {
+                    TypeAnalyzer::analyze_condition(condition)?;
+                }
+            }
+            ast::Statement::Switch {
+                scrutinee, arms, ..
+            } => {
+                let scrutinee_type = scrutinee.type_info();
+                if !TypeInformation::same_type(scrutinee_type, &TypeInformation::Number) {
+                    return Err((
+                        *scrutinee.location(),
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "Expected the switch value to be Num, got {:?}",
+                            scrutinee_type
+                        )),
+                    ));
                 }
-                Some(expected_type) => {
-                    let expression_type = *expression.type_info();
 
-                    if expression_type != *expected_type {
+                let mut seen = HashSet::new();
+                for (label, location, _) in arms {
+                    let ast::LiteralType::Number(value) = label else {
                         return Err((
-                            *expression.location(),
-                            format!(
-                                "expected {:?}, but got {:?}",
-                                expected_type, expression_type
-                            ),
+                            *location,
+                            ErrorCode::TypeMismatch.tag("Case labels have to be numbers"),
+                        ));
+                    };
+                    if !seen.insert(*value) {
+                        return Err((
+                            *location,
+                            ErrorCode::DuplicateCase
+                                .tag(format!("Case {value} is already handled")),
                         ));
                     }
                 }
-            },
-            ast::Statement::Return(return_expression) => {
-                if self.return_type != *return_expression.type_info() {
+            }
+            ast::Statement::Repeat { count, .. } => {
+                let count_type = count.type_info();
+                if !TypeInformation::same_type(count_type, &TypeInformation::Number) {
                     return Err((
-                        *return_expression.location(),
-                        format!(
-                            "expected {:?}, got {:?}",
-                            self.return_type,
-                            return_expression.type_info()
-                        ),
+                        *count.location(),
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "Expected the repeat count to be Num, got {:?}",
+                            count_type
+                        )),
                     ));
                 }
             }
-            ast::Statement::If { condition, .. } => {
-                let condition_type = *condition.type_info();
-                if !TypeInformation::same_type(condition_type, TypeInformation::Boolean) {
+            ast::Statement::Bench { iterations, .. } => {
+                let iterations_type = iterations.type_info();
+                if !TypeInformation::same_type(iterations_type, &TypeInformation::Number) {
                     return Err((
-                        *condition.location(),
-                        format!("Expected condition to be bool, got {:?}", condition_type),
+                        *iterations.location(),
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "Expected the bench iterations to be Num, got {:?}",
+                            iterations_type
+                        )),
+                    ));
+                }
+            }
+            ast::Statement::While { condition, .. }
+            | ast::Statement::WhileAssign { condition, .. }
+            | ast::Statement::DoWhile { condition, .. }
+            | ast::Statement::CStyleFor { condition, .. } => {
+                TypeAnalyzer::analyze_condition(condition)?;
+            }
+            ast::Statement::Break
+            | ast::Statement::ForEach { .. }
+            | ast::Statement::Defer { .. } => {}
+            ast::Statement::AssertEq {
+                expected, actual, ..
+            } => {
+                let expected_type = expected.type_info();
+                let actual_type = actual.type_info();
+                if !TypeInformation::same_type(expected_type, actual_type) {
+                    return Err((
+                        SourceLocation::combine(expected.location(), actual.location()),
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "assert_eq! needs both values to have the same type, got {:?} and {:?}",
+                            expected_type, actual_type
+                        )),
+                    ));
+                }
+                if !matches!(
+                    expected_type,
+                    TypeInformation::Number | TypeInformation::Boolean | TypeInformation::String(_)
+                ) {
+                    return Err((
+                        *expected.location(),
+                        ErrorCode::InvalidComparison.tag(format!(
+                            "assert_eq! only compares Num, Bool and String, got {expected_type:?}"
+                        )),
+                    ));
+                }
+            }
+            ast::Statement::AssertType {
+                expression,
+                expected_type,
+                location,
+            } => {
+                let Some(expected_type) = DefinitionAnalyzer::get_type(
+                    expected_type,
+                    &self.bit_structs,
+                    &self.type_aliases,
+                ) else {
+                    return Err((*location, ErrorCode::InvalidType.tag("Invalid type name")));
+                };
+
+                let expression_type = expression.type_info();
+                if !TypeInformation::same_type(&expected_type, expression_type) {
+                    return Err((
+                        *expression.location(),
+                        ErrorCode::TypeMismatch.tag(format!(
+                            "expected {:?}, got {:?}",
+                            expected_type, expression_type
+                        )),
                     ));
                 }
             }
@@ -225,23 +1341,110 @@ impl super::Analyzer for TypeAnalyzer {
     ) -> crate::CompilerResult<()> {
         // Clear/Setup function context
         match statement {
-            ast::TopLevelStatement::FunctionDefinition { metadata, .. } => {
+            ast::TopLevelStatement::FunctionDefinition {
+                parameters,
+                named_returns,
+                metadata,
+                body,
+                ..
+            } => {
+                self.var_types.clear();
+                self.scopes.clear();
+                self.slice_sources.clear();
+                self.small_types = if self.small_ints {
+                    // Named return values are declared with a type, like parameters
+                    let declared: Vec<_> =
+                        parameters.iter().chain(&*named_returns).cloned().collect();
+                    SmallIntAnalyzer::small_types(body, &declared)
+                } else {
+                    HashMap::new()
+                };
+
+                // Parameters are borrowed from the caller
+                for (parameter, type_) in parameters.iter().zip(&metadata.parameter_types) {
+                    self.var_types
+                        .insert(parameter.name.clone(), type_.clone().mark_borrowed());
+                    self.scopes.define(parameter.name.clone(), parameter.name.clone());
+                }
+                self.named_returns.clear();
+                for (name, type_) in &metadata.named_returns {
+                    self.var_types.insert(name.clone(), type_.clone().mark_borrowed());
+                    self.scopes.define(name.clone(), name.clone());
+                    self.named_returns.push(name.clone());
+                }
+
+                self.return_type = metadata.return_type.clone().unwrap();
+            }
+            ast::TopLevelStatement::TestFunction { metadata, body, .. } => {
                 self.var_types.clear();
-                self.return_type = metadata.return_type.unwrap();
+                self.scopes.clear();
+                self.slice_sources.clear();
+                self.named_returns.clear();
+                self.small_types = if self.small_ints {
+                    SmallIntAnalyzer::small_types(body, &[])
+                } else {
+                    HashMap::new()
+                };
+                self.return_type = metadata.return_type.clone().unwrap();
             }
+            ast::TopLevelStatement::ExternFunction { .. }
+            | ast::TopLevelStatement::BitStruct { .. }
+            | ast::TopLevelStatement::TypeAlias { .. } => {}
         }
 
         Ok(())
     }
 
+    fn define_loop_variable(
+        &mut self,
+        var: &mut String,
+        array: &ast::Expression,
+    ) -> crate::CompilerResult<()> {
+        let element = match array.type_info() {
+            TypeInformation::Array { element, .. } => element.as_ref().clone(),
+            TypeInformation::VarArgs => TypeInformation::Number,
+            _ => {
+                return Err((
+                    *array.location(),
+                    ErrorCode::TypeMismatch.tag(format!(
+                        "Expected an array to loop over, got {:?}",
+                        array.type_info()
+                    )),
+                ))
+            }
+        };
+
+        let stored_name = self.new_stored_name(var);
+        self.var_types.insert(stored_name.clone(), element);
+        self.scopes.define(var.clone(), stored_name.clone());
+        *var = stored_name;
+
+        Ok(())
+    }
+
+    fn enter_codebody(&mut self) {
+        self.scopes.push();
+    }
+
+    fn exit_codebody(&mut self) {
+        // Slices stored in the block are gone with it
+        let scope = self.scopes.pop();
+        self.slice_sources
+            .retain(|slice, _| !scope.values().any(|name| name == slice));
+    }
+
     fn visit_toplevel(
         &mut self,
         statement: &mut ast::TopLevelStatement,
     ) -> crate::CompilerResult<()> {
         match statement {
-            ast::TopLevelStatement::FunctionDefinition { metadata, .. } => {
+            ast::TopLevelStatement::FunctionDefinition { metadata, .. }
+            | ast::TopLevelStatement::TestFunction { metadata, .. } => {
                 metadata.var_types = self.var_types.clone();
             }
+            ast::TopLevelStatement::ExternFunction { .. }
+            | ast::TopLevelStatement::BitStruct { .. }
+            | ast::TopLevelStatement::TypeAlias { .. } => {}
         }
 
         Ok(())
@@ -9,17 +9,92 @@ pub struct File(pub Vec<TopLevelStatement>);
 pub enum TopLevelStatement {
     FunctionDefinition {
         function_name: String,
+        function_name_location: SourceLocation,
+        /// Public functions are exported from the resulting object file
+        is_public: bool,
+        parameters: Vec<Parameter>,
+        /// `-> (quot: Num, rem: Num)`, declared as variables and returned by a bare `return`
+        named_returns: Vec<Parameter>,
         body: CodeBody,
+        /// The `}` closing the body
+        body_end_location: SourceLocation,
+        return_type_name: String,
+        return_type_location: SourceLocation,
+        metadata: FunctionMetadata,
+    },
+    /// `#[test] fn name() { ... }`, only compiled and run by `viv test`
+    TestFunction {
+        name: String,
+        name_location: SourceLocation,
+        body: CodeBody,
+        metadata: FunctionMetadata,
+    },
+    /// `struct Flags { ready: Bool[1], count: Num[7] }`, fields packed into a single integer.
+    /// Calling `Flags()` creates one with every bit set to 0
+    BitStruct {
+        name: String,
+        name_location: SourceLocation,
+        fields: Vec<BitField>,
+    },
+    /// `type Name = Type;`, another name for an existing type
+    TypeAlias {
+        name: String,
+        name_location: SourceLocation,
+        target_type: String,
+        target_location: SourceLocation,
+    },
+    /// A C function declared with `extern fn name(args) -> Type;`, linked in from outside
+    ExternFunction {
+        function_name: String,
+        function_name_location: SourceLocation,
+        parameters: Vec<Parameter>,
         return_type_name: String,
         return_type_location: SourceLocation,
         metadata: FunctionMetadata,
     },
 }
 
+/// A field of a bit struct, `name: Type[width]`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BitField {
+    pub name: String,
+    pub type_name: String,
+    pub type_location: SourceLocation,
+    /// The number of bits the field takes up
+    pub width: usize,
+}
+
+/// A function parameter, `name: Type`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub type_name: String,
+    pub type_location: SourceLocation,
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct FunctionMetadata {
     pub var_types: HashMap<String, TypeInformation>,
+    pub parameter_types: Vec<TypeInformation>,
     pub return_type: Option<TypeInformation>,
+    /// The `/** */` comment in front of the function
+    pub doc_comment: Option<String>,
+    pub inlining_hint: InliningHint,
+    /// The message of `#[deprecated("...")]`, empty for a plain `#[deprecated]`
+    pub deprecated: Option<String>,
+    /// The last parameter is `name: ...`, which takes any number of arguments
+    pub is_variadic: bool,
+    /// The named return values with their types
+    pub named_returns: Vec<(String, TypeInformation)>,
+}
+
+/// Set with `#[inline]` or `#[noinline]`, without one LLVM decides
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum InliningHint {
+    #[default]
+    None,
+    Always,
+    Never,
 }
 
 /// A code body is a collection of statements
@@ -30,22 +105,99 @@ pub struct CodeBody(pub Vec<Statement>);
 /// A statement is the building blocks of a program, some statements contain more statements (like the body of a loop);
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Statement {
-    /// A print statement is used to output the value of a expression
-    Print(Expression),
-    Assert(Expression),
+    /// A print statement is used to output the values of one or more expressions,
+    /// followed by a single newline
+    Print(Vec<Expression>),
+    /// `eprint`, the same as `print` but writes to stderr
+    PrintErr(Vec<Expression>),
+    /// Aborts the program if the expression is false, printing the message if there is one
+    Assert(Expression, Option<String>),
     Test(String, Expression),
+    /// `bench "name" iterations -> expression;`, only compiled with `--bench`
+    Bench {
+        name: String,
+        iterations: Expression,
+        body: Expression,
+    },
     /// An assignment stores the value of a expression in the provided name
     Assignment {
         expression_location: SourceLocation,
         var_name: String,
         expression: Expression,
     },
-    Return(Expression),
+    /// `return value;`, or `return;` in functions that return Void. The location is the keyword
+    Return(Option<Expression>, SourceLocation),
+    /// An if statement, `else if` branches are kept as a flat chain instead of nested ifs
     If {
         condition: Expression,
         then: CodeBody,
+        else_if_chains: Vec<(Expression, CodeBody)>,
         otherwise: CodeBody,
-    }
+    },
+    /// A while loop, the else body only runs if the loop was not exited with a break
+    While {
+        condition: Expression,
+        body: CodeBody,
+        else_body: Option<CodeBody>,
+    },
+    /// `while x = next(), x != 0 { ... }`, the assignment runs before every condition check.
+    /// `assignment` is always a `Statement::Assignment`
+    WhileAssign {
+        assignment: Box<Statement>,
+        condition: Expression,
+        body: CodeBody,
+    },
+    /// `for var in array { ... }`, runs the body once for every element of the array
+    ForEach {
+        var: String,
+        array: Expression,
+        body: CodeBody,
+    },
+    /// `switch x { case 1: { ... } default: { ... } }`, there is no fallthrough between cases
+    Switch {
+        scrutinee: Expression,
+        arms: Vec<(LiteralType, SourceLocation, CodeBody)>,
+        default: Option<CodeBody>,
+    },
+    /// `repeat count { ... }`, the count is evaluated once before the first run
+    Repeat {
+        count: Expression,
+        body: CodeBody,
+    },
+    /// `do { ... } while condition;`, the body runs once before the condition is checked
+    DoWhile {
+        body: CodeBody,
+        condition: Expression,
+    },
+    /// `for (init; condition; update) { ... }`, variables assigned in `init` only exist in the loop
+    CStyleFor {
+        init: Box<Statement>,
+        condition: Expression,
+        update: Box<Statement>,
+        body: CodeBody,
+    },
+    Break,
+    /// `defer statement;`, the statement runs when the function returns, the last deferred first.
+    /// Only allowed directly in the function body
+    Defer {
+        body: Box<Statement>,
+        location: SourceLocation,
+    },
+    /// `_ = expression;` or just `expression;`, evaluates the expression and throws away the result
+    Discard(Expression),
+    /// `assert_type!(expression, Type);`, checked at compile time and not compiled
+    AssertType {
+        expression: Expression,
+        expected_type: String,
+        location: SourceLocation,
+    },
+    /// `assert_eq!(expected, actual, "message");`, prints both values when they differ
+    AssertEq {
+        expected: Expression,
+        actual: Expression,
+        message: Option<String>,
+        location: SourceLocation,
+    },
 }
 
 // An expression is the building block of the language. it usually does stuff.
@@ -76,9 +228,27 @@ pub enum Expression {
     },
     /// Loads a value as stored by the assignment expression
     Var(ExpressionMetadata, String),
+    /// `[1, 2, 3]`, all elements have the same type
+    Array(ExpressionMetadata, Vec<Expression>),
+    /// `(1, true)`, always has at least 2 elements
+    Tuple(ExpressionMetadata, Vec<Expression>),
+    /// `tuple.0`
+    TupleIndex {
+        tuple: Box<Expression>,
+        index: usize,
+        metadata: ExpressionMetadata,
+    },
+    /// Calls a function by name, currently only built-in functions can be called
+    FunctionCall {
+        name: String,
+        arguments: Vec<Expression>,
+        metadata: ExpressionMetadata,
+        /// `return f(...);` inside of `f` itself, set by the return check
+        is_tail_call: bool,
+    },
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ExpressionMetadata {
     pub location: SourceLocation,
     pub type_information: Option<TypeInformation>,
@@ -93,14 +263,65 @@ impl From<SourceLocation> for ExpressionMetadata {
     }
 }
 
+impl Statement {
+    /// The location of the expression the statement is built around, `break` has none
+    pub fn location(&self) -> Option<SourceLocation> {
+        match self {
+            Statement::Print(expressions) | Statement::PrintErr(expressions) => {
+                expressions.first().map(|expr| *expr.location())
+            }
+            Statement::Assignment {
+                expression_location: location,
+                ..
+            }
+            | Statement::Return(_, location)
+            | Statement::Defer { location, .. }
+            | Statement::AssertType { location, .. }
+            | Statement::AssertEq { location, .. } => Some(*location),
+            Statement::Assert(expr, _)
+            | Statement::Test(_, expr)
+            | Statement::Discard(expr)
+            | Statement::If {
+                condition: expr, ..
+            }
+            | Statement::While {
+                condition: expr, ..
+            }
+            | Statement::DoWhile {
+                condition: expr, ..
+            }
+            | Statement::ForEach { array: expr, .. }
+            | Statement::Switch {
+                scrutinee: expr, ..
+            }
+            | Statement::Repeat { count: expr, .. }
+            | Statement::Bench {
+                iterations: expr, ..
+            } => Some(*expr.location()),
+            Statement::WhileAssign {
+                assignment: statement,
+                ..
+            }
+            | Statement::CStyleFor {
+                init: statement, ..
+            } => statement.location(),
+            Statement::Break => None,
+        }
+    }
+}
+
 impl Expression {
     pub fn metadata(&self) -> &ExpressionMetadata {
         match self {
             Expression::Literal(meta, _)
             | Expression::Binary { metadata: meta, .. }
             | Expression::Var(meta, _)
+            | Expression::Array(meta, _)
+            | Expression::Tuple(meta, _)
+            | Expression::TupleIndex { metadata: meta, .. }
             | Expression::ComparisonChain { metadata: meta, .. }
-            | Expression::PrefixExpression { metadata: meta, .. } => meta,
+            | Expression::PrefixExpression { metadata: meta, .. }
+            | Expression::FunctionCall { metadata: meta, .. } => meta,
         }
     }
 
@@ -111,6 +332,22 @@ impl Expression {
     pub fn type_info(&self) -> &TypeInformation {
         self.metadata().type_information.as_ref().unwrap()
     }
+
+    /// The value of a number literal, or of a negated one
+    pub fn number_literal(&self) -> Option<i64> {
+        match self {
+            Expression::Literal(_, LiteralType::Number(value)) => Some(i64::from(*value)),
+            Expression::PrefixExpression {
+                op: PrefixOprator::Negate,
+                expression,
+                ..
+            } => match expression.as_ref() {
+                Expression::Literal(_, LiteralType::Number(value)) => Some(-i64::from(*value)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 /// A operator describes what action should be taken on the expressions of a binary-exp
@@ -131,11 +368,20 @@ pub enum Comparison {
     GreaterThanEqual,
     LessThan,
     LessThanEqual,
+    /// `needle in haystack`
+    Contains,
+    /// `needle not in haystack`
+    NotContains,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum PrefixOprator {
     Not,
+    Negate,
+    /// `&expr`, the address of a variable or of a copy of the value
+    AddressOf,
+    /// `*expr`, loads the value a pointer points to
+    Deref,
 }
 
 /// A literal is a hardcoded value
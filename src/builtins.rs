@@ -0,0 +1,158 @@
+use crate::types::{Ownership, TypeInformation};
+
+/// Functions that are provided by the compiler itself
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Builtin {
+    /// `substr(String, Num, Num) -> Str`, takes the start index and the length of the slice,
+    /// both are clamped to the string
+    Substr,
+
+    /// `wrapping_*(Num, Num) -> Num`, wraps around on overflow
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    /// `checked_*(Num, Num) -> Bool`, returns true if the operation would overflow
+    CheckedAdd,
+    CheckedSub,
+    CheckedMul,
+    /// `saturating_*(Num, Num) -> Num`, clamps the result to the bounds of `Num`
+    SaturatingAdd,
+    SaturatingSub,
+    SaturatingMul,
+
+    /// `input() -> String`, reads a single word from stdin, at most 1023 bytes long.
+    /// Empty if nothing could be read
+    Input,
+    /// `input_num() -> Num`, reads a number from stdin, 0 if there is no number
+    InputNum,
+
+    /// `exit(Num)`, stops the program with the given exit code
+    Exit,
+
+    /// `len(Array) -> Num`, the size of any array, known at compile time.
+    /// Like `sizeof` in C the argument is never evaluated.
+    /// `len(String) -> Num` counts the bytes of the string at runtime
+    Len,
+
+    /// `format(String, ...) -> String`, formats the values like `printf` into a new string.
+    /// The values can be any `Num`, `Bool` or `String`, and a literal format has to match them
+    Format,
+
+    /// `get_field(BitStruct, "field")`, reads a field of a bit struct
+    GetField,
+    /// `set_field(BitStruct, "field", value) -> BitStruct`, a copy with the field changed
+    SetField,
+
+    /// `typeof_str(any) -> String`, the name of the type of the value as a constant string.
+    /// The value is still evaluated
+    TypeofStr,
+
+    /// `char_at(String, Num) -> Num`, the byte at the index as a number.
+    /// The program aborts if the index is not inside the string
+    CharAt,
+    /// `set_char(String, Num, Num)`, overwrites the byte at the index, checked like `char_at`.
+    /// The string has to be a variable, since only the copy a variable owns can be changed
+    SetChar,
+    /// `char_count(String) -> Num`, the number of bytes in the string, like `len`
+    CharCount,
+
+    /// `get_bits(value, offset, width) -> Num`, the `width` bits of the value starting at bit
+    /// `offset`, counted from the lowest bit. The offset is 0 to 31 and the width 0 to 32, bits
+    /// past the top of the value are 0
+    GetBits,
+    /// `set_bits(value, offset, width, field) -> Num`, a copy of the value with those bits
+    /// replaced by the lowest bits of the field
+    SetBits,
+
+    /// `range(Num, Num) -> Array`, the numbers from the start up to but not including the end.
+    /// Both have to be number literals so the size of the array is known at compile time,
+    /// and the range can have at most 65536 numbers
+    Range,
+}
+
+/// How the arguments of a builtin are type checked
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Signature {
+    /// Takes exactly these parameters and returns the type, like a function in the code
+    Fixed(Vec<TypeInformation>, TypeInformation),
+    /// The types depend on the arguments, so the type analyzer has a check for the builtin.
+    /// `len` and `typeof_str` take more than one type, `format` is variadic, the field decides
+    /// the types of `get_field` and `set_field`, and the bounds decide the size of `range`
+    Custom,
+}
+
+const BUILTINS: [(&str, Builtin); 24] = [
+    ("substr", Builtin::Substr),
+    ("wrapping_add", Builtin::WrappingAdd),
+    ("wrapping_sub", Builtin::WrappingSub),
+    ("wrapping_mul", Builtin::WrappingMul),
+    ("checked_add", Builtin::CheckedAdd),
+    ("checked_sub", Builtin::CheckedSub),
+    ("checked_mul", Builtin::CheckedMul),
+    ("saturating_add", Builtin::SaturatingAdd),
+    ("saturating_sub", Builtin::SaturatingSub),
+    ("saturating_mul", Builtin::SaturatingMul),
+    ("input", Builtin::Input),
+    ("input_num", Builtin::InputNum),
+    ("exit", Builtin::Exit),
+    ("len", Builtin::Len),
+    ("format", Builtin::Format),
+    ("get_field", Builtin::GetField),
+    ("set_field", Builtin::SetField),
+    ("typeof_str", Builtin::TypeofStr),
+    ("range", Builtin::Range),
+    ("char_at", Builtin::CharAt),
+    ("set_char", Builtin::SetChar),
+    ("char_count", Builtin::CharCount),
+    ("get_bits", Builtin::GetBits),
+    ("set_bits", Builtin::SetBits),
+];
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        BUILTINS
+            .iter()
+            .find(|(builtin_name, _)| *builtin_name == name)
+            .map(|&(_, builtin)| builtin)
+    }
+
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        BUILTINS.iter().map(|&(name, _)| name)
+    }
+
+    pub fn signature(self) -> Signature {
+        let number = || TypeInformation::Number;
+        let string = || TypeInformation::String(Ownership::Borrowed);
+        match self {
+            Self::Substr => Signature::Fixed(
+                vec![string(), number(), number()],
+                TypeInformation::StringSlice,
+            ),
+            Self::WrappingAdd
+            | Self::WrappingSub
+            | Self::WrappingMul
+            | Self::SaturatingAdd
+            | Self::SaturatingSub
+            | Self::SaturatingMul => Signature::Fixed(vec![number(), number()], number()),
+            Self::CheckedAdd | Self::CheckedSub | Self::CheckedMul => {
+                Signature::Fixed(vec![number(), number()], TypeInformation::Boolean)
+            }
+            Self::Input => Signature::Fixed(vec![], TypeInformation::String(Ownership::Owned)),
+            Self::InputNum => Signature::Fixed(vec![], number()),
+            Self::Exit => Signature::Fixed(vec![number()], TypeInformation::Void),
+            Self::CharAt => Signature::Fixed(vec![string(), number()], number()),
+            Self::SetChar => {
+                Signature::Fixed(vec![string(), number(), number()], TypeInformation::Void)
+            }
+            Self::CharCount => Signature::Fixed(vec![string()], number()),
+            Self::GetBits => Signature::Fixed(vec![number(); 3], number()),
+            Self::SetBits => Signature::Fixed(vec![number(); 4], number()),
+            Self::Len
+            | Self::TypeofStr
+            | Self::Format
+            | Self::GetField
+            | Self::SetField
+            | Self::Range => Signature::Custom,
+        }
+    }
+}
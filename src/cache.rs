@@ -0,0 +1,194 @@
+use std::cmp::Reverse;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::source_map::escape_json;
+use crate::CompilerConfig;
+
+/// The most bytes of IR the cache keeps, the entries used longest ago are removed past it
+const MAX_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Compiled IR stored under a hash of everything that went into compiling it.
+/// Failing to read or write the cache never fails the compilation, it just compiles again.
+pub struct IrCache {
+    dir: PathBuf,
+}
+
+impl IrCache {
+    /// `None` when the cache is turned off, or when the config asks for output that only the
+    /// analysis produces, like warnings or the printed AST
+    pub fn for_config(config: &CompilerConfig) -> Option<Self> {
+        let reports_analysis = config.warn_shadowing
+            || config.warn_dead_functions
+            || config.warn_dead_stores
+            || config.print_ast
+            || config.print_ast_after_pass.is_some();
+        if reports_analysis {
+            return None;
+        }
+
+        config.cache_dir.clone().map(|dir| Self { dir })
+    }
+
+    /// The IR of the code, compared with the stored source so a hash collision compiles again
+    pub fn get(&self, key: &str, name: &str, code: &str) -> Option<String> {
+        let source = std::fs::read_to_string(self.dir.join(format!("{key}.viv"))).ok()?;
+        if source != code {
+            return None;
+        }
+
+        let path = self.dir.join(format!("{key}.ll"));
+        let ir = rename_module(&std::fs::read_to_string(&path).ok()?, name)?;
+        // A hit counts as a use, so the entries removed first are the ones unused for longest
+        let _ = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        Some(ir)
+    }
+
+    /// Writes `<key>.ll` with the source in `<key>.viv`, and a `<key>.json` sidecar with the file
+    /// name and compiler version
+    pub fn store(&self, key: &str, name: &str, code: &str, ir: &str) {
+        let metadata = format!(
+            "{{\"file\": \"{}\", \"compiler_version\": \"{}\"}}",
+            escape_json(name),
+            env!("CARGO_PKG_VERSION")
+        );
+
+        // The IR is written last, so an entry is only ever found once its sidecars exist
+        let _ = std::fs::create_dir_all(&self.dir)
+            .and_then(|()| std::fs::write(self.dir.join(format!("{key}.json")), metadata))
+            .and_then(|()| std::fs::write(self.dir.join(format!("{key}.viv")), code))
+            .and_then(|()| std::fs::write(self.dir.join(format!("{key}.ll")), ir));
+        self.prune();
+    }
+
+    /// Removes the entries used longest ago until the IR fits in `MAX_CACHE_BYTES`
+    fn prune(&self) {
+        let Ok(dir) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut entries = Vec::new();
+        for path in dir.filter_map(|entry| Some(entry.ok()?.path())) {
+            let Ok(metadata) = path.metadata() else {
+                continue;
+            };
+            if path.extension().and_then(|extension| extension.to_str()) == Some("ll") {
+                entries.push((metadata.modified().ok(), metadata.len(), path));
+            }
+        }
+
+        // Newest first, everything after the limit is reached goes
+        entries.sort_by_key(|(modified, _, _)| Reverse(*modified));
+        let mut total = 0;
+        for (_, size, path) in entries {
+            total += size;
+            if total > MAX_CACHE_BYTES {
+                remove_entry(&path);
+            }
+        }
+    }
+}
+
+/// The IR is removed first, so a half removed entry is never found
+fn remove_entry(ir_path: &Path) {
+    for extension in ["ll", "viv", "json"] {
+        let _ = std::fs::remove_file(ir_path.with_extension(extension));
+    }
+}
+
+/// The IR compiled from the same code in another file, with the module header naming `name`.
+/// When the old name is used anywhere else, like in the labels of `test` statements, or LLVM had
+/// to escape one of the names, the IR can not be reused.
+fn rename_module(ir: &str, name: &str) -> Option<String> {
+    let stored_name = ir
+        .lines()
+        .next()?
+        .strip_prefix("; ModuleID = '")?
+        .strip_suffix('\'')?;
+    if stored_name == name {
+        return Some(ir.to_string());
+    }
+
+    let is_plain = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| (c.is_ascii_graphic() || c == ' ') && !matches!(c, '"' | '\\' | '\''))
+    };
+    let header = format!("; ModuleID = '{stored_name}'\nsource_filename = \"{stored_name}\"\n");
+    let body = ir.strip_prefix(&header)?;
+    if !is_plain(stored_name) || !is_plain(name) || body.contains(stored_name) {
+        return None;
+    }
+
+    Some(format!(
+        "; ModuleID = '{name}'\nsource_filename = \"{name}\"\n{body}"
+    ))
+}
+
+/// `$XDG_CACHE_HOME/viv_script`, or `~/.cache/viv_script`
+#[must_use]
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let cache_home = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(cache_home.join("viv_script"))
+}
+
+/// Hashes the code with the settings that change the IR, and the compiler binary itself,
+/// so rebuilding the compiler without bumping its version still misses the old entries.
+/// The file name is left out, the same code in another file uses the same entry.
+pub fn cache_key(code: &str, config: &CompilerConfig) -> String {
+    let mut defines: Vec<_> = config.defines.iter().collect();
+    defines.sort();
+
+    let compiler = std::env::current_exe()
+        .and_then(std::fs::metadata)
+        .map(|metadata| format!("{} {:?}", metadata.len(), metadata.modified().ok()))
+        .unwrap_or_default();
+
+    let mut hasher = Fnv1a::default();
+    for part in [
+        env!("CARGO_PKG_VERSION"),
+        &compiler,
+        code,
+        &format!(
+            "{} {:?} {} {} {}",
+            config.optimize, config.target, config.test, config.bench, config.small_ints
+        ),
+        &format!("{defines:?}"),
+    ] {
+        hasher.write(part.as_bytes());
+        // Keeps `("ab", "c")` and `("a", "bc")` apart
+        hasher.write_u8(0xff);
+    }
+
+    format!("{:016x}", hasher.finish())
+}
+
+/// 64 bit FNV-1a, unlike `DefaultHasher` it is guaranteed to be the same in every Rust version
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
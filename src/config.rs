@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+pub const CONFIG_FILE_NAME: &str = "viv.toml";
+
+/// Project level settings, loaded from a `viv.toml` file.
+/// Flags passed on the command line take precedence over these.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct VivConfig {
+    pub optimize: bool,
+    pub llc_path: Option<String>,
+    pub gcc_path: Option<String>,
+    pub test_folder: Option<String>,
+}
+
+impl Default for VivConfig {
+    fn default() -> Self {
+        Self {
+            optimize: true,
+            llc_path: None,
+            gcc_path: None,
+            test_folder: None,
+        }
+    }
+}
+
+impl VivConfig {
+    /// Looks for a config file in `start` and all of its parent folders
+    #[must_use]
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|folder| folder.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the closest config file to the current directory, or the default config if there is none
+    pub fn load() -> Result<Self, String> {
+        let current_dir = std::env::current_dir().map_err(|err| err.to_string())?;
+
+        match Self::find(&current_dir) {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+                toml::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+}
@@ -0,0 +1,583 @@
+use crate::SourceLocation;
+
+/// Number of single character insertions, deletions or substitutions needed to turn `a` into `b`
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current_row = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + usize::from(a_char != *b_char);
+            let deletion = previous_row[j + 1] + 1;
+            let insertion = current_row[j] + 1;
+            current_row.push(substitution.min(deletion).min(insertion));
+        }
+
+        previous_row = current_row;
+    }
+
+    previous_row[b.len()]
+}
+
+/// Returns the candidate closest to `name`, if it is close enough to likely be a typo
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    const MAX_DISTANCE: usize = 2;
+
+    candidates
+        .into_iter()
+        .map(|candidate| (levenshtein_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+        // Ties are broken by name so the suggestion does not depend on hashmap order
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Formats `message` with a "did you mean" hint when one of the candidates is close to `name`
+pub fn with_suggestion<'a>(
+    message: String,
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> String {
+    match closest_match(name, candidates) {
+        Some(suggestion) => format!("{message}, did you mean '{suggestion}'?"),
+        None => message,
+    }
+}
+
+/// Problems that do not stop compilation.
+/// Each kind except `DeprecatedFunction` and `VariadicUnsafe` has to be enabled in `CompilerConfig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompilerWarning {
+    /// A variable in a nested block hides a variable with the same name from an outer block
+    Shadowing { location: SourceLocation, name: String },
+    /// A function that is never called
+    UnusedFunction { location: SourceLocation, name: String },
+    /// An assignment that is overwritten before the variable is read
+    DeadStore { location: SourceLocation, name: String },
+    /// A call to a function marked `#[deprecated]`, the message is empty if it has none
+    DeprecatedFunction {
+        location: SourceLocation,
+        name: String,
+        message: String,
+    },
+    /// A value that is not a Num or Bool passed to a rest parameter, which is not type checked
+    VariadicUnsafe {
+        location: SourceLocation,
+        name: String,
+        type_name: String,
+    },
+}
+
+impl CompilerWarning {
+    pub fn location(&self) -> SourceLocation {
+        match self {
+            Self::Shadowing { location, .. }
+            | Self::UnusedFunction { location, .. }
+            | Self::DeadStore { location, .. }
+            | Self::DeprecatedFunction { location, .. }
+            | Self::VariadicUnsafe { location, .. } => *location,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::Shadowing { name, .. } => {
+                format!("{name} shadows a variable from an outer block")
+            }
+            Self::UnusedFunction { name, .. } => format!("Function {name} is never called"),
+            Self::DeadStore { name, .. } => {
+                format!("The value assigned to {name} is overwritten before it is read")
+            }
+            Self::DeprecatedFunction { name, message, .. } if message.is_empty() => {
+                format!("Function {name} is deprecated")
+            }
+            Self::DeprecatedFunction { name, message, .. } => {
+                format!("Function {name} is deprecated: {message}")
+            }
+            Self::VariadicUnsafe { name, type_name, .. } => format!(
+                "{type_name} passed to the rest parameter of {name}, these are not type checked"
+            ),
+        }
+    }
+}
+
+/// Error codes that are shown in error messages and can be looked up with `viv --explain`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    TypeMismatch = 1,
+    UndefinedVariable = 2,
+    InvalidType = 3,
+    UndefinedFunction = 4,
+    UnsupportedOperator = 5,
+    InvalidComparison = 6,
+    NonBooleanCondition = 7,
+    WrongArgumentCount = 8,
+    BreakOutsideLoop = 9,
+    UnclosedString = 10,
+    InvalidCharacter = 11,
+    UnexpectedToken = 12,
+    DuplicateFunction = 13,
+    MissingReturn = 14,
+    DuplicateCase = 15,
+    InternalError = 16,
+    CyclicTypeAlias = 17,
+    FileNotFound = 18,
+    ReservedName = 19,
+    InvalidTarget = 20,
+}
+
+impl ErrorCode {
+    pub const ALL: [Self; 20] = [
+        Self::TypeMismatch,
+        Self::UndefinedVariable,
+        Self::InvalidType,
+        Self::UndefinedFunction,
+        Self::UnsupportedOperator,
+        Self::InvalidComparison,
+        Self::NonBooleanCondition,
+        Self::WrongArgumentCount,
+        Self::BreakOutsideLoop,
+        Self::UnclosedString,
+        Self::InvalidCharacter,
+        Self::UnexpectedToken,
+        Self::DuplicateFunction,
+        Self::MissingReturn,
+        Self::DuplicateCase,
+        Self::InternalError,
+        Self::CyclicTypeAlias,
+        Self::FileNotFound,
+        Self::ReservedName,
+        Self::InvalidTarget,
+    ];
+
+    /// Parses codes like `E002`, the leading `E` is optional
+    pub fn from_code(code: &str) -> Option<Self> {
+        let number = code
+            .strip_prefix(['E', 'e'])
+            .unwrap_or(code)
+            .parse::<usize>()
+            .ok()?;
+        Self::ALL.into_iter().find(|error| *error as usize == number)
+    }
+
+    pub fn code(self) -> String {
+        format!("E{:03}", self as usize)
+    }
+
+    /// Prefixes the message with the error code
+    pub fn tag(self, message: impl std::fmt::Display) -> String {
+        format!("[{}] {message}", self.code())
+    }
+
+    pub fn explanation(self) -> &'static str {
+        match self {
+            Self::TypeMismatch => {
+                r#"A value of one type was used where another type was expected.
+
+Both sides of an operator, every part of a comparison chain, arguments to
+builtin functions and returned values all need to have the expected type.
+A variable also keeps the type of its first assignment.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        x = 1;
+        x = "one";
+        return 0;
+    }
+
+Use a new variable for the value of the other type:
+
+    fn main() -> Num {
+        x = 1;
+        x_name = "one";
+        return 0;
+    }"#
+            }
+            Self::UndefinedVariable => {
+                r#"A variable was used before anything was assigned to it.
+
+Variables are created by their first assignment, reading a name that has
+not been assigned yet is an error. This is often caused by a typo, in which
+case the compiler suggests the name you most likely meant.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        count = 1;
+        print cuont;
+        return 0;
+    }
+
+Use the name of a variable that exists:
+
+    fn main() -> Num {
+        count = 1;
+        print count;
+        return 0;
+    }"#
+            }
+            Self::InvalidType => {
+                r#"A type name that does not exist was used.
+
+The available types are `Num`, `Bool`, `String` and `Str`, pointers to
+them which are written as `&Num`, tuples like `(Num, Bool)` and arrays
+like `[Num; 3]`. Names given with `type` and `struct` can be used too.
+
+The same code is used for invalid aliases and bit structs, like a field
+that is too wide or an alias that is defined twice.
+
+Erroneous code example:
+
+    fn main() -> Number {
+        return 0;
+    }
+
+Use one of the builtin types:
+
+    fn main() -> Num {
+        return 0;
+    }"#
+            }
+            Self::UndefinedFunction => {
+                r#"A function that does not exist was called.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print subtr("hello", 0, 2);
+        return 0;
+    }
+
+Check the spelling of the function name:
+
+    fn main() -> Num {
+        print substr("hello", 0, 2);
+        return 0;
+    }"#
+            }
+            Self::UnsupportedOperator => {
+                r#"An operator was used on a type that does not support it.
+
+Arithmetic (`+`, `-`, `*`, `/`) and negation only work on `Num`, `!`
+only works on `Bool` and `*` only dereferences pointers. The address of a
+temporary `String` can not be taken, store it in a variable first.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print true + false;
+        return 0;
+    }
+
+Use an operator that the type supports:
+
+    fn main() -> Num {
+        print !false;
+        return 0;
+    }"#
+            }
+            Self::InvalidComparison => {
+                r#"A comparison was used on a type that does not support it.
+
+`Num` supports all comparisons, `Bool` only supports `==` and `!=`, and
+`String` only supports `in` and `not in`.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print "a" == "b";
+        return 0;
+    }
+
+Compare numbers instead:
+
+    fn main() -> Num {
+        print 1 == 2;
+        return 0;
+    }"#
+            }
+            Self::NonBooleanCondition => {
+                r#"A condition was not a `Bool`.
+
+The conditions of `if` and `while`, as well as `assert` and `test`
+statements, need a `Bool`. Numbers and strings are not converted to
+booleans, compare numbers with `x != 0` and strings with `len(x) != 0`.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        x = 1;
+        if x {
+            print "x is set";
+        }
+        return 0;
+    }
+
+Compare the value explicitly:
+
+    fn main() -> Num {
+        x = 1;
+        if x != 0 {
+            print "x is set";
+        }
+        return 0;
+    }"#
+            }
+            Self::WrongArgumentCount => {
+                r#"A function was called with the wrong number of arguments.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print substr("hello", 1);
+        return 0;
+    }
+
+`substr` takes the string, the start index and the length:
+
+    fn main() -> Num {
+        print substr("hello", 1, 3);
+        return 0;
+    }"#
+            }
+            Self::BreakOutsideLoop => {
+                r#"`break` was used outside of a loop.
+
+`break` exits the innermost `while`, `repeat`, `do` or `for` loop, so it can only
+be used inside one.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        break;
+        return 0;
+    }
+
+Use `return` to leave a function early:
+
+    fn main() -> Num {
+        return 0;
+    }"#
+            }
+            Self::UnclosedString => {
+                r#"A string literal was not closed before the end of the line.
+
+Strings can not span multiple lines.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print "hello;
+        return 0;
+    }
+
+Close the string with a `"`:
+
+    fn main() -> Num {
+        print "hello";
+        return 0;
+    }"#
+            }
+            Self::InvalidCharacter => {
+                r#"The source code contains a character that is not part of the language.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        x = 1 % 2;
+        return 0;
+    }
+
+Characters that are not used by any token are only allowed inside strings."#
+            }
+            Self::UnexpectedToken => {
+                r#"The parser found a token it did not expect.
+
+This is usually caused by a missing `;`, brace or parenthesis.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print "hello"
+        return 0;
+    }
+
+Add the missing token:
+
+    fn main() -> Num {
+        print "hello";
+        return 0;
+    }"#
+            }
+            Self::DuplicateFunction => {
+                r#"A function with the same name was defined more than once.
+
+Every function name has to be unique, when compiling multiple files this
+includes the functions in all of them.
+
+Erroneous code example:
+
+    fn helper() -> Num {
+        return 1;
+    }
+
+    fn helper() -> Num {
+        return 2;
+    }
+
+Or across files:
+
+    // main.viv
+    fn helper() -> Num {
+        return 1;
+    }
+
+    // other.viv
+    fn helper() -> Num {
+        return 2;
+    }
+
+Rename one of the functions."#
+            }
+            Self::MissingReturn => {
+                r#"A function can reach the end of its body without returning a value.
+
+Every path through a function has to end with a `return`. An `if` or
+`switch` only counts when it has an `else` or `default` and every branch
+returns. Loops do not count because the compiler does not know if they
+finish, except for `do` loops whose body returns.
+
+Erroneous code example:
+
+    fn sign(x: Num) -> Num {
+        if x > 0 {
+            return 1;
+        }
+    }
+
+Return a value on every path:
+
+    fn sign(x: Num) -> Num {
+        if x > 0 {
+            return 1;
+        }
+        return 0;
+    }"#
+            }
+            Self::DuplicateCase => {
+                r#"A `switch` has more than one `case` with the same value.
+
+Only the first of them could ever run, so this is always a mistake.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        switch 1 {
+            case 1: { print "one"; }
+            case 1: { print "also one"; }
+        }
+        return 0;
+    }
+
+Merge the bodies of the cases:
+
+    fn main() -> Num {
+        switch 1 {
+            case 1: {
+                print "one";
+                print "also one";
+            }
+        }
+        return 0;
+    }"#
+            }
+            Self::InternalError => {
+                r#"The compiler generated invalid LLVM IR.
+
+This is a bug in the compiler and not in your code. The error
+contains what LLVM found wrong with the IR, please report it
+together with the code that caused it."#
+            }
+            Self::CyclicTypeAlias => {
+                r#"A type alias refers to itself.
+
+Aliases are only other names for a type, so following them has to
+end at a real type.
+
+Erroneous code example:
+
+    type Meters = Distance;
+    type Distance = Meters;
+
+Make one of them refer to a real type:
+
+    type Meters = Num;
+    type Distance = Meters;"#
+            }
+            Self::FileNotFound => {
+                r#"A file included with `include_str!` could not be read.
+
+The path is relative to the folder of the file being compiled, and the
+file has to be valid UTF-8 text without NUL bytes.
+
+Erroneous code example:
+
+    fn main() -> Num {
+        print include_str!("missing.txt");
+        return 0;
+    }
+
+Check that the file exists next to the source file:
+
+    fn main() -> Num {
+        print include_str!("greeting.txt");
+        return 0;
+    }"#
+            }
+            Self::ReservedName => {
+                r#"A function has the name of a C function the compiled code uses itself.
+
+The generated code calls functions like `printf`, `malloc` and `free` from
+the C library, so the program can not define its own function with that
+name. Declaring them with `extern fn` is fine.
+
+Erroneous code example:
+
+    fn free(value: Num) -> Num {
+        return value;
+    }
+
+Give the function another name:
+
+    fn release(value: Num) -> Num {
+        return value;
+    }"#
+            }
+            Self::InvalidTarget => {
+                r#"The target given with `--target` is not one LLVM can compile for.
+
+The target is an LLVM target triple, made of the architecture, vendor,
+operating system and environment.
+
+Erroneous code example:
+
+    viv build main.viv --target not-a-target
+
+Use a full target triple:
+
+    viv build main.viv --target aarch64-unknown-linux-gnu"#
+            }
+        }
+    }
+}
+
+/// Prints the documentation for an error code, used by `viv --explain`
+pub fn explain_error(code: ErrorCode) {
+    println!("{}\n\n{}", code.code(), code.explanation());
+}
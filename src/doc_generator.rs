@@ -0,0 +1,165 @@
+use std::fmt::Write;
+
+use crate::ast;
+
+/// The pages documenting every function in the file, as `(file name, html)`.
+/// `index.html` lists the signatures and links to a `fn.<name>.html` page per function.
+pub fn generate(title: &str, file: &ast::File) -> Vec<(String, String)> {
+    let functions: Vec<Function> = file
+        .0
+        .iter()
+        .filter_map(|statement| match statement {
+            ast::TopLevelStatement::FunctionDefinition {
+                function_name,
+                is_public,
+                parameters,
+                return_type_name,
+                metadata,
+                ..
+            } => Some(Function {
+                name: function_name,
+                signature: signature(
+                    function_name,
+                    *is_public,
+                    parameters,
+                    return_type_name,
+                    metadata,
+                ),
+                doc_comment: metadata.doc_comment.as_deref(),
+            }),
+            ast::TopLevelStatement::TestFunction { .. }
+            | ast::TopLevelStatement::ExternFunction { .. }
+            | ast::TopLevelStatement::BitStruct { .. }
+            | ast::TopLevelStatement::TypeAlias { .. } => None,
+        })
+        .collect();
+
+    let mut pages = vec![("index.html".to_string(), index_page(title, &functions))];
+    pages.extend(functions.iter().map(|function| {
+        (
+            format!("fn.{}.html", function.name),
+            function_page(title, function),
+        )
+    }));
+    pages
+}
+
+struct Function<'a> {
+    name: &'a str,
+    signature: String,
+    doc_comment: Option<&'a str>,
+}
+
+/// `pub fn name(a: Num, b: String) -> Num`, with the types resolved by the type analyzer
+fn signature(
+    name: &str,
+    is_public: bool,
+    parameters: &[ast::Parameter],
+    return_type_name: &str,
+    metadata: &ast::FunctionMetadata,
+) -> String {
+    let parameters: Vec<String> = parameters
+        .iter()
+        .enumerate()
+        .map(|(index, parameter)| {
+            let type_name = metadata
+                .parameter_types
+                .get(index)
+                .map_or_else(|| parameter.type_name.clone(), |type_| type_.name());
+            format!("{}: {type_name}", parameter.name)
+        })
+        .collect();
+    let return_type = if metadata.named_returns.is_empty() {
+        metadata
+            .return_type
+            .as_ref()
+            .map_or_else(|| return_type_name.to_string(), |type_| type_.name())
+    } else {
+        let named_returns: Vec<String> = metadata
+            .named_returns
+            .iter()
+            .map(|(name, type_)| format!("{name}: {}", type_.name()))
+            .collect();
+        format!("({})", named_returns.join(", "))
+    };
+
+    let mut signature = String::new();
+    if is_public {
+        signature.push_str("pub ");
+    }
+    write!(signature, "fn {name}({})", parameters.join(", ")).unwrap();
+    if return_type != "Void" {
+        write!(signature, " -> {return_type}").unwrap();
+    }
+    signature
+}
+
+fn index_page(title: &str, functions: &[Function]) -> String {
+    let mut body = String::new();
+    writeln!(body, "<h1>{}</h1>", escape_html(title)).unwrap();
+    body.push_str("<ul>\n");
+    for function in functions {
+        // The first paragraph of the doc comment is the summary
+        let summary = function
+            .doc_comment
+            .and_then(|doc_comment| doc_comment.split("\n\n").next())
+            .unwrap_or_default();
+        writeln!(
+            body,
+            "<li><a href=\"fn.{}.html\"><code>{}</code></a> {}</li>",
+            function.name,
+            escape_html(&function.signature),
+            escape_html(summary)
+        )
+        .unwrap();
+    }
+    body.push_str("</ul>\n");
+
+    page(title, &body)
+}
+
+fn function_page(title: &str, function: &Function) -> String {
+    let mut body = String::new();
+    writeln!(
+        body,
+        "<p><a href=\"index.html\">{}</a></p>",
+        escape_html(title)
+    )
+    .unwrap();
+    writeln!(body, "<h1>{}</h1>", function.name).unwrap();
+    writeln!(
+        body,
+        "<pre><code>{}</code></pre>",
+        escape_html(&function.signature)
+    )
+    .unwrap();
+    for paragraph in function.doc_comment.unwrap_or_default().split("\n\n") {
+        if !paragraph.trim().is_empty() {
+            writeln!(body, "<p>{}</p>", escape_html(paragraph.trim())).unwrap();
+        }
+    }
+
+    page(&format!("{} - {title}", function.name), &body)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         </head>\n<body>\n{body}</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
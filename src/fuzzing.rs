@@ -0,0 +1,3 @@
+//! Entry points for the fuzzing harnesses in `fuzz/`, only available with the `fuzzing` feature.
+
+pub use crate::parser::{parse_tokens, tokenize, SourceLocation, Token, TokenValue};
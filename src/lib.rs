@@ -6,70 +6,555 @@
     clippy::cast_sign_loss
 )]
 
+pub use analyzers::ANALYSIS_PASSES;
+pub use cache::default_cache_dir;
+pub use config::VivConfig;
+pub use diagnostics::{explain_error, CompilerWarning, ErrorCode};
+pub use lsp::run_language_server;
 pub use parser::SourceLocation;
-use std::{os::unix::process::ExitStatusExt, path::PathBuf};
+pub use source_map::{SourceMap, SourceMapEntry};
+use std::{
+    collections::HashMap,
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Instant,
+};
 
 mod analyzers;
+mod builtins;
+mod cache;
+mod config;
+mod diagnostics;
+mod doc_generator;
+mod lsp;
+mod source_map;
 mod types;
 
 mod ast;
 mod llvm_generator;
 mod parser;
 
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+
 type CompilerResult<T> = Result<T, (SourceLocation, String)>;
 
+/// Settings that change how code is compiled
+#[derive(Debug, Clone)]
+pub struct CompilerConfig {
+    pub optimize: bool,
+    /// Print the time spent in each compiler phase to stderr
+    pub verbose: bool,
+    /// Target triple to compile for, `None` means the host
+    pub target: Option<String>,
+    /// Produce a shared library instead of an executable
+    pub shared: bool,
+    /// Link the standard library statically
+    pub static_link: bool,
+    /// Instrument the executable or optimize it with a profile, this needs clang
+    pub pgo: PgoMode,
+    /// Link time optimization, this needs clang and on Linux also lld
+    pub lto: bool,
+    /// Root folder for headers and libraries used when linking
+    pub sysroot: Option<String>,
+    /// Warn when a variable in a nested block shadows one from an outer block
+    pub warn_shadowing: bool,
+    /// Warn about functions that are never called
+    pub warn_dead_functions: bool,
+    /// Warn about assignments that are overwritten before they are read
+    pub warn_dead_stores: bool,
+    /// Store variables that are only assigned small number literals in `i8` or `i16`
+    pub small_ints: bool,
+    /// Compile the `#[test]` functions and run them before `main`
+    pub test: bool,
+    /// Compile the `bench` statements, otherwise they are left out
+    pub bench: bool,
+    /// Values for `#if` directives, `PLATFORM` defaults to the OS the compiler runs on
+    pub defines: HashMap<String, String>,
+    /// Print the AST to stderr before and after the analysis
+    pub print_ast: bool,
+    /// Print the AST to stderr after the analysis pass with this name, see `ANALYSIS_PASSES`
+    pub print_ast_after_pass: Option<String>,
+    /// Folder `include_str!` paths are relative to, `None` is the working directory
+    pub source_dir: Option<PathBuf>,
+    /// Folder `compile_to_ir` caches the IR of unchanged code in, `None` turns the cache off
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl CompilerConfig {
+    /// PGO and LTO work on the IR, so clang compiles it instead of llc
+    #[must_use]
+    pub fn compiles_ir_with_clang(&self) -> bool {
+        self.pgo != PgoMode::None || self.lto
+    }
+}
+
+impl Default for CompilerConfig {
+    fn default() -> Self {
+        Self {
+            optimize: true,
+            verbose: false,
+            target: None,
+            shared: false,
+            static_link: false,
+            pgo: PgoMode::None,
+            lto: false,
+            sysroot: None,
+            warn_shadowing: false,
+            warn_dead_functions: false,
+            warn_dead_stores: false,
+            small_ints: false,
+            test: false,
+            bench: false,
+            defines: HashMap::from([(
+                "PLATFORM".to_string(),
+                std::env::consts::OS.to_string(),
+            )]),
+            print_ast: false,
+            print_ast_after_pass: None,
+            source_dir: None,
+            cache_dir: None,
+        }
+    }
+}
+
+/// Profile guided optimization, a `Generate` build writes `default.profraw` when it runs,
+/// which `llvm-profdata merge` turns into the profile for `Use`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PgoMode {
+    #[default]
+    None,
+    Generate,
+    Use(PathBuf),
+}
+
+/// Flags passed through to the linker
+#[derive(Debug, Clone, Default)]
+pub struct LinkConfig {
+    pub flags: Vec<String>,
+}
+
+impl From<&CompilerConfig> for LinkConfig {
+    fn from(config: &CompilerConfig) -> Self {
+        let mut flags = Vec::new();
+
+        if config.shared {
+            let shared_flag = if cfg!(target_os = "macos") {
+                "-dynamiclib"
+            } else {
+                "-shared"
+            };
+            flags.push(shared_flag.to_string());
+        } else {
+            flags.push("-no-pie".to_string());
+        }
+
+        if config.static_link {
+            flags.push("-static".to_string());
+        }
+        if let Some(sysroot) = &config.sysroot {
+            flags.push(format!("--sysroot={sysroot}"));
+        }
+
+        match &config.pgo {
+            PgoMode::None => {}
+            PgoMode::Generate => flags.push("-fprofile-generate".to_string()),
+            PgoMode::Use(profile) => flags.push(format!("-fprofile-use={}", profile.display())),
+        }
+        if config.lto {
+            flags.push("-flto".to_string());
+            // The default linker on Linux can not read the bitcode objects
+            if !cfg!(target_os = "macos") {
+                flags.push("-fuse-ld=lld".to_string());
+            }
+        }
+        // The linker compiles the IR itself, so it needs the optimization level too
+        if config.compiles_ir_with_clang() && config.optimize {
+            flags.push("-O2".to_string());
+        }
+
+        Self { flags }
+    }
+}
+
+fn timed<T>(config: &CompilerConfig, phase: &str, action: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = action();
+
+    if config.verbose {
+        eprintln!("[{}] {}ms", phase, start.elapsed().as_millis());
+    }
+
+    result
+}
+
 pub fn report_error(code: &str, err: &(SourceLocation, String)) {
     let traceback = err.0.get_line_highlights(code);
     eprintln!("{}\nERROR: {}", traceback, err.1);
 }
 
-pub fn compile_to_ir(name: &str, code: &str, output: &str, optimize: bool) -> CompilerResult<()> {
-    let mut ast = parser::parse_file(code)?;
+pub fn report_warning(code: &str, warning: &CompilerWarning) {
+    let traceback = warning.location().get_line_highlights(code);
+    eprintln!("{}\nWARNING: {}", traceback, warning.message());
+}
 
-    analyzers::apply_analyzer(&mut ast)?;
+pub fn compile_to_ir(
+    name: &str,
+    code: &str,
+    output: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<()> {
+    // The key does not cover the included files
+    let cache = cache::IrCache::for_config(config).filter(|_| !includes_files(code, config));
+    let ir = match cache {
+        Some(cache) => {
+            let key = cache::cache_key(code, config);
+            if let Some(ir) = timed(config, "cache", || cache.get(&key, name, code)) {
+                ir
+            } else {
+                let (ast, warnings) = analyze(code, config)?;
+                for warning in &warnings {
+                    report_warning(code, warning);
+                }
+                let ir = compile_ast_to_ir(name, ast, config, false)?;
+                // A cache hit skips the analysis, so code with warnings is never stored
+                if warnings.is_empty() {
+                    cache.store(&key, name, code, &ir);
+                }
+                ir
+            }
+        }
+        None => compile_to_ir_string(name, code, config)?,
+    };
+    std::fs::write(output, ir).unwrap();
 
-    let ctx = llvm_generator::Compiler::create_context();
-    let mut compiler = llvm_generator::Compiler::new(name, &ctx);
+    Ok(())
+}
 
-    compiler.compile_code(ast, optimize);
-    compiler.save_in(output);
+/// Whether the code uses `include_str!`, found from the tokens so spacing and comments do not
+/// matter. Code that can not be lexed counts as including files, the analysis reports why.
+fn includes_files(code: &str, config: &CompilerConfig) -> bool {
+    let tokens = parser::preprocess(code, &config.defines).and_then(|code| parser::tokenize(&code));
+    let Ok(tokens) = tokens else {
+        return true;
+    };
 
-    Ok(())
+    tokens.windows(2).any(|pair| {
+        matches!(&pair[0].value, parser::TokenValue::Identifier(name) if name == "include_str")
+            && pair[1].value == parser::TokenValue::Bang
+    })
 }
 
-pub fn compile_to_obj(llc: PathBuf, from: &str, to: &str) {
-    std::process::Command::new(llc)
-        .args([from, "-filetype=obj", "-o", to])
-        .spawn()
-        .unwrap()
-        .wait()
-        .unwrap()
-        .success()
-        .then_some(())
-        .expect("Non zero exit code");
+/// Same as `compile_to_ir` but returns the IR instead of writing it to a file
+pub fn compile_to_ir_string(
+    name: &str,
+    code: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<String> {
+    let ast = analyze_code(code, config)?;
+    compile_ast_to_ir(name, ast, config, false)
 }
 
-pub fn compile_to_exe(gcc: PathBuf, from: &str, to: &str) {
-    std::process::Command::new(gcc)
-        .args([from, "-no-pie", "-o", to])
-        .spawn()
-        .unwrap()
-        .wait()
-        .unwrap()
-        .success()
-        .then_some(())
-        .expect("Non zero exit code");
+/// Same as `compile_to_ir_string` but also returns which statement each line of the IR belongs to
+pub fn compile_to_ir_with_sourcemap(
+    name: &str,
+    code: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<(String, SourceMap)> {
+    let ast = analyze_code(code, config)?;
+    let ir = compile_ast_to_ir(name, ast, config, true)?;
+    let source_map = SourceMap::from_ir(&ir);
+    Ok((ir, source_map))
+}
+
+/// Parses and analyzes the code without generating any IR
+pub fn check(code: &str) -> CompilerResult<()> {
+    analyze_code(code, &CompilerConfig::default()).map(|_| ())
 }
 
+/// HTML documentation for the functions in the code, as `(file name, html)` pages
+pub fn generate_docs(
+    name: &str,
+    code: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<Vec<(String, String)>> {
+    let ast = analyze_code(code, config)?;
+    Ok(doc_generator::generate(name, &ast))
+}
+
+/// The type of the smallest expression containing the 1-indexed position, and its location
 #[must_use]
-pub fn run_exe(exe: &str) -> i32 {
-    let mut exe = PathBuf::from(exe);
+pub fn type_at(code: &str, line: usize, column: usize) -> Option<(SourceLocation, String)> {
+    let (mut ast, _) = analyze(code, &CompilerConfig::default()).ok()?;
+    analyzers::find_expression_type(&mut ast, line, column)
+        .map(|(location, type_information)| (location, type_information.name()))
+}
+
+/// Same as `check` but returns the warnings enabled in the config instead of printing them
+pub fn collect_warnings(
+    code: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<Vec<CompilerWarning>> {
+    analyze(code, config).map(|(_, warnings)| warnings)
+}
+
+/// Compiles multiple source files, given as `(file_name, code)`, into a single IR file.
+/// The files are analyzed together, so they can call each other's functions.
+/// Error messages are prefixed with the name of the file they come from, and `location.file` is
+/// its index in `files`.
+pub fn compile_multiple_to_ir(
+    name: &str,
+    files: &[(&str, &str)],
+    output: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<()> {
+    let mut merged = ast::File(Vec::new());
+    let mut defined_in: HashMap<String, &str> = HashMap::new();
+    let mut declared_in: HashMap<String, (&str, String)> = HashMap::new();
+    let source_dir = config.source_dir.clone().unwrap_or_default();
+
+    for (index, &(file_name, code)) in files.iter().enumerate() {
+        let in_file = move |(mut location, message): (SourceLocation, String)| {
+            location.file = index;
+            (location, format!("{file_name}: {message}"))
+        };
+        let code = parser::preprocess(code, &config.defines).map_err(in_file)?;
+        let mut tokens = parser::tokenize(&code).map_err(in_file)?;
+        // Every location in the AST comes from a token, so they all point at this file
+        for token in &mut tokens {
+            token.source_location.file = index;
+        }
+        let ast = parser::parse_tokens(tokens, &source_dir)
+            .map_err(|errors| in_file(parser::combine_errors(&code, errors)))?;
+
+        for statement in ast.0 {
+            match &statement {
+                ast::TopLevelStatement::FunctionDefinition {
+                    function_name,
+                    function_name_location,
+                    ..
+                }
+                | ast::TopLevelStatement::TestFunction {
+                    name: function_name,
+                    name_location: function_name_location,
+                    ..
+                } => {
+                    if let Some(other_file) = defined_in.insert(function_name.clone(), file_name) {
+                        return Err((
+                            *function_name_location,
+                            ErrorCode::DuplicateFunction.tag(format!(
+                                "{file_name}: Function {function_name} is already defined in {other_file}"
+                            )),
+                        ));
+                    }
+                }
+                // Each file declares the C functions and types it uses, the first one is kept
+                ast::TopLevelStatement::ExternFunction { .. }
+                | ast::TopLevelStatement::BitStruct { .. }
+                | ast::TopLevelStatement::TypeAlias { .. } => {
+                    let (name, location, signature) = shared_declaration(&statement);
+                    match declared_in.get(name) {
+                        Some((_, other_signature)) if *other_signature == signature => continue,
+                        Some((other_file, _)) => {
+                            return Err((
+                                location,
+                                ErrorCode::DuplicateFunction.tag(format!(
+                                    "{file_name}: {name} is declared differently in {other_file}"
+                                )),
+                            ));
+                        }
+                        None => {
+                            declared_in.insert(name.to_string(), (file_name, signature));
+                        }
+                    }
+                }
+            }
+
+            merged.0.push(statement);
+        }
+    }
+
+    let warnings = analyzers::apply_analyzer(&mut merged, config).map_err(|(location, message)| {
+        let file_name = files[location.file].0;
+        (location, format!("{file_name}: {message}"))
+    })?;
+    for warning in &warnings {
+        report_warning(files[warning.location().file].1, warning);
+    }
+
+    std::fs::write(output, compile_ast_to_ir(name, merged, config, false)?).unwrap();
+
+    Ok(())
+}
+
+/// The name, location and written out signature of a declaration that several files can repeat
+fn shared_declaration(statement: &ast::TopLevelStatement) -> (&str, SourceLocation, String) {
+    match statement {
+        ast::TopLevelStatement::ExternFunction {
+            function_name,
+            function_name_location,
+            parameters,
+            return_type_name,
+            ..
+        } => {
+            let parameters: Vec<&str> = parameters
+                .iter()
+                .map(|parameter| parameter.type_name.as_str())
+                .collect();
+            (
+                function_name,
+                *function_name_location,
+                format!("({}) -> {return_type_name}", parameters.join(", ")),
+            )
+        }
+        ast::TopLevelStatement::BitStruct {
+            name,
+            name_location,
+            fields,
+        } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{}: {}[{}]", field.name, field.type_name, field.width))
+                .collect();
+            (name, *name_location, fields.join(", "))
+        }
+        ast::TopLevelStatement::TypeAlias {
+            name,
+            name_location,
+            target_type,
+            ..
+        } => (name, *name_location, target_type.clone()),
+        ast::TopLevelStatement::FunctionDefinition { .. }
+        | ast::TopLevelStatement::TestFunction { .. } => {
+            unreachable!("Functions can not be declared more than once")
+        }
+    }
+}
+
+/// Analyzes the code and reports any warnings
+fn analyze_code(code: &str, config: &CompilerConfig) -> CompilerResult<ast::File> {
+    let (ast, warnings) = analyze(code, config)?;
+    for warning in &warnings {
+        report_warning(code, warning);
+    }
+
+    Ok(ast)
+}
+
+fn analyze(
+    code: &str,
+    config: &CompilerConfig,
+) -> CompilerResult<(ast::File, Vec<CompilerWarning>)> {
+    let code = timed(config, "preprocess", || parser::preprocess(code, &config.defines))?;
+    let tokens = timed(config, "lex", || parser::tokenize(&code))?;
+    let source_dir = config.source_dir.clone().unwrap_or_default();
+    let mut ast = timed(config, "parse", || parser::parse_tokens(tokens, &source_dir))
+        .map_err(|errors| parser::combine_errors(&code, errors))?;
+
+    if config.print_ast {
+        eprintln!("AST before analysis:\n{ast:#?}");
+    }
+    let warnings = timed(config, "analyze", || analyzers::apply_analyzer(&mut ast, config))?;
+    if config.print_ast {
+        eprintln!("AST after analysis:\n{ast:#?}");
+    }
+
+    Ok((ast, warnings))
+}
+
+fn compile_ast_to_ir(
+    name: &str,
+    ast: ast::File,
+    config: &CompilerConfig,
+    track_locations: bool,
+) -> CompilerResult<String> {
+    let ctx = llvm_generator::Compiler::create_context();
+    let mut compiler = llvm_generator::Compiler::new(name, &ctx);
+    if track_locations {
+        compiler.enable_location_tracking();
+    }
+    if let Some(target) = &config.target {
+        compiler.set_target(target)?;
+    }
+    if config.test {
+        compiler.enable_tests();
+    }
+    if config.bench {
+        compiler.enable_benchmarks();
+    }
+
+    timed(config, "codegen", || compiler.compile_code(ast));
+    // Invalid IR is a bug in the code generator, there is no location in the code to blame
+    compiler.verify().map_err(|err| {
+        (
+            SourceLocation::new(1, 1, 1),
+            ErrorCode::InternalError.tag(format!("generated invalid LLVM IR\n{err}")),
+        )
+    })?;
+    if config.optimize {
+        timed(config, "optimize", || compiler.optimize());
+    }
+    Ok(compiler.ir_string())
+}
+
+pub fn compile_to_obj(llc: PathBuf, from: &str, to: &str, config: &CompilerConfig) {
+    timed(config, "emit object", || {
+        let mut command = std::process::Command::new(llc);
+        command.args([from, "-filetype=obj", "-o", to]);
+        if let Some(target) = &config.target {
+            command.arg(format!("--mtriple={target}"));
+        }
+        if config.shared {
+            command.arg("-relocation-model=pic");
+        }
+
+        command
+            .spawn()
+            .unwrap()
+            .wait()
+            .unwrap()
+            .success()
+            .then_some(())
+            .expect("Non zero exit code");
+    });
+}
+
+pub fn compile_to_exe(
+    gcc: PathBuf,
+    from: &str,
+    to: &str,
+    config: &CompilerConfig,
+    link_config: &LinkConfig,
+) {
+    timed(config, "link", || {
+        std::process::Command::new(gcc)
+            .arg(from)
+            .args(&link_config.flags)
+            .args(["-o", to])
+            .spawn()
+            .unwrap()
+            .wait()
+            .unwrap()
+            .success()
+            .then_some(())
+            .expect("Non zero exit code");
+    });
+}
+
+fn exe_path(exe: &str) -> PathBuf {
+    let exe = PathBuf::from(exe);
 
     if exe.is_relative() {
-        exe = PathBuf::from(".").join(exe);
+        PathBuf::from(".").join(exe)
+    } else {
+        exe
     }
+}
 
-    let exit = std::process::Command::new(exe)
+#[must_use]
+pub fn run_exe(exe: &str) -> i32 {
+    let exit = std::process::Command::new(exe_path(exe))
         .spawn()
         .unwrap()
         .wait()
@@ -78,21 +563,49 @@ pub fn run_exe(exe: &str) -> i32 {
     exit.code().unwrap_or_else(|| exit.signal().unwrap())
 }
 
+/// Runs the executable and returns its exit code, stdout and stderr instead of printing them
+#[must_use]
+pub fn run_exe_capture(exe: &str, args: &[String]) -> (i32, String, String) {
+    let output = std::process::Command::new(exe_path(exe))
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    let exit_code = output
+        .status
+        .code()
+        .unwrap_or_else(|| output.status.signal().unwrap());
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    (exit_code, stdout, stderr)
+}
+
+/// Finds the first of the given executables, names containing a path separator are used as paths directly
 #[must_use]
 pub fn find_exe(possible_names: &[&str]) -> Option<PathBuf> {
-    possible_names
-        .iter()
-        .map(|name| find_on_path(name))
-        .find(std::option::Option::is_some)
-        .flatten()
+    possible_names.iter().find_map(|name| find_on_path(name))
 }
 
 #[must_use]
 fn find_on_path(name: &str) -> Option<PathBuf> {
-    let path_env = std::env::var("PATH").expect("PATH env var not found!");
-    let path_env = std::env::split_paths(&path_env);
+    if name.contains(std::path::is_separator) {
+        let path = PathBuf::from(name);
+        return is_executable(&path).then_some(path);
+    }
+
+    let path_env = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&path_env)
+        .map(|folder| folder.join(name))
+        .find(|to_check| is_executable(to_check))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
 
-    path_env
-        .map(|path| path.join(name))
-        .find(|to_check| to_check.exists())
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
 }
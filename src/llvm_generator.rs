@@ -1,21 +1,71 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet};
 
 use inkwell::{
+    attributes::{Attribute, AttributeLoc},
+    basic_block::BasicBlock,
     builder::Builder,
     context::Context,
-    module::Module,
+    module::{Linkage, Module},
     passes::PassManager,
-    types::{BasicType, BasicTypeEnum},
-    values::{BasicValue, BasicValueEnum, IntValue, PointerValue},
-    AddressSpace,
+    targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetTriple},
+    types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, IntType},
+    values::{
+        BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallableValue, FunctionValue,
+        InstructionValue, IntValue, PointerValue,
+    },
+    AddressSpace, OptimizationLevel,
 };
 
 use crate::ast::{self, Expression};
-use crate::types::TypeInformation;
+use crate::builtins::Builtin;
+use crate::diagnostics::ErrorCode;
+use crate::source_map::LOCATION_METADATA_KIND;
+use crate::types::{Ownership, TypeInformation};
+use crate::SourceLocation;
+
+/// The functions every module declares for the generated code to call, functions in the code can
+/// not have these names
+pub const RUNTIME_FUNCTIONS: [&str; 15] = [
+    "printf",
+    "dprintf",
+    "malloc",
+    "realloc",
+    "free",
+    "strlen",
+    "memcpy",
+    "strstr",
+    "strcmp",
+    "snprintf",
+    "scanf",
+    "exit",
+    "clock_gettime",
+    "abort",
+    "__run_tests",
+];
+
+/// Where `print` and `eprint` write to
+#[derive(Clone, Copy)]
+enum Output {
+    Stdout,
+    Stderr,
+}
+
+/// The variables declared in a scope, with their type and stack slot
+type Scope<'ctx> = HashMap<String, (TypeInformation, PointerValue<'ctx>)>;
 
 struct FunctionContext<'ctx> {
+    /// The type of every variable in the function, set by the type analyzer
     var_types: HashMap<String, TypeInformation>,
-    var_pointers: HashMap<String, PointerValue<'ctx>>,
+    /// Innermost last, a variable belongs to the scope it is first assigned in.
+    /// Loop bodies get their own scope, so their strings are freed after every iteration.
+    scopes: Vec<Scope<'ctx>>,
+    /// The block a break should jump to and the number of scopes outside the loop,
+    /// for each loop we are currently inside
+    loop_exits: Vec<(BasicBlock<'ctx>, usize)>,
+    /// Statements from `defer`, run in reverse order when the function returns
+    deferred: Vec<ast::Statement>,
+    /// The last instruction `tag_instructions` reached, the instructions before it have a location
+    last_tagged: Option<InstructionValue<'ctx>>,
 }
 
 pub struct Compiler<'ctx> {
@@ -25,6 +75,18 @@ pub struct Compiler<'ctx> {
     fpm: PassManager<Module<'ctx>>,
 
     function_context: Option<FunctionContext<'ctx>>,
+    /// Functions defined or declared in the code, as opposed to the C functions the builtins use,
+    /// with the type the code gave them
+    defined_functions: HashMap<String, FunctionType<'ctx>>,
+    /// Variadic functions defined in the code, they take the number of rest arguments as their
+    /// last fixed parameter
+    counted_variadics: HashSet<String>,
+    /// Compile the `#[test]` functions and run them at the start of `main`
+    run_tests: bool,
+    /// Compile `bench` statements, without this they compile to nothing
+    run_benchmarks: bool,
+    /// Attach the location of each statement to its instructions, used for source maps
+    track_locations: bool,
 }
 
 impl<'ctx> Compiler<'ctx> {
@@ -70,9 +132,55 @@ impl<'ctx> Compiler<'ctx> {
             builder,
             fpm,
             function_context: None,
+            defined_functions: HashMap::new(),
+            counted_variadics: HashSet::new(),
+            run_tests: false,
+            run_benchmarks: false,
+            track_locations: false,
         }
     }
 
+    pub fn enable_tests(&mut self) {
+        self.run_tests = true;
+    }
+
+    pub fn enable_benchmarks(&mut self) {
+        self.run_benchmarks = true;
+    }
+
+    pub fn enable_location_tracking(&mut self) {
+        self.track_locations = true;
+    }
+
+    /// Errors for target triples LLVM does not know, there is no location in the code to blame
+    pub fn set_target(&self, triple_name: &str) -> crate::CompilerResult<()> {
+        Target::initialize_all(&InitializationConfig::default());
+
+        let invalid_target = |reason: String| {
+            (
+                SourceLocation::new(1, 1, 1),
+                ErrorCode::InvalidTarget.tag(format!("Invalid target {triple_name}: {reason}")),
+            )
+        };
+        let triple = TargetTriple::create(triple_name);
+        let target = Target::from_triple(&triple).map_err(|err| invalid_target(err.to_string()))?;
+        let target_machine = target
+            .create_target_machine(
+                &triple,
+                "generic",
+                "",
+                OptimizationLevel::Default,
+                RelocMode::Default,
+                CodeModel::Default,
+            )
+            .ok_or_else(|| invalid_target("could not create a target machine".to_string()))?;
+
+        self.module.set_triple(&triple);
+        self.module
+            .set_data_layout(&target_machine.get_target_data().get_data_layout());
+        Ok(())
+    }
+
     fn compile_glibc_definitions(&self) {
         // types
         let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
@@ -86,6 +194,12 @@ impl<'ctx> Compiler<'ctx> {
         self.module
             .add_function("printf", printf_function_type, None);
 
+        // int dprintf( int fd, const char *format, ... );
+        let dprintf_argument_types = [i32_type.into(), i8_ptr_type.into()];
+        let dprintf_function_type = i32_type.fn_type(&dprintf_argument_types, true);
+        self.module
+            .add_function("dprintf", dprintf_function_type, None);
+
         // void* malloc( size_t size );
         let malloc_argument_types = [size_type.into()];
         let malloc_function_type = i8_ptr_type.fn_type(&malloc_argument_types, false);
@@ -115,62 +229,213 @@ impl<'ctx> Compiler<'ctx> {
         self.module
             .add_function("memcpy", memcpy_function_type, None);
 
+        // char *strstr( const char *str, const char *substr );
+        let strstr_argument_types = [i8_ptr_type.into(), i8_ptr_type.into()];
+        let strstr_function_type = i8_ptr_type.fn_type(&strstr_argument_types, false);
+        self.module
+            .add_function("strstr", strstr_function_type, None);
+
+        // int strcmp( const char *lhs, const char *rhs );
+        let strcmp_argument_types = [i8_ptr_type.into(), i8_ptr_type.into()];
+        let strcmp_function_type = i32_type.fn_type(&strcmp_argument_types, false);
+        self.module
+            .add_function("strcmp", strcmp_function_type, None);
+
+        // int snprintf( char *buffer, size_t bufsz, const char *format, ... );
+        let snprintf_argument_types = [i8_ptr_type.into(), size_type.into(), i8_ptr_type.into()];
+        let snprintf_function_type = i32_type.fn_type(&snprintf_argument_types, true);
+        self.module
+            .add_function("snprintf", snprintf_function_type, None);
+
+        // int scanf( const char *format, ... );
+        let scanf_argument_types = [i8_ptr_type.into()];
+        let scanf_function_type = i32_type.fn_type(&scanf_argument_types, true);
+        self.module.add_function("scanf", scanf_function_type, None);
+
+        // _Noreturn void exit( int exit_code );
+        let exit_argument_types = [i32_type.into()];
+        let exit_function_type = void_type.fn_type(&exit_argument_types, false);
+        self.module.add_function("exit", exit_function_type, None);
+
+        // int clock_gettime( clockid_t clock_id, struct timespec *tp );
+        let clock_gettime_argument_types = [i32_type.into(), i8_ptr_type.into()];
+        let clock_gettime_function_type = i32_type.fn_type(&clock_gettime_argument_types, false);
+        self.module
+            .add_function("clock_gettime", clock_gettime_function_type, None);
+
         // _Noreturn void abort(void);
         let abort_argument_types = [];
         let abort_function_type = void_type.fn_type(&abort_argument_types, false);
         self.module.add_function("abort", abort_function_type, None);
     }
 
-    fn get_type_for(&self, type_: TypeInformation) -> BasicTypeEnum<'ctx> {
+    /// Strings shared by every print site, instead of a new global each time
+    fn compile_constant_strings(&self) {
+        for (name, value) in [("True_String", "true"), ("False_String", "false")] {
+            let string = self.context.const_string(value.as_bytes(), true);
+            let global = self.module.add_global(string.get_type(), None, name);
+            global.set_initializer(&string);
+            global.set_constant(true);
+            global.set_linkage(Linkage::Private);
+            global.set_unnamed_addr(true);
+        }
+    }
+
+    fn compile_intrinsic_definitions(&self) {
+        // types
+        let i32_type = self.context.i32_type();
+        let bool_type = self.context.bool_type();
+
+        // {i32, i1} llvm.s*.with.overflow.i32(i32, i32)
+        let overflow_return_type = self
+            .context
+            .struct_type(&[i32_type.into(), bool_type.into()], false);
+        let overflow_function_type =
+            overflow_return_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+        for name in [
+            "llvm.sadd.with.overflow.i32",
+            "llvm.ssub.with.overflow.i32",
+            "llvm.smul.with.overflow.i32",
+        ] {
+            self.module.add_function(name, overflow_function_type, None);
+        }
+
+        // i32 llvm.s*.sat.i32(i32, i32)
+        let saturating_function_type = i32_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+        self.module
+            .add_function("llvm.sadd.sat.i32", saturating_function_type, None);
+        self.module
+            .add_function("llvm.ssub.sat.i32", saturating_function_type, None);
+
+        // There is no plain saturating multiplication, but fixed point with a scale of 0 is the same thing
+        // i32 llvm.smul.fix.sat.i32(i32, i32, i32 scale)
+        let fixed_point_function_type =
+            i32_type.fn_type(&[i32_type.into(), i32_type.into(), i32_type.into()], false);
+        self.module
+            .add_function("llvm.smul.fix.sat.i32", fixed_point_function_type, None);
+
+        // void llvm.va_start(i8* list) and void llvm.va_end(i8* list)
+        let va_function_type = self
+            .context
+            .void_type()
+            .fn_type(&[self.context.i8_type().ptr_type(AddressSpace::Generic).into()], false);
+        self.module
+            .add_function("llvm.va_start", va_function_type, None);
+        self.module
+            .add_function("llvm.va_end", va_function_type, None);
+    }
+
+    fn get_type_for(&self, type_: &TypeInformation) -> BasicTypeEnum<'ctx> {
         match type_ {
-            TypeInformation::Number => self.context.i32_type().as_basic_type_enum(),
+            TypeInformation::Number | TypeInformation::VarArgs => {
+                self.context.i32_type().as_basic_type_enum()
+            }
+            TypeInformation::Number8 => self.context.i8_type().as_basic_type_enum(),
+            TypeInformation::Number16 => self.context.i16_type().as_basic_type_enum(),
             TypeInformation::Boolean => self.context.bool_type().as_basic_type_enum(),
             TypeInformation::String(_) => self
                 .context
                 .i8_type()
                 .ptr_type(AddressSpace::Generic)
                 .as_basic_type_enum(),
+            TypeInformation::StringSlice => {
+                let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+                let i32_type = self.context.i32_type();
+                self.context
+                    .struct_type(&[i8_ptr_type.into(), i32_type.into()], false)
+                    .as_basic_type_enum()
+            }
+            TypeInformation::Pointer(type_) => self
+                .get_type_for(type_)
+                .ptr_type(AddressSpace::Generic)
+                .as_basic_type_enum(),
+            TypeInformation::Array { element, size } => self
+                .get_type_for(element)
+                .array_type(u32::try_from(*size).unwrap())
+                .as_basic_type_enum(),
+            TypeInformation::BitStruct { fields, .. } => {
+                let width: usize = fields.iter().map(|(_, _, width)| width).sum();
+                self.context
+                    .custom_width_int_type(u32::try_from(width).unwrap())
+                    .as_basic_type_enum()
+            }
+            TypeInformation::Tuple(elements) => {
+                let element_types: Vec<BasicTypeEnum> =
+                    elements.iter().map(|type_| self.get_type_for(type_)).collect();
+                self.context
+                    .struct_type(&element_types, false)
+                    .as_basic_type_enum()
+            }
+            TypeInformation::Void => unreachable!("Void values are never stored"),
         }
     }
 
-    fn free_if_needed(&self, value: BasicValueEnum, type_: TypeInformation) {
-        if let TypeInformation::String(true) = type_ {
+    fn free_if_needed(&self, value: BasicValueEnum, type_: &TypeInformation) {
+        if matches!(type_, TypeInformation::String(ownership) if ownership.is_owned()) {
             let free_function = self.module.get_function("free").unwrap();
             self.builder
                 .build_call(free_function, &[value.into()], "Free_Tmp_String");
         }
     }
 
-    fn get_owned_string(&self, value: BasicValueEnum<'ctx>) -> BasicValueEnum<'ctx> {
+    /// Bytes needed to store the string, including the null terminator
+    fn build_string_size(&self, value: BasicValueEnum<'ctx>) -> IntValue<'ctx> {
         let strlen = self.module.get_function("strlen").unwrap();
         let string_length = self
             .builder
-            .build_call(strlen, &[value.into()], "String_Len")
+            .build_call(strlen, &[value.into()], "String_Length")
             .try_as_basic_value()
-            .unwrap_left();
+            .unwrap_left()
+            .into_int_value();
+
+        let size_t = self.context.i64_type();
+        self.builder
+            .build_int_add(string_length, size_t.const_int(1, false), "String_Size")
+    }
+
+    fn get_owned_string(&self, value: BasicValueEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        let string_size = self.build_string_size(value);
 
         let malloc = self.module.get_function("malloc").unwrap();
         let heap_pointer = self
             .builder
-            .build_call(malloc, &[string_length.into()], "Heap_Pointer")
+            .build_call(malloc, &[string_size.into()], "Heap_Pointer")
             .try_as_basic_value()
             .unwrap_left();
 
         let memcpy = self.module.get_function("memcpy").unwrap();
-        self.builder
-            .build_call(memcpy, &[heap_pointer.into(), value.into()], "Malloc");
+        self.builder.build_call(
+            memcpy,
+            &[heap_pointer.into(), value.into(), string_size.into()],
+            "Memcpy",
+        );
 
         heap_pointer
     }
 
-    fn compile_literal(&self, lit: &ast::LiteralType) -> BasicValueEnum<'ctx> {
+    /// Sign extends numbers stored in fewer bits to a Num, other values are returned as is
+    fn widen(&self, value: BasicValueEnum<'ctx>, type_: &TypeInformation) -> BasicValueEnum<'ctx> {
+        match type_ {
+            TypeInformation::Number8 | TypeInformation::Number16 => self
+                .builder
+                .build_int_s_extend(value.into_int_value(), self.context.i32_type(), "Widen")
+                .as_basic_value_enum(),
+            _ => value,
+        }
+    }
+
+    fn compile_literal(
+        &self,
+        lit: &ast::LiteralType,
+        type_: &TypeInformation,
+    ) -> BasicValueEnum<'ctx> {
         match lit {
-            ast::LiteralType::Number(value) => {
-                let i32_type = self.context.i32_type();
-                i32_type
-                    .const_int(*value as u64, false)
-                    .as_basic_value_enum()
-            }
+            // Number literals assigned to a small variable are stored in fewer bits
+            ast::LiteralType::Number(value) => self
+                .get_type_for(type_)
+                .into_int_type()
+                .const_int(*value as u64, false)
+                .as_basic_value_enum(),
             ast::LiteralType::String(value) => {
                 let global_string =
                     unsafe { self.builder.build_global_string(value, "Literal_String") };
@@ -188,7 +453,7 @@ impl<'ctx> Compiler<'ctx> {
 
     fn compile_expression(&self, exp: &ast::Expression) -> BasicValueEnum<'ctx> {
         match exp {
-            ast::Expression::Literal(_, lit) => self.compile_literal(lit),
+            ast::Expression::Literal(_, lit) => self.compile_literal(lit, exp.type_info()),
             ast::Expression::Binary {
                 metadata: _,
                 left,
@@ -228,33 +493,53 @@ impl<'ctx> Compiler<'ctx> {
                 let mut bool_values: Vec<IntValue> = Vec::with_capacity(comparisons.len());
 
                 let mut left = self.compile_expression(first_element);
-                let mut comparisons = VecDeque::from(comparisons.clone());
+                let mut left_type = first_element.type_info();
 
                 // Calculate comparisons
-                while !comparisons.is_empty() {
-                    let (comp, right) = comparisons.pop_front().unwrap();
-                    let right = self.compile_expression(&right);
+                for &(comp, ref right_expression) in comparisons {
+                    let right = self.compile_expression(right_expression);
 
                     let bool_value = match first_element.type_info() {
-                        TypeInformation::Number => self.builder.build_int_compare(
-                            match comp {
-                                ast::Comparison::Equal => inkwell::IntPredicate::EQ,
-                                ast::Comparison::NotEqual => inkwell::IntPredicate::NE,
-                                ast::Comparison::GreaterThan => inkwell::IntPredicate::SGT,
-                                ast::Comparison::GreaterThanEqual => inkwell::IntPredicate::SGE,
-                                ast::Comparison::LessThan => inkwell::IntPredicate::SLT,
-                                ast::Comparison::LessThanEqual => inkwell::IntPredicate::SLE,
-                            },
-                            left.into_int_value(),
-                            right.into_int_value(),
-                            "Comparison_Chain",
-                        ),
-                        TypeInformation::Boolean => unreachable!(),
-                        TypeInformation::String(_) => unreachable!(),
+                        // Booleans only allow == and !=, which work the same for i1
+                        TypeInformation::Number | TypeInformation::Boolean => {
+                            self.builder.build_int_compare(
+                                match comp {
+                                    ast::Comparison::Equal => inkwell::IntPredicate::EQ,
+                                    ast::Comparison::NotEqual => inkwell::IntPredicate::NE,
+                                    ast::Comparison::GreaterThan => inkwell::IntPredicate::SGT,
+                                    ast::Comparison::GreaterThanEqual => inkwell::IntPredicate::SGE,
+                                    ast::Comparison::LessThan => inkwell::IntPredicate::SLT,
+                                    ast::Comparison::LessThanEqual => inkwell::IntPredicate::SLE,
+                                    ast::Comparison::Contains | ast::Comparison::NotContains => {
+                                        unreachable!("Only valid for strings")
+                                    }
+                                },
+                                left.into_int_value(),
+                                right.into_int_value(),
+                                "Comparison_Chain",
+                            )
+                        }
+                        TypeInformation::String(_) => {
+                            self.compile_string_contains(comp, left, right)
+                        }
+                        TypeInformation::Number8
+                        | TypeInformation::Number16
+                        | TypeInformation::StringSlice
+                        | TypeInformation::Pointer(_)
+                        | TypeInformation::Array { .. }
+                        | TypeInformation::Tuple(_)
+                        | TypeInformation::BitStruct { .. }
+                        | TypeInformation::Void
+                        | TypeInformation::VarArgs => unreachable!(),
                     };
                     bool_values.push(bool_value);
+
+                    // Every element is compared with the ones next to it, so this one is done
+                    self.free_if_needed(left, left_type);
                     left = right;
+                    left_type = right_expression.type_info();
                 }
+                self.free_if_needed(left, left_type);
 
                 // 1 == 2 == 3
                 // at this point we have the result of 1 == 2, 2 == 3
@@ -268,265 +553,1227 @@ impl<'ctx> Compiler<'ctx> {
                     .as_basic_value_enum()
             }
             ast::Expression::Var(_, ref name) => {
-                let function_context = self.function_context.as_ref().unwrap();
-                let stack_ptr = function_context.var_pointers.get(name).unwrap();
+                let (type_, stack_ptr) = self.variable(name);
 
-                match exp.type_info() {
+                match type_ {
+                    TypeInformation::Number8 | TypeInformation::Number16 => {
+                        let value = self.builder.build_load(stack_ptr, "Var_Load");
+                        self.widen(value, type_)
+                    }
                     TypeInformation::Number
                     | TypeInformation::Boolean
-                    | TypeInformation::String(_) => self.builder.build_load(*stack_ptr, "Var_Load"),
+                    | TypeInformation::String(_)
+                    | TypeInformation::StringSlice
+                    | TypeInformation::Pointer(_)
+                    | TypeInformation::Array { .. }
+                    | TypeInformation::Tuple(_)
+                    | TypeInformation::BitStruct { .. }
+                    | TypeInformation::VarArgs => {
+                        self.builder.build_load(stack_ptr, "Var_Load")
+                    }
+                    TypeInformation::Void => unreachable!("Void can not be assigned"),
                 }
             },
+            ast::Expression::PrefixExpression {
+                op: ast::PrefixOprator::AddressOf,
+                expression,
+                ..
+            } => self.compile_address_of(expression),
             ast::Expression::PrefixExpression { op, expression, .. } => {
                 let value = self.compile_expression(expression);
                 match expression.type_info() {
                     TypeInformation::Boolean => match op {
-                        ast::PrefixOprator::Not => self.builder.build_not(value.into_int_value(), "Not").as_basic_value_enum()
+                        ast::PrefixOprator::Not => self.builder.build_not(value.into_int_value(), "Not").as_basic_value_enum(),
+                        _ => unreachable!(),
+                    },
+                    TypeInformation::Number
+                    | TypeInformation::Number8
+                    | TypeInformation::Number16 => match op {
+                        ast::PrefixOprator::Negate => self.builder.build_int_neg(value.into_int_value(), "Negate").as_basic_value_enum(),
+                        _ => unreachable!(),
+                    },
+                    TypeInformation::Pointer(pointee) => match op {
+                        ast::PrefixOprator::Deref => {
+                            let pointer = value.into_pointer_value();
+                            let value = self.builder.build_load(pointer, "Deref");
+                            self.widen(value, pointee)
+                        }
+                        _ => unreachable!(),
                     },
                     _ => unreachable!()
                 }
             }
+            ast::Expression::Array(_, elements) => {
+                let array_type = self.get_type_for(exp.type_info()).into_array_type();
+                let mut array = array_type.get_undef();
+                for (index, element) in elements.iter().enumerate() {
+                    let value = self.compile_expression(element);
+                    array = self
+                        .builder
+                        .build_insert_value(array, value, u32::try_from(index).unwrap(), "Array")
+                        .unwrap()
+                        .into_array_value();
+                }
+                array.as_basic_value_enum()
+            }
+            ast::Expression::Tuple(_, elements) => {
+                let tuple_type = self.get_type_for(exp.type_info()).into_struct_type();
+                let mut tuple = tuple_type.get_undef();
+                for (index, element) in elements.iter().enumerate() {
+                    let value = self.compile_expression(element);
+                    tuple = self
+                        .builder
+                        .build_insert_value(tuple, value, u32::try_from(index).unwrap(), "Tuple")
+                        .unwrap()
+                        .into_struct_value();
+                }
+                tuple.as_basic_value_enum()
+            }
+            ast::Expression::TupleIndex { tuple, index, .. } => {
+                let tuple = self.compile_expression(tuple).into_struct_value();
+                self.builder
+                    .build_extract_value(tuple, u32::try_from(*index).unwrap(), "Tuple_Element")
+                    .unwrap()
+            }
+            ast::Expression::FunctionCall {
+                name, arguments, ..
+            } => self.compile_function_call(name, arguments),
         }
     }
 
-    fn compile_printf(&self, format_string: &str, value: BasicValueEnum) {
-        let printf_function = self.module.get_function("printf").unwrap();
-        let format_string = unsafe {
-            self.builder
-                .build_global_string(format_string, "Print_Format_String")
-        };
-        let printf_arguments = [
-            // Format string
-            self.builder
-                .build_pointer_cast(
-                    format_string.as_pointer_value(),
-                    self.context.i8_type().ptr_type(AddressSpace::Generic),
-                    "Format",
-                )
-                .into(),
-            value.into(),
-        ];
+    /// Variables give the address of their own stack slot, other values are copied to the stack
+    fn compile_address_of(&self, expression: &Expression) -> BasicValueEnum<'ctx> {
+        if let Expression::Var(_, name) = expression {
+            return self.variable(name).1.as_basic_value_enum();
+        }
 
-        self.builder
-            .build_call(printf_function, &printf_arguments, "Print_Statement");
+        let value = self.compile_expression(expression);
+        let stack_pointer = self.build_entry_alloca(value.get_type(), "Address_Of");
+        self.builder.build_store(stack_pointer, value);
+        stack_pointer.as_basic_value_enum()
     }
 
-    fn compile_print_number(&self, value: BasicValueEnum) {
-        self.compile_printf("%d\n", value);
-    }
+    /// Allocates stack space in the entry block of the current function,
+    /// so allocations inside a loop do not grow the stack on every iteration
+    fn build_entry_alloca<T: BasicType<'ctx>>(&self, type_: T, name: &str) -> PointerValue<'ctx> {
+        let current_block = self.builder.get_insert_block().unwrap();
+        let entry_block = current_block
+            .get_parent()
+            .unwrap()
+            .get_first_basic_block()
+            .unwrap();
+        match entry_block.get_first_instruction() {
+            Some(instruction) => self.builder.position_before(&instruction),
+            None => self.builder.position_at_end(entry_block),
+        }
 
-    fn compile_print_string(&self, type_: TypeInformation, value: BasicValueEnum) {
-        self.compile_printf("%s\n", value);
-        self.free_if_needed(value, type_);
+        let stack_pointer = self.builder.build_alloca(type_, name);
+        self.builder.position_at_end(current_block);
+        stack_pointer
     }
 
-    fn compile_print_bool(&self, value: BasicValueEnum) {
-        let current_location = self.builder.get_insert_block().unwrap();
-        let true_branch = self
-            .context
-            .insert_basic_block_after(current_location, "True_Branch");
-        let false_branch = self
-            .context
-            .insert_basic_block_after(true_branch, "False_Branch");
-        let continue_branch = self
-            .context
-            .insert_basic_block_after(false_branch, "Continue_Branch");
+    fn compile_function_call(&self, name: &str, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        // Functions defined in the code take priority over builtins with the same name
+        if self.defined_functions.contains_key(name) {
+            return self.compile_user_function_call(name, arguments);
+        }
 
-        // Jump
-        self.builder
-            .build_conditional_branch(value.into_int_value(), true_branch, false_branch);
+        let builtin = Builtin::from_name(name).unwrap();
+        match builtin {
+            Builtin::Substr => {
+                let string = self.compile_expression(&arguments[0]).into_pointer_value();
+                let start = self.compile_expression(&arguments[1]).into_int_value();
+                let length = self.compile_expression(&arguments[2]).into_int_value();
+
+                // Clamped to the string, so the slice never reads past its end
+                let zero = self.context.i32_type().const_zero();
+                let string_length = self.build_string_length(string.as_basic_value_enum());
+                let start = self.build_clamp(start, zero, string_length);
+                let remaining = self.builder.build_int_sub(string_length, start, "Remaining");
+                let length = self.build_clamp(length, zero, remaining);
+
+                let slice_start = unsafe { self.builder.build_gep(string, &[start], "Slice_Start") };
+                self.build_string_slice(slice_start, length)
+            }
+            Builtin::WrappingAdd | Builtin::WrappingSub | Builtin::WrappingMul => {
+                let left = self.compile_expression(&arguments[0]).into_int_value();
+                let right = self.compile_expression(&arguments[1]).into_int_value();
+
+                // LLVM integer arithmetic wraps as long as we dont mark it as nsw
+                match builtin {
+                    Builtin::WrappingAdd => self.builder.build_int_add(left, right, "Wrapping_Add"),
+                    Builtin::WrappingSub => self.builder.build_int_sub(left, right, "Wrapping_Sub"),
+                    _ => self.builder.build_int_mul(left, right, "Wrapping_Mul"),
+                }
+                .as_basic_value_enum()
+            }
+            Builtin::CheckedAdd | Builtin::CheckedSub | Builtin::CheckedMul => {
+                let intrinsic_name = match builtin {
+                    Builtin::CheckedAdd => "llvm.sadd.with.overflow.i32",
+                    Builtin::CheckedSub => "llvm.ssub.with.overflow.i32",
+                    _ => "llvm.smul.with.overflow.i32",
+                };
+                let intrinsic = self.module.get_function(intrinsic_name).unwrap();
+
+                let left = self.compile_expression(&arguments[0]);
+                let right = self.compile_expression(&arguments[1]);
+                let result = self
+                    .builder
+                    .build_call(intrinsic, &[left.into(), right.into()], "Checked_Result")
+                    .try_as_basic_value()
+                    .unwrap_left()
+                    .into_struct_value();
+
+                self.builder
+                    .build_extract_value(result, 1, "Checked_Overflow")
+                    .unwrap()
+            }
+            Builtin::SaturatingAdd | Builtin::SaturatingSub | Builtin::SaturatingMul => {
+                let left = self.compile_expression(&arguments[0]);
+                let right = self.compile_expression(&arguments[1]);
+
+                let call = match builtin {
+                    Builtin::SaturatingAdd => self.builder.build_call(
+                        self.module.get_function("llvm.sadd.sat.i32").unwrap(),
+                        &[left.into(), right.into()],
+                        "Saturating_Add",
+                    ),
+                    Builtin::SaturatingSub => self.builder.build_call(
+                        self.module.get_function("llvm.ssub.sat.i32").unwrap(),
+                        &[left.into(), right.into()],
+                        "Saturating_Sub",
+                    ),
+                    _ => {
+                        let scale = self.context.i32_type().const_zero();
+                        self.builder.build_call(
+                            self.module.get_function("llvm.smul.fix.sat.i32").unwrap(),
+                            &[left.into(), right.into(), scale.into()],
+                            "Saturating_Mul",
+                        )
+                    }
+                };
+                call.try_as_basic_value().unwrap_left()
+            }
+            Builtin::Input => {
+                const BUFFER_SIZE: u64 = 1024;
+
+                let malloc = self.module.get_function("malloc").unwrap();
+                let size = self.context.i64_type().const_int(BUFFER_SIZE, false);
+                let buffer = self
+                    .builder
+                    .build_call(malloc, &[size.into()], "Input_Buffer")
+                    .try_as_basic_value()
+                    .unwrap_left();
+                // scanf leaves the buffer alone when it fails or hits the end of the input
+                self.builder
+                    .build_store(buffer.into_pointer_value(), self.context.i8_type().const_zero());
+
+                // Stops at the first whitespace, and leaves room for the null terminator
+                let format = format!("%{}s", BUFFER_SIZE - 1);
+                self.compile_scanf(&format, buffer);
+                buffer
+            }
+            Builtin::Exit => {
+                let exit_code = self.compile_expression(&arguments[0]);
+                let exit = self.module.get_function("exit").unwrap();
+                self.builder.build_call(exit, &[exit_code.into()], "Exit");
+                self.builder.build_unreachable();
+
+                // Like after a break, the dead code after the exit needs a block to live in
+                let current_block = self.builder.get_insert_block().unwrap();
+                let dead_block = self
+                    .context
+                    .insert_basic_block_after(current_block, "After_Exit");
+                self.builder.position_at_end(dead_block);
+
+                // Void is never used, so any value will do
+                self.context.i32_type().const_zero().as_basic_value_enum()
+            }
+            Builtin::Len => match arguments[0].type_info() {
+                // The rest parameter holds the number of arguments
+                TypeInformation::VarArgs => self.compile_expression(&arguments[0]),
+                TypeInformation::Array { size, .. } => self
+                    .context
+                    .i32_type()
+                    .const_int(u64::try_from(*size).unwrap(), false)
+                    .as_basic_value_enum(),
+                _ => self.compile_string_length(&arguments[0]),
+            },
+            Builtin::CharCount => self.compile_string_length(&arguments[0]),
+            Builtin::CharAt => {
+                let string = self.compile_expression(&arguments[0]);
+                let index = self.compile_expression(&arguments[1]).into_int_value();
+                self.build_char_index_check("char_at", string, index, arguments[1].location());
+                let char_pointer = unsafe {
+                    self.builder
+                        .build_gep(string.into_pointer_value(), &[index], "Char_Pointer")
+                };
+                let char = self.builder.build_load(char_pointer, "Char").into_int_value();
+                self.free_if_needed(string, arguments[0].type_info());
 
-        // True
-        self.builder.position_at_end(true_branch);
-        let true_string = unsafe {
-            self.builder
-                .build_global_string("true", "True_String")
-                .as_pointer_value()
-        };
-        self.compile_printf("%s\n", true_string.as_basic_value_enum());
-        self.builder.build_unconditional_branch(continue_branch);
+                self.builder
+                    .build_int_z_extend(char, self.context.i32_type(), "Char_Code")
+                    .as_basic_value_enum()
+            }
+            Builtin::SetChar => {
+                // Always a variable, so the string is the copy it owns
+                let string = self.compile_expression(&arguments[0]).into_pointer_value();
+                let index = self.compile_expression(&arguments[1]).into_int_value();
+                let value = self.compile_expression(&arguments[2]).into_int_value();
+                let location = arguments[1].location();
+                self.build_char_index_check("set_char", string.into(), index, location);
+                let char_pointer =
+                    unsafe { self.builder.build_gep(string, &[index], "Char_Pointer") };
+                let char = self
+                    .builder
+                    .build_int_truncate(value, self.context.i8_type(), "Char");
+                self.builder.build_store(char_pointer, char);
+
+                // Void is never used, so any value will do
+                self.context.i32_type().const_zero().as_basic_value_enum()
+            }
+            Builtin::InputNum => {
+                let number = self.build_entry_alloca(self.context.i32_type(), "Input_Number");
+                // Like the buffer of input, this is what is left when scanf reads nothing
+                self.builder.build_store(number, self.context.i32_type().const_zero());
+                self.compile_scanf("%d", number.as_basic_value_enum());
+                self.builder.build_load(number, "Input_Number")
+            }
+            Builtin::Format => self.compile_format(arguments),
+            Builtin::TypeofStr => {
+                let value = self.compile_expression(&arguments[0]);
+                self.free_if_needed(value, arguments[0].type_info());
 
-        // False
-        self.builder.position_at_end(false_branch);
-        let false_string = unsafe {
-            self.builder
-                .build_global_string("false", "False_String")
-                .as_pointer_value()
-        };
-        self.compile_printf("%s\n", false_string.as_basic_value_enum());
-        self.builder.build_unconditional_branch(continue_branch);
+                let type_name = arguments[0].type_info().name();
+                let global_string =
+                    unsafe { self.builder.build_global_string(&type_name, "Type_Name") };
+                global_string.as_pointer_value().as_basic_value_enum()
+            }
+            Builtin::GetField => self.compile_get_field(arguments),
+            Builtin::SetField => self.compile_set_field(arguments),
+            Builtin::Range => self.compile_range(arguments),
+            Builtin::GetBits => self.compile_get_bits(arguments),
+            Builtin::SetBits => self.compile_set_bits(arguments),
+        }
+    }
 
-        // Continue
-        self.builder.position_at_end(continue_branch);
+    /// `width` ones in the lowest bits, none for a width of 0 and all of them for 32 or more
+    fn build_bit_mask(&self, width: IntValue<'ctx>) -> IntValue<'ctx> {
+        let number_type = self.context.i32_type();
+        let unused_bits =
+            self.builder
+                .build_int_sub(number_type.const_int(32, false), width, "Unused_Bits");
+        self.build_shift(number_type.const_all_ones(), unused_bits, false)
     }
 
-    fn compile_print(&self, expression: &ast::Expression) {
-        let value = self.compile_expression(expression);
-        let type_ = expression.type_info();
+    /// A logical shift where shifting by 32 or more moves every bit out, and a negative amount
+    /// does not shift. LLVM makes those shifts poison, so the amount is clamped first
+    fn build_shift(
+        &self,
+        value: IntValue<'ctx>,
+        amount: IntValue<'ctx>,
+        left: bool,
+    ) -> IntValue<'ctx> {
+        let number_type = self.context.i32_type();
+        let max_amount = number_type.const_int(31, false);
+        let clamped = self.build_clamp(amount, number_type.const_zero(), max_amount);
+        let shifted = if left {
+            self.builder.build_left_shift(value, clamped, "Shifted")
+        } else {
+            self.builder.build_right_shift(value, clamped, false, "Shifted")
+        };
 
-        match type_ {
-            TypeInformation::Number => self.compile_print_number(value),
-            TypeInformation::String(_) => self.compile_print_string(*type_, value),
-            TypeInformation::Boolean => self.compile_print_bool(value),
-        }
+        let too_far = self.builder.build_int_compare(
+            inkwell::IntPredicate::SGT,
+            amount,
+            max_amount,
+            "Shifted_Out",
+        );
+        self.builder
+            .build_select(too_far, number_type.const_zero(), shifted, "Shift")
+            .into_int_value()
     }
 
-    fn compile_var_allocations(&mut self) {
-        let function_context = self.function_context.as_mut().unwrap();
-        for name in function_context.var_types.keys() {
-            let type_ = function_context.var_types.get(name).unwrap();
-
-            let pointer = match type_ {
-                TypeInformation::Number => {
-                    let i32_type = self.context.i32_type();
-                    self.builder.build_alloca(i32_type, "Stack_Pointer")
-                }
-                TypeInformation::Boolean => {
-                    let bool_type = self.context.bool_type();
-                    self.builder.build_alloca(bool_type, "Stack_Pointer")
-                }
-                TypeInformation::String(_) => {
-                    let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
-                    let size_t = self.context.i64_type();
+    /// `(value >> offset) & mask`
+    fn compile_get_bits(&self, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        let value = self.compile_expression(&arguments[0]).into_int_value();
+        let offset = self.compile_expression(&arguments[1]).into_int_value();
+        let width = self.compile_expression(&arguments[2]).into_int_value();
 
-                    let stack_pointer = self.builder.build_alloca(i8_ptr_type, "Stack_Pointer");
+        let shifted = self.build_shift(value, offset, false);
+        self.builder
+            .build_and(shifted, self.build_bit_mask(width), "Bits")
+            .as_basic_value_enum()
+    }
 
-                    let malloc_function = self.module.get_function("malloc").unwrap();
-                    let malloc_arguments = [size_t.const_int(0, false).into()];
-                    let heap_pointer =
-                        self.builder
-                            .build_call(malloc_function, &malloc_arguments, "Heap_Pointer");
+    /// `(value & !(mask << offset)) | ((field & mask) << offset)`
+    fn compile_set_bits(&self, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        let value = self.compile_expression(&arguments[0]).into_int_value();
+        let offset = self.compile_expression(&arguments[1]).into_int_value();
+        let width = self.compile_expression(&arguments[2]).into_int_value();
+        let field = self.compile_expression(&arguments[3]).into_int_value();
+
+        let mask = self.build_bit_mask(width);
+        let shifted_mask = self.build_shift(mask, offset, true);
+        let cleared = self.builder.build_and(
+            value,
+            self.builder.build_not(shifted_mask, "Bits_Kept"),
+            "Bits_Cleared",
+        );
 
-                    self.builder.build_store(
-                        stack_pointer,
-                        heap_pointer.try_as_basic_value().unwrap_left(),
-                    );
+        let new_bits = self.builder.build_and(field, mask, "New_Bits");
+        let new_bits = self.build_shift(new_bits, offset, true);
+        self.builder
+            .build_or(cleared, new_bits, "Bits_Set")
+            .as_basic_value_enum()
+    }
 
-                    stack_pointer
-                }
-            };
+    /// Counts the bytes with `strlen`
+    fn compile_string_length(&self, argument: &Expression) -> BasicValueEnum<'ctx> {
+        let string = self.compile_expression(argument);
+        let length = self.build_string_length(string);
+        self.free_if_needed(string, argument.type_info());
+        length.as_basic_value_enum()
+    }
 
-            function_context.var_pointers.insert(name.clone(), pointer);
-        }
+    /// `strlen` of the string as a Num
+    fn build_string_length(&self, string: BasicValueEnum<'ctx>) -> IntValue<'ctx> {
+        let strlen = self.module.get_function("strlen").unwrap();
+        let length = self
+            .builder
+            .build_call(strlen, &[string.into()], "String_Length")
+            .try_as_basic_value()
+            .unwrap_left()
+            .into_int_value();
+        self.builder
+            .build_int_truncate(length, self.context.i32_type(), "Length")
     }
 
-    fn compile_assignment(&mut self, name: &str, expr: &ast::Expression) {
-        let function_context = self.function_context.as_ref().unwrap();
-        let type_ = function_context.var_types.get(name).unwrap();
-        let pointer = function_context.var_pointers.get(name).unwrap();
+    /// Aborts with a message unless `0 <= index < strlen(string)`
+    fn build_char_index_check(
+        &self,
+        name: &str,
+        string: BasicValueEnum<'ctx>,
+        index: IntValue<'ctx>,
+        location: &SourceLocation,
+    ) {
+        let line_num = location.line_start;
+        let length = self.build_string_length(string);
+        // A negative index is a huge unsigned one, so one compare covers both ends
+        let in_bounds =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::ULT, index, length, "In_Bounds");
 
-        let expr_value = self.compile_expression(expr);
+        let current_block = self.builder.get_insert_block().unwrap();
+        let abort_block = self
+            .context
+            .insert_basic_block_after(current_block, &format!("{line_num}L_Index_Abort"));
+        let success_block = self
+            .context
+            .insert_basic_block_after(abort_block, &format!("{line_num}L_Index_Ok"));
+        self.builder
+            .build_conditional_branch(in_bounds, success_block, abort_block);
 
-        match type_ {
-            TypeInformation::Number | TypeInformation::Boolean => {
-                self.builder.build_store(*pointer, expr_value);
-            }
-            TypeInformation::String(_) => {
-                // Allocate space for new string
-                // check is we have a borrowed or owned string
-                let existing_heap_pointer = self.builder.build_load(*pointer, "Existing_String");
-                let expr_value = self.builder.build_pointer_cast(
-                    expr_value.into_pointer_value(),
-                    self.context.i8_type().ptr_type(AddressSpace::Generic),
-                    "Expr Value",
-                );
+        self.builder.position_at_end(abort_block);
+        self.compile_printf(
+            Output::Stderr,
+            &format!("{name} on line {line_num}: index %d is out of bounds for length %d\n"),
+            &[index.into(), length.into()],
+        );
+        let abort = self.module.get_function("abort").unwrap();
+        self.builder
+            .build_call(abort, &[], &format!("{line_num}L_Index_Abort_Call"));
+        self.builder.build_unreachable();
 
-                match expr.type_info() {
-                    TypeInformation::String(true) => {
-                        // We own it, lets just use it!
-                        // free existing string
-                        let free_function = self.module.get_function("free").unwrap();
-                        let free_arguments = [existing_heap_pointer.into()];
-                        self.builder
-                            .build_call(free_function, &free_arguments, "Free_String");
+        self.builder.position_at_end(success_block);
+    }
 
-                        // store new pointer
-                        self.builder.build_store(*pointer, expr_value);
-                    }
-                    TypeInformation::String(false) => {
-                        // get size of new string
-                        let strlen_function = self.module.get_function("strlen").unwrap();
-                        let string_length = self
-                            .builder
-                            .build_call(strlen_function, &[expr_value.into()], "String_Length")
-                            .try_as_basic_value()
-                            .unwrap_left();
+    /// The value limited to `min..=max`
+    fn build_clamp(
+        &self,
+        value: IntValue<'ctx>,
+        min: IntValue<'ctx>,
+        max: IntValue<'ctx>,
+    ) -> IntValue<'ctx> {
+        let below = self
+            .builder
+            .build_int_compare(inkwell::IntPredicate::SLT, value, min, "Below");
+        let value = self
+            .builder
+            .build_select(below, min, value, "At_Least")
+            .into_int_value();
+        let above = self
+            .builder
+            .build_int_compare(inkwell::IntPredicate::SGT, value, max, "Above");
+        self.builder
+            .build_select(above, max, value, "Clamped")
+            .into_int_value()
+    }
 
-                        // Make sure allocated space is large enough
-                        let realloc_function = self.module.get_function("realloc").unwrap();
-                        let heap_pointer = self
-                            .builder
-                            .build_call(
-                                realloc_function,
-                                &[existing_heap_pointer.into(), string_length.into()],
-                                "Heap_Pointer",
-                            )
-                            .try_as_basic_value()
-                            .unwrap_left();
+    /// The bounds are literals, so the whole array is a constant
+    fn compile_range(&self, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        let bound = |argument: &Expression| {
+            argument
+                .number_literal()
+                .expect("Checked by the type analyzer")
+        };
 
-                        // Copy string into heap
-                        let memcpy_function = self.module.get_function("memcpy").unwrap();
-                        self.builder.build_call(
-                            memcpy_function,
-                            &[heap_pointer.into(), expr_value.into(), string_length.into()],
-                            "Memcpy",
-                        );
+        let number_type = self.context.i32_type();
+        let values: Vec<_> = (bound(&arguments[0])..bound(&arguments[1]))
+            .map(|value| number_type.const_int(value as u64, false))
+            .collect();
+        number_type.const_array(&values).as_basic_value_enum()
+    }
 
-                        // Store new pointer
-                        self.builder.build_store(*pointer, heap_pointer);
-                    }
-                    _ => unreachable!("Should always be string type"),
-                }
-            }
-        }
+    /// The offset, type and width of the field named by the second argument
+    fn get_bit_field(arguments: &[Expression]) -> (u32, &TypeInformation, u32) {
+        let Expression::Literal(_, ast::LiteralType::String(field_name)) = &arguments[1] else {
+            unreachable!("Checked by the type analyzer")
+        };
+        let (offset, field_type, width) = arguments[0].type_info().bit_field(field_name).unwrap();
+        (
+            u32::try_from(offset).unwrap(),
+            field_type,
+            u32::try_from(width).unwrap(),
+        )
     }
 
-    fn free_used_vars(&self) {
-        let function_context = self.function_context.as_ref().unwrap();
-        let free_function = self.module.get_function("free").unwrap();
-        for name in function_context.var_pointers.keys() {
-            let type_ = function_context.var_types.get(name).unwrap();
-            let pointer = function_context.var_pointers.get(name).unwrap();
-
-            match type_ {
-                TypeInformation::Number | TypeInformation::Boolean => {}
-                TypeInformation::String(_) => {
-                    let heap_pointer = self.builder.build_load(*pointer, "HeapPointer");
-                    self.builder
-                        .build_call(free_function, &[heap_pointer.into()], "Free_String");
-                }
-            }
+    /// Zero extends or truncates the integer to the given type
+    fn build_int_resize(
+        &self,
+        value: IntValue<'ctx>,
+        type_: IntType<'ctx>,
+        name: &str,
+    ) -> IntValue<'ctx> {
+        if value.get_type().get_bit_width() < type_.get_bit_width() {
+            self.builder.build_int_z_extend(value, type_, name)
+        } else {
+            self.builder.build_int_truncate_or_bit_cast(value, type_, name)
         }
     }
 
-    fn compile_return(&self, expr: &ast::Expression) {
-        self.free_used_vars();
-
-        let type_ = expr.type_info();
-        let value = self.compile_expression(expr);
+    fn compile_get_field(&self, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        let (offset, field_type, width) = Compiler::get_bit_field(arguments);
+        let value = self.compile_expression(&arguments[0]).into_int_value();
 
-        match type_ {
-            TypeInformation::Number | TypeInformation::Boolean | TypeInformation::String(true) => {
-                self.builder.build_return(Some(&value));
-            }
-            TypeInformation::String(false) => {
-                let value = self.get_owned_string(value);
-                self.builder.build_return(Some(&value));
-            }
+        let shifted = self.builder.build_right_shift(
+            value,
+            value.get_type().const_int(u64::from(offset), false),
+            false,
+            "Field_Shifted",
+        );
+        let field =
+            self.build_int_resize(shifted, self.context.custom_width_int_type(width), "Field");
+
+        match field_type {
+            TypeInformation::Boolean => self
+                .builder
+                .build_int_compare(
+                    inkwell::IntPredicate::NE,
+                    field,
+                    field.get_type().const_zero(),
+                    "Field_Bool",
+                )
+                .as_basic_value_enum(),
+            _ => self
+                .build_int_resize(field, self.context.i32_type(), "Field_Num")
+                .as_basic_value_enum(),
         }
     }
 
-    fn compile_assert(&self, expr: &ast::Expression) {
-        let abort = self.module.get_function("abort").unwrap();
-        let printf = self.module.get_function("printf").unwrap();
+    fn compile_set_field(&self, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        let (offset, _, width) = Compiler::get_bit_field(arguments);
+        let value = self.compile_expression(&arguments[0]).into_int_value();
+        let new_value = self.compile_expression(&arguments[2]).into_int_value();
+        let struct_type = value.get_type();
+
+        let field_mask = u64::MAX >> (64 - width);
+        let cleared = self.builder.build_and(
+            value,
+            struct_type.const_int(!(field_mask << offset), false),
+            "Field_Cleared",
+        );
 
-        let expr_value = self.compile_expression(expr).into_int_value();
-        let line_num = expr.location().line_start;
+        let new_bits = self.build_int_resize(new_value, struct_type, "Field_Value");
+        let new_bits =
+            self.builder
+                .build_and(new_bits, struct_type.const_int(field_mask, false), "Field_Value");
+        let new_bits = self.builder.build_left_shift(
+            new_bits,
+            struct_type.const_int(u64::from(offset), false),
+            "Field_Value",
+        );
 
-        let current_block = self.builder.get_insert_block().unwrap();
-        let abort_block = self
-            .context
-            .insert_basic_block_after(current_block, &format!("{}L_Assert_Abort", line_num));
+        self.builder
+            .build_or(cleared, new_bits, "Field_Set")
+            .as_basic_value_enum()
+    }
+
+    /// `Name()` returns a bit struct with every bit set to 0
+    fn compile_bit_struct_constructor(&mut self, name: &str, fields: &[ast::BitField]) {
+        // Every file using the struct declares it
+        if self.defined_functions.contains_key(name) {
+            return;
+        }
+
+        let width: usize = fields.iter().map(|field| field.width).sum();
+        let struct_type = self
+            .context
+            .custom_width_int_type(u32::try_from(width).unwrap());
+        let function_type = struct_type.fn_type(&[], false);
+
+        let function = self
+            .module
+            .add_function(name, function_type, Some(Linkage::Internal));
+        let entry_block = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry_block);
+        self.builder.build_return(Some(&struct_type.const_zero()));
+
+        self.defined_functions.insert(name.to_string(), function_type);
+    }
+
+    /// Runs snprintf once to get the length, and then again into a buffer of that size
+    fn compile_format(&self, arguments: &[Expression]) -> BasicValueEnum<'ctx> {
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let size_type = self.context.i64_type();
+
+        let values: Vec<BasicValueEnum> = arguments
+            .iter()
+            .map(|argument| self.compile_expression(argument))
+            .collect();
+        let i32_type = self.context.i32_type();
+        let format_arguments: Vec<BasicMetadataValueEnum> = values
+            .iter()
+            .zip(arguments)
+            .map(|(&value, argument)| match argument.type_info() {
+                // C variadic functions expect an int, not a single bit
+                TypeInformation::Boolean => self
+                    .builder
+                    .build_int_z_extend(value.into_int_value(), i32_type, "Format_Bool")
+                    .into(),
+                _ => value.into(),
+            })
+            .collect();
+
+        let snprintf = self.module.get_function("snprintf").unwrap();
+        let mut length_arguments: Vec<BasicMetadataValueEnum> =
+            vec![i8_ptr_type.const_null().into(), size_type.const_zero().into()];
+        length_arguments.extend(format_arguments.iter().copied());
+        let length = self
+            .builder
+            .build_call(snprintf, &length_arguments, "Format_Length")
+            .try_as_basic_value()
+            .unwrap_left()
+            .into_int_value();
+
+        // The length does not include the null terminator
+        let length = self
+            .builder
+            .build_int_s_extend(length, size_type, "Format_Length");
+        let size = self
+            .builder
+            .build_int_add(length, size_type.const_int(1, false), "Format_Size");
+
+        let malloc = self.module.get_function("malloc").unwrap();
+        let buffer = self
+            .builder
+            .build_call(malloc, &[size.into()], "Format_Buffer")
+            .try_as_basic_value()
+            .unwrap_left();
+
+        let mut format_call_arguments: Vec<BasicMetadataValueEnum> =
+            vec![buffer.into(), size.into()];
+        format_call_arguments.extend(format_arguments);
+        self.builder
+            .build_call(snprintf, &format_call_arguments, "Format_Result");
+
+        for (value, argument) in values.into_iter().zip(arguments) {
+            self.free_if_needed(value, argument.type_info());
+        }
+
+        buffer
+    }
+
+    fn compile_scanf(&self, format_string: &str, destination: BasicValueEnum<'ctx>) {
+        let scanf_function = self.module.get_function("scanf").unwrap();
+        let format_string = unsafe {
+            self.builder
+                .build_global_string(format_string, "Input_Format_String")
+        };
+        let format = self.builder.build_pointer_cast(
+            format_string.as_pointer_value(),
+            self.context.i8_type().ptr_type(AddressSpace::Generic),
+            "Format",
+        );
+
+        self.builder.build_call(
+            scanf_function,
+            &[format.into(), destination.into()],
+            "Scanf_Result",
+        );
+    }
+
+    fn compile_user_function_call(
+        &self,
+        name: &str,
+        arguments: &[Expression],
+    ) -> BasicValueEnum<'ctx> {
+        let function = self.module.get_function(name).unwrap();
+        let function_type = self.defined_functions[name];
+        // An extern can declare a C function we already use with different types, like strlen
+        let callable = if function.get_type() == function_type {
+            CallableValue::from(function)
+        } else {
+            let pointer = self.builder.build_pointer_cast(
+                function.as_global_value().as_pointer_value(),
+                function_type.ptr_type(AddressSpace::Generic),
+                "Extern_Function",
+            );
+            CallableValue::try_from(pointer).unwrap()
+        };
+
+        let argument_values: Vec<BasicValueEnum> = arguments
+            .iter()
+            .map(|argument| self.compile_expression(argument))
+            .collect();
+        let mut call_arguments: Vec<BasicMetadataValueEnum> =
+            argument_values.iter().map(|&value| value.into()).collect();
+        if function_type.is_var_arg() {
+            self.add_rest_arguments(name, function_type, arguments, &mut call_arguments);
+        }
+
+        let result = self
+            .builder
+            .build_call(callable, &call_arguments, "Call_Result")
+            .try_as_basic_value()
+            .left()
+            // Void is never used, so any value will do
+            .unwrap_or_else(|| self.context.i32_type().const_zero().as_basic_value_enum());
+
+        // The function copies any strings it keeps, so temporary strings can be freed right away
+        for (value, argument) in argument_values.into_iter().zip(arguments) {
+            self.free_if_needed(value, argument.type_info());
+        }
+
+        result
+    }
+
+    /// Widens bools passed as rest arguments to a Num like C does, and passes the number of rest
+    /// arguments to variadic functions from the code
+    fn add_rest_arguments(
+        &self,
+        name: &str,
+        function_type: FunctionType<'ctx>,
+        arguments: &[Expression],
+        call_arguments: &mut Vec<BasicMetadataValueEnum<'ctx>>,
+    ) {
+        let counted = self.counted_variadics.contains(name);
+        let fixed_parameters = usize::try_from(function_type.count_param_types()).unwrap();
+        let rest_start = fixed_parameters - usize::from(counted);
+
+        let i32_type = self.context.i32_type();
+        for (value, argument) in call_arguments[rest_start..]
+            .iter_mut()
+            .zip(&arguments[rest_start..])
+        {
+            if *argument.type_info() == TypeInformation::Boolean {
+                let BasicMetadataValueEnum::IntValue(boolean) = *value else {
+                    unreachable!("Bools are integers")
+                };
+                *value = self
+                    .builder
+                    .build_int_z_extend(boolean, i32_type, "Rest_Bool")
+                    .into();
+            }
+        }
+
+        if counted {
+            let count = u64::try_from(arguments.len() - rest_start).unwrap();
+            call_arguments.insert(rest_start, i32_type.const_int(count, false).into());
+        }
+    }
+
+    fn build_string_slice(
+        &self,
+        pointer: PointerValue<'ctx>,
+        length: IntValue<'ctx>,
+    ) -> BasicValueEnum<'ctx> {
+        let slice_type = self
+            .get_type_for(&TypeInformation::StringSlice)
+            .into_struct_type();
+
+        let slice = self
+            .builder
+            .build_insert_value(slice_type.get_undef(), pointer, 0, "Slice_Pointer")
+            .unwrap();
+        self.builder
+            .build_insert_value(slice, length, 1, "Slice_Length")
+            .unwrap()
+            .into_struct_value()
+            .as_basic_value_enum()
+    }
+
+    /// `needle in haystack`, using strstr to search for the needle
+    fn compile_string_contains(
+        &self,
+        comparison: ast::Comparison,
+        needle: BasicValueEnum<'ctx>,
+        haystack: BasicValueEnum<'ctx>,
+    ) -> IntValue<'ctx> {
+        let strstr_function = self.module.get_function("strstr").unwrap();
+        let found = self
+            .builder
+            .build_call(
+                strstr_function,
+                &[haystack.into(), needle.into()],
+                "Substring_Pointer",
+            )
+            .try_as_basic_value()
+            .unwrap_left()
+            .into_pointer_value();
+
+        match comparison {
+            ast::Comparison::Contains => self.builder.build_is_not_null(found, "Contains"),
+            ast::Comparison::NotContains => self.builder.build_is_null(found, "Not_Contains"),
+            _ => unreachable!("Not a string comparison"),
+        }
+    }
+
+    fn compile_printf(&self, output: Output, format_string: &str, values: &[BasicValueEnum<'ctx>]) {
+        let mut printf_arguments: Vec<BasicMetadataValueEnum> = Vec::new();
+        let printf_function = match output {
+            Output::Stdout => self.module.get_function("printf").unwrap(),
+            Output::Stderr => {
+                // The file descriptor of stderr
+                printf_arguments.push(self.context.i32_type().const_int(2, false).into());
+                self.module.get_function("dprintf").unwrap()
+            }
+        };
+        let format_string = unsafe {
+            self.builder
+                .build_global_string(format_string, "Print_Format_String")
+        };
+
+        // Format string
+        printf_arguments.push(
+            self.builder
+                .build_pointer_cast(
+                    format_string.as_pointer_value(),
+                    self.context.i8_type().ptr_type(AddressSpace::Generic),
+                    "Format",
+                )
+                .into(),
+        );
+        printf_arguments.extend(values.iter().map(|value| BasicMetadataValueEnum::from(*value)));
+
+        self.builder
+            .build_call(printf_function, &printf_arguments, "Print_Statement");
+    }
+
+    fn compile_print_number(&self, output: Output, value: BasicValueEnum<'ctx>, end: &str) {
+        self.compile_printf(output, &format!("%d{end}"), &[value]);
+    }
+
+    fn compile_print_string(
+        &self,
+        output: Output,
+        type_: &TypeInformation,
+        value: BasicValueEnum<'ctx>,
+        end: &str,
+    ) {
+        self.compile_printf(output, &format!("%s{end}"), &[value]);
+        self.free_if_needed(value, type_);
+    }
+
+    fn compile_print_string_slice(&self, output: Output, value: BasicValueEnum<'ctx>, end: &str) {
+        let slice = value.into_struct_value();
+        let pointer = self
+            .builder
+            .build_extract_value(slice, 0, "Slice_Pointer")
+            .unwrap();
+        let length = self
+            .builder
+            .build_extract_value(slice, 1, "Slice_Length")
+            .unwrap();
+
+        self.compile_printf(output, &format!("%.*s{end}"), &[length, pointer]);
+    }
+
+    /// Picks one of the strings from `compile_constant_strings`, so there is no branch
+    fn compile_print_bool(&self, output: Output, value: BasicValueEnum<'ctx>, end: &str) {
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let [true_string, false_string] = ["True_String", "False_String"].map(|name| {
+            let global = self.module.get_global(name).unwrap();
+            self.builder
+                .build_pointer_cast(global.as_pointer_value(), i8_ptr_type, name)
+        });
+
+        let string = self.builder.build_select(
+            value.into_int_value(),
+            true_string,
+            false_string,
+            "Bool_String",
+        );
+        self.compile_printf(output, &format!("%s{end}"), &[string]);
+    }
+
+    /// `[1, 2, 3]`, the first element is printed before the loop so the rest can start with `, `
+    fn compile_print_array(
+        &self,
+        output: Output,
+        value: BasicValueEnum<'ctx>,
+        element: &TypeInformation,
+        size: usize,
+        end: &str,
+    ) {
+        if size == 0 {
+            self.compile_printf(output, &format!("[]{end}"), &[]);
+            return;
+        }
+
+        let i32_type = self.context.i32_type();
+        let array_pointer = self.build_entry_alloca(value.get_type(), "Print_Array");
+        self.builder.build_store(array_pointer, value);
+
+        self.compile_printf(output, "[", &[]);
+        let first_element = self
+            .builder
+            .build_extract_value(value.into_array_value(), 0, "First_Element")
+            .unwrap();
+        self.compile_print_value(output, first_element, element, "");
+
+        let index_pointer = self.build_entry_alloca(i32_type, "Print_Index");
+        self.builder
+            .build_store(index_pointer, i32_type.const_int(1, false));
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let header_block = self
+            .context
+            .insert_basic_block_after(current_block, "Print_Array_Header");
+        let body_block = self
+            .context
+            .insert_basic_block_after(header_block, "Print_Array_Body");
+        let exit_block = self
+            .context
+            .insert_basic_block_after(body_block, "Print_Array_Exit");
+        self.builder.build_unconditional_branch(header_block);
+
+        // Header
+        self.builder.position_at_end(header_block);
+        let index = self
+            .builder
+            .build_load(index_pointer, "Index")
+            .into_int_value();
+        let size = i32_type.const_int(u64::try_from(size).unwrap(), false);
+        let in_bounds =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::SLT, index, size, "In_Bounds");
+        self.builder
+            .build_conditional_branch(in_bounds, body_block, exit_block);
+
+        // Body
+        self.builder.position_at_end(body_block);
+        let element_pointer = unsafe {
+            self.builder.build_in_bounds_gep(
+                array_pointer,
+                &[i32_type.const_zero(), index],
+                "Element_Pointer",
+            )
+        };
+        let element_value = self.builder.build_load(element_pointer, "Element");
+        self.compile_printf(output, ", ", &[]);
+        self.compile_print_value(output, element_value, element, "");
+        let next_index =
+            self.builder
+                .build_int_add(index, i32_type.const_int(1, false), "Next_Index");
+        self.builder.build_store(index_pointer, next_index);
+        self.builder.build_unconditional_branch(header_block);
+
+        // Exit
+        self.builder.position_at_end(exit_block);
+        self.compile_printf(output, &format!("]{end}"), &[]);
+    }
+
+    /// Prints the values without separators, followed by a single newline
+    fn compile_print_values(&self, output: Output, expressions: &[ast::Expression]) {
+        // Only the last value is followed by a newline
+        for (index, expr) in expressions.iter().enumerate() {
+            let end = if index + 1 == expressions.len() { "\n" } else { "" };
+            self.compile_print(output, expr, end);
+        }
+    }
+
+    /// Prints the value followed by `end`
+    fn compile_print(&self, output: Output, expression: &ast::Expression, end: &str) {
+        let value = self.compile_expression(expression);
+        self.compile_print_value(output, value, expression.type_info(), end);
+    }
+
+    fn compile_print_value(
+        &self,
+        output: Output,
+        value: BasicValueEnum<'ctx>,
+        type_: &TypeInformation,
+        end: &str,
+    ) {
+        match type_ {
+            TypeInformation::Number => self.compile_print_number(output, value, end),
+            TypeInformation::Number8 | TypeInformation::Number16 => {
+                self.compile_print_number(output, self.widen(value, type_), end);
+            }
+            TypeInformation::String(_) => self.compile_print_string(output, type_, value, end),
+            TypeInformation::StringSlice => self.compile_print_string_slice(output, value, end),
+            TypeInformation::Boolean => self.compile_print_bool(output, value, end),
+            TypeInformation::Pointer(_) => {
+                self.compile_printf(output, &format!("%p{end}"), &[value]);
+            }
+            TypeInformation::Array { element, size } => {
+                self.compile_print_array(output, value, element, *size, end);
+            }
+            TypeInformation::Tuple(_)
+            | TypeInformation::BitStruct { .. }
+            | TypeInformation::Void
+            | TypeInformation::VarArgs => unreachable!("Rejected by the type checker"),
+        }
+    }
+
+    /// The type and stack slot of a variable that has been declared
+    fn variable(&self, name: &str) -> (&TypeInformation, PointerValue<'ctx>) {
+        let function_context = self.function_context.as_ref().unwrap();
+        let (type_, pointer) = function_context
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .unwrap();
+        (type_, *pointer)
+    }
+
+    /// Gives the variable a stack slot in the innermost scope, unless it already has one.
+    /// Strings start out null, which `realloc` and `free` both accept.
+    fn declare_variable(&mut self, name: &str) {
+        let function_context = self.function_context.as_ref().unwrap();
+        if function_context
+            .scopes
+            .iter()
+            .any(|scope| scope.contains_key(name))
+        {
+            return;
+        }
+        let type_ = function_context.var_types[name].clone();
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let entry_block = current_block
+            .get_parent()
+            .unwrap()
+            .get_first_basic_block()
+            .unwrap();
+        let first_instruction = entry_block.get_first_instruction();
+        let pointer = self.build_entry_alloca(self.get_type_for(&type_), "Stack_Pointer");
+
+        // In the entry block, so the slot is null even on paths that never assign it
+        if let TypeInformation::String(_) = type_ {
+            match first_instruction {
+                Some(instruction) => self.builder.position_before(&instruction),
+                None => self.builder.position_at_end(entry_block),
+            }
+            let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+            self.builder.build_store(pointer, i8_ptr_type.const_null());
+            self.builder.position_at_end(current_block);
+        }
+
+        let function_context = self.function_context.as_mut().unwrap();
+        function_context
+            .scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), (type_, pointer));
+    }
+
+    fn enter_scope(&mut self) {
+        let function_context = self.function_context.as_mut().unwrap();
+        function_context.scopes.push(HashMap::new());
+    }
+
+    fn exit_scope(&mut self) {
+        let scope = self.function_context.as_mut().unwrap().scopes.pop().unwrap();
+        let current_block = self.builder.get_insert_block().unwrap();
+        if current_block.get_terminator().is_none() {
+            self.free_strings(&scope);
+        }
+    }
+
+    /// Compiles a loop body in its own scope, `break` jumps to the exit block
+    fn compile_loop_body(&mut self, body: &ast::CodeBody, exit_block: BasicBlock<'ctx>) {
+        let function_context = self.function_context.as_mut().unwrap();
+        let outer_scopes = function_context.scopes.len();
+        function_context.loop_exits.push((exit_block, outer_scopes));
+
+        self.enter_scope();
+        self.compile_codeblock(body);
+        self.exit_scope();
+
+        self.function_context.as_mut().unwrap().loop_exits.pop();
+    }
+
+    fn compile_assignment(&mut self, name: &str, expr: &ast::Expression) {
+        let expr_value = self.compile_expression(expr);
+        self.declare_variable(name);
+        self.store_variable(name, expr_value, expr.type_info());
+    }
+
+    /// Stores the value in the variable, strings are copied unless the value is owned
+    fn store_variable(
+        &self,
+        name: &str,
+        expr_value: BasicValueEnum<'ctx>,
+        value_type: &TypeInformation,
+    ) {
+        let (type_, pointer) = self.variable(name);
+
+        match type_ {
+            TypeInformation::Number
+            | TypeInformation::Number8
+            | TypeInformation::Number16
+            | TypeInformation::Boolean
+            | TypeInformation::StringSlice
+            | TypeInformation::Pointer(_)
+            | TypeInformation::Array { .. }
+            | TypeInformation::Tuple(_)
+            | TypeInformation::BitStruct { .. }
+            | TypeInformation::VarArgs => {
+                self.builder.build_store(pointer, expr_value);
+            }
+            TypeInformation::Void => unreachable!("Void can not be assigned"),
+            TypeInformation::String(_) => {
+                // Allocate space for new string
+                // check is we have a borrowed or owned string
+                let existing_heap_pointer = self.builder.build_load(pointer, "Existing_String");
+                let expr_value = self.builder.build_pointer_cast(
+                    expr_value.into_pointer_value(),
+                    self.context.i8_type().ptr_type(AddressSpace::Generic),
+                    "Expr Value",
+                );
+
+                match value_type {
+                    TypeInformation::String(Ownership::Owned) => {
+                        // We own it, lets just use it!
+                        // free existing string
+                        let free_function = self.module.get_function("free").unwrap();
+                        let free_arguments = [existing_heap_pointer.into()];
+                        self.builder
+                            .build_call(free_function, &free_arguments, "Free_String");
+
+                        // store new pointer
+                        self.builder.build_store(pointer, expr_value);
+                    }
+                    TypeInformation::String(Ownership::Borrowed) => {
+                        // get size of new string
+                        let string_size = self.build_string_size(expr_value.into());
+
+                        // Make sure allocated space is large enough
+                        let realloc_function = self.module.get_function("realloc").unwrap();
+                        let heap_pointer = self
+                            .builder
+                            .build_call(
+                                realloc_function,
+                                &[existing_heap_pointer.into(), string_size.into()],
+                                "Heap_Pointer",
+                            )
+                            .try_as_basic_value()
+                            .unwrap_left();
+
+                        // Copy string into heap
+                        let memcpy_function = self.module.get_function("memcpy").unwrap();
+                        self.builder.build_call(
+                            memcpy_function,
+                            &[heap_pointer.into(), expr_value.into(), string_size.into()],
+                            "Memcpy",
+                        );
+
+                        // Store new pointer
+                        self.builder.build_store(pointer, heap_pointer);
+                    }
+                    _ => unreachable!("Should always be string type"),
+                }
+            }
+        }
+    }
+
+    fn free_used_vars(&self) {
+        let function_context = self.function_context.as_ref().unwrap();
+        for scope in &function_context.scopes {
+            self.free_strings(scope);
+        }
+    }
+
+    /// Frees the strings of the scope and sets them to null, since the scope can run again.
+    /// Sorted so the generated IR is the same on every run
+    fn free_strings(&self, scope: &Scope<'ctx>) {
+        let free_function = self.module.get_function("free").unwrap();
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let mut names: Vec<&String> = scope.keys().collect();
+        names.sort();
+
+        for name in names {
+            let (type_, pointer) = &scope[name];
+            if let TypeInformation::String(_) = type_ {
+                let heap_pointer = self.builder.build_load(*pointer, "HeapPointer");
+                self.builder
+                    .build_call(free_function, &[heap_pointer.into()], "Free_String");
+                self.builder.build_store(*pointer, i8_ptr_type.const_null());
+            }
+        }
+    }
+
+    /// Runs the deferred statements, the last one deferred first
+    fn compile_deferred(&mut self) {
+        // Taken out while compiling them, compiling a statement needs `self` mutably
+        let deferred = std::mem::take(&mut self.function_context.as_mut().unwrap().deferred);
+        for stmt in deferred.iter().rev() {
+            self.compile_statement(stmt);
+        }
+        self.function_context.as_mut().unwrap().deferred = deferred;
+    }
+
+    fn compile_return(&mut self, expr: Option<&ast::Expression>) {
+        let Some(expr) = expr else {
+            self.compile_deferred();
+            self.free_used_vars();
+            self.builder.build_return(None);
+            return;
+        };
+
+        // Deferred code runs before the value is computed,
+        // the variables are only freed after since the value might use them
+        self.compile_deferred();
+        let type_ = expr.type_info();
+        let value = self.compile_expression(expr);
+        let value = match type_ {
+            TypeInformation::String(Ownership::Borrowed) => Some(self.get_owned_string(value)),
+            // `return exit(1);` in a test function
+            TypeInformation::Void => None,
+            _ => Some(value),
+        };
+
+        self.free_used_vars();
+        match value {
+            Some(value) => self.builder.build_return(Some(&value)),
+            None => self.builder.build_return(None),
+        };
+    }
+
+    fn compile_assert(&self, expr: &ast::Expression, message: Option<&str>) {
+        let abort = self.module.get_function("abort").unwrap();
+        let printf = self.module.get_function("printf").unwrap();
+
+        let expr_value = self.compile_expression(expr).into_int_value();
+        let line_num = expr.location().line_start;
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let abort_block = self
+            .context
+            .insert_basic_block_after(current_block, &format!("{}L_Assert_Abort", line_num));
         let success_block = self
             .context
             .insert_basic_block_after(abort_block, &format!("{}L_Assert_Success", line_num));
@@ -541,12 +1788,13 @@ impl<'ctx> Compiler<'ctx> {
             self.builder
                 .build_global_string("%s\n", "Assert_Msg_Format_String")
         };
+        let message = match message {
+            Some(message) => format!("Assert on line {} failed: {}", line_num, message),
+            None => format!("Assert on line {} failed", line_num),
+        };
         let msg_string = unsafe {
             self.builder
-                .build_global_string(
-                    &format!("Assert on line {} failed", line_num),
-                    "Assert_Msg_String",
-                )
+                .build_global_string(&message, "Assert_Msg_String")
                 .as_pointer_value()
         };
         let printf_arguments = [
@@ -560,19 +1808,100 @@ impl<'ctx> Compiler<'ctx> {
             msg_string.into(),
         ];
         self.builder
-            .build_call(printf, &printf_arguments, "Assert_Printf");
+            .build_call(printf, &printf_arguments, "Assert_Printf");
+        self.builder
+            .build_call(abort, &[], &format!("{}L_Assert_Abort_Call", line_num));
+        self.builder.build_unreachable();
+
+        // Continue to build on the success branch
+        self.builder.position_at_end(success_block);
+    }
+
+    /// Evaluates both sides once, and prints them when they are not equal
+    fn compile_assert_eq(
+        &self,
+        expected: &ast::Expression,
+        actual: &ast::Expression,
+        message: Option<&str>,
+        line_num: usize,
+    ) {
+        let abort = self.module.get_function("abort").unwrap();
+
+        let type_ = expected.type_info();
+        let expected_value = self.compile_expression(expected);
+        let actual_value = self.compile_expression(actual);
+
+        let equal = match type_ {
+            TypeInformation::Number | TypeInformation::Boolean => self.builder.build_int_compare(
+                inkwell::IntPredicate::EQ,
+                expected_value.into_int_value(),
+                actual_value.into_int_value(),
+                "Assert_Eq",
+            ),
+            TypeInformation::String(_) => {
+                let strcmp = self.module.get_function("strcmp").unwrap();
+                let difference = self
+                    .builder
+                    .build_call(
+                        strcmp,
+                        &[expected_value.into(), actual_value.into()],
+                        "String_Difference",
+                    )
+                    .try_as_basic_value()
+                    .unwrap_left()
+                    .into_int_value();
+                self.builder.build_int_compare(
+                    inkwell::IntPredicate::EQ,
+                    difference,
+                    self.context.i32_type().const_zero(),
+                    "Assert_Eq",
+                )
+            }
+            TypeInformation::Number8
+            | TypeInformation::Number16
+            | TypeInformation::StringSlice
+            | TypeInformation::Pointer(_)
+            | TypeInformation::Array { .. }
+            | TypeInformation::Tuple(_)
+            | TypeInformation::BitStruct { .. }
+            | TypeInformation::Void
+            | TypeInformation::VarArgs => unreachable!("Rejected by the type checker"),
+        };
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let abort_block = self
+            .context
+            .insert_basic_block_after(current_block, &format!("{}L_Assert_Abort", line_num));
+        let success_block = self
+            .context
+            .insert_basic_block_after(abort_block, &format!("{}L_Assert_Success", line_num));
+
+        self.builder
+            .build_conditional_branch(equal, success_block, abort_block);
+
+        // On stderr since abort does not flush stdout. Printing frees owned strings, which is
+        // fine since the program aborts right after
+        self.builder.position_at_end(abort_block);
+        let prefix = match message {
+            Some(message) => format!("Assertion failed on line {line_num}: {message}, expected "),
+            None => format!("Assertion failed on line {line_num}: expected "),
+        };
+        self.compile_printf(Output::Stderr, &prefix.replace('%', "%%"), &[]);
+        self.compile_print_value(Output::Stderr, expected_value, type_, " but got ");
+        self.compile_print_value(Output::Stderr, actual_value, actual.type_info(), "\n");
         self.builder
             .build_call(abort, &[], &format!("{}L_Assert_Abort_Call", line_num));
         self.builder.build_unreachable();
 
-        // Continue to build on the success branch
         self.builder.position_at_end(success_block);
+        self.free_if_needed(expected_value, type_);
+        self.free_if_needed(actual_value, actual.type_info());
     }
 
-    fn compile_test(&mut self, name: &str, expr: &ast::Expression) {
-        // lets prefix the name with the current file
-        let padding_length = 20 - (3 + name.len());
-        let name = format!(
+    /// The test name padded and followed by the current file
+    fn test_label(&self, name: &str) -> String {
+        let padding_length = 20usize.saturating_sub(3 + name.len());
+        format!(
             "\x1b[36m{}{}\x1b[33m{}\x1b[0m",
             &name,
             " ".repeat(padding_length),
@@ -581,7 +1910,11 @@ impl<'ctx> Compiler<'ctx> {
                 .to_str()
                 .unwrap()
                 .to_owned()
-        );
+        )
+    }
+
+    fn compile_test(&mut self, name: &str, expr: &ast::Expression) {
+        let name = self.test_label(name);
 
         let abort = self.module.get_function("abort").unwrap();
         let printf = self.module.get_function("printf").unwrap();
@@ -654,73 +1987,705 @@ impl<'ctx> Compiler<'ctx> {
             .build_call(printf, &printf_arguments, "Test_Ok_Printf");
     }
 
+    /// Runs the expression in a loop between two clock readings and prints the average time
+    fn compile_bench(&self, name: &str, iterations: &Expression, body: &Expression) {
+        if !self.run_benchmarks {
+            return;
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+
+        let iterations = self.compile_expression(iterations).into_int_value();
+        let counter_pointer = self.build_entry_alloca(i32_type, "Bench_Counter");
+        self.builder.build_store(counter_pointer, i32_type.const_zero());
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let header_block = self
+            .context
+            .insert_basic_block_after(current_block, "Bench_Header");
+        let body_block = self
+            .context
+            .insert_basic_block_after(header_block, "Bench_Body");
+        let exit_block = self
+            .context
+            .insert_basic_block_after(body_block, "Bench_Exit");
+
+        let start = self.build_clock_nanoseconds();
+        self.builder.build_unconditional_branch(header_block);
+
+        // Header
+        self.builder.position_at_end(header_block);
+        let counter = self
+            .builder
+            .build_load(counter_pointer, "Counter")
+            .into_int_value();
+        let keep_going = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            counter,
+            iterations,
+            "Keep_Going",
+        );
+        self.builder
+            .build_conditional_branch(keep_going, body_block, exit_block);
+
+        // Body
+        self.builder.position_at_end(body_block);
+        let value = self.compile_expression(body);
+        if *body.type_info() != TypeInformation::Void {
+            // Keeps the optimizer from removing an expression whose result is never used
+            let sink = self.build_entry_alloca(value.get_type(), "Bench_Sink");
+            self.builder
+                .build_store(sink, value)
+                .set_volatile(true)
+                .unwrap();
+        }
+        self.free_if_needed(value, body.type_info());
+        let next_counter =
+            self.builder
+                .build_int_add(counter, i32_type.const_int(1, false), "Next_Counter");
+        self.builder.build_store(counter_pointer, next_counter);
+        self.builder.build_unconditional_branch(header_block);
+
+        // Exit
+        self.builder.position_at_end(exit_block);
+        let end = self.build_clock_nanoseconds();
+        let elapsed = self.builder.build_int_sub(end, start, "Bench_Elapsed");
+
+        // A bench that never ran took no time, dividing by 1 avoids dividing by zero
+        let no_runs = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            iterations,
+            i32_type.const_int(1, false),
+            "No_Runs",
+        );
+        let runs = self
+            .builder
+            .build_select(no_runs, i32_type.const_int(1, false), iterations, "Bench_Runs")
+            .into_int_value();
+        let runs = self.builder.build_int_s_extend(runs, i64_type, "Bench_Runs");
+        let per_iteration = self
+            .builder
+            .build_int_signed_div(elapsed, runs, "Bench_Per_Iteration");
+
+        let format_string = format!("{}: %lldns per iteration\n", name.replace('%', "%%"));
+        self.compile_printf(Output::Stdout, &format_string, &[per_iteration.into()]);
+    }
+
+    /// The monotonic clock in nanoseconds
+    fn build_clock_nanoseconds(&self) -> IntValue<'ctx> {
+        let i64_type = self.context.i64_type();
+        let timespec_type = self
+            .context
+            .struct_type(&[i64_type.into(), i64_type.into()], false);
+        let timespec = self.build_entry_alloca(timespec_type, "Timespec");
+        let timespec_argument = self.builder.build_pointer_cast(
+            timespec,
+            self.context.i8_type().ptr_type(AddressSpace::Generic),
+            "Timespec_Argument",
+        );
+
+        // CLOCK_MONOTONIC on Linux
+        let clock_id = self.context.i32_type().const_int(1, false);
+        let clock_gettime = self.module.get_function("clock_gettime").unwrap();
+        self.builder.build_call(
+            clock_gettime,
+            &[clock_id.into(), timespec_argument.into()],
+            "Clock_Gettime",
+        );
+
+        let timespec = self
+            .builder
+            .build_load(timespec, "Timespec_Value")
+            .into_struct_value();
+        let seconds = self
+            .builder
+            .build_extract_value(timespec, 0, "Seconds")
+            .unwrap()
+            .into_int_value();
+        let nanoseconds = self
+            .builder
+            .build_extract_value(timespec, 1, "Nanoseconds")
+            .unwrap()
+            .into_int_value();
+        let seconds = self.builder.build_int_mul(
+            seconds,
+            i64_type.const_int(1_000_000_000, false),
+            "Seconds_In_Nanoseconds",
+        );
+        self.builder
+            .build_int_add(seconds, nanoseconds, "Clock_Nanoseconds")
+    }
+
     fn compile_if(
         &mut self,
         condition: &Expression,
         then: &ast::CodeBody,
+        else_if_chains: &[(Expression, ast::CodeBody)],
         otherwise: &ast::CodeBody,
     ) {
         let current_block = self.builder.get_insert_block().unwrap();
-        let true_case = self
-            .context
-            .insert_basic_block_after(current_block, "True_Case");
-        let false_case = self
+        let continue_block = self
             .context
-            .insert_basic_block_after(true_case, "False_Case");
+            .insert_basic_block_after(current_block, "Continue");
+
+        let branches = std::iter::once((condition, then))
+            .chain(else_if_chains.iter().map(|(condition, body)| (condition, body)));
+
+        for (condition, body) in branches {
+            let current_block = self.builder.get_insert_block().unwrap();
+            let true_case = self
+                .context
+                .insert_basic_block_after(current_block, "True_Case");
+            let false_case = self
+                .context
+                .insert_basic_block_after(true_case, "False_Case");
+
+            let condition_result = self.compile_expression(condition);
+            self.builder.build_conditional_branch(
+                condition_result.into_int_value(),
+                true_case,
+                false_case,
+            );
+
+            // True
+            self.builder.position_at_end(true_case);
+            self.compile_codeblock(body);
+            self.build_branch_if_open(continue_block);
+
+            // False, either the next condition in the chain or the else body
+            self.builder.position_at_end(false_case);
+        }
+
+        self.compile_codeblock(otherwise);
+        self.build_branch_if_open(continue_block);
+
+        // Continue
+        self.builder.position_at_end(continue_block);
+    }
+
+    /// A single `switch` instruction, so LLVM can turn it into a jump table
+    fn compile_switch(
+        &mut self,
+        scrutinee: &Expression,
+        arms: &[(ast::LiteralType, SourceLocation, ast::CodeBody)],
+        default: Option<&ast::CodeBody>,
+    ) {
+        let value = self.compile_expression(scrutinee).into_int_value();
+
+        let current_block = self.builder.get_insert_block().unwrap();
         let continue_block = self
             .context
-            .insert_basic_block_after(false_case, "Continue");
+            .insert_basic_block_after(current_block, "Switch_Continue");
+        let default_block = match default {
+            Some(_) => self
+                .context
+                .insert_basic_block_after(current_block, "Switch_Default"),
+            None => continue_block,
+        };
+
+        let mut cases = Vec::new();
+        let mut previous_block = current_block;
+        for (label, _, _) in arms {
+            let case_block = self
+                .context
+                .insert_basic_block_after(previous_block, "Case");
+            let label = self.compile_literal(label, &TypeInformation::Number);
+            cases.push((label.into_int_value(), case_block));
+            previous_block = case_block;
+        }
+        self.builder.build_switch(value, default_block, &cases);
+
+        for ((_, case_block), (_, _, body)) in cases.iter().zip(arms) {
+            self.builder.position_at_end(*case_block);
+            self.compile_codeblock(body);
+            self.build_branch_if_open(continue_block);
+        }
+
+        if let Some(default) = default {
+            self.builder.position_at_end(default_block);
+            self.compile_codeblock(default);
+            self.build_branch_if_open(continue_block);
+        }
+
+        // Continue
+        self.builder.position_at_end(continue_block);
+    }
+
+    /// `init` is compiled before every check of the condition,
+    /// `update` after every run of the body that did not break
+    fn compile_while(
+        &mut self,
+        init: Option<&ast::Statement>,
+        condition: &Expression,
+        body: &ast::CodeBody,
+        update: Option<&ast::Statement>,
+        else_body: Option<&ast::CodeBody>,
+    ) {
+        let current_block = self.builder.get_insert_block().unwrap();
+        let header_block = self
+            .context
+            .insert_basic_block_after(current_block, "Loop_Header");
+        let body_block = self
+            .context
+            .insert_basic_block_after(header_block, "Loop_Body");
+        let exit_block = self
+            .context
+            .insert_basic_block_after(body_block, "Loop_Exit");
+
+        // The condition failing is the only way to leave the loop without a break,
+        // so that is where the else body goes.
+        let normal_exit_block = match else_body {
+            Some(_) => self
+                .context
+                .insert_basic_block_after(body_block, "Loop_Else"),
+            None => exit_block,
+        };
 
+        self.builder.build_unconditional_branch(header_block);
+
+        // Header
+        self.builder.position_at_end(header_block);
+        if let Some(init) = init {
+            self.compile_statement(init);
+        }
         let condition_result = self.compile_expression(condition);
         self.builder.build_conditional_branch(
             condition_result.into_int_value(),
-            true_case,
-            false_case,
+            body_block,
+            normal_exit_block,
         );
 
-        // True
-        self.builder.position_at_end(true_case);
-        self.compile_codeblock(then);
-        self.builder.build_unconditional_branch(continue_block);
+        // Body
+        self.builder.position_at_end(body_block);
+        self.compile_loop_body(body, exit_block);
+        if let Some(update) = update {
+            if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                self.compile_statement(update);
+            }
+        }
+        self.build_branch_if_open(header_block);
 
-        // False
-        self.builder.position_at_end(false_case);
-        self.compile_codeblock(otherwise);
-        self.builder.build_unconditional_branch(continue_block);
+        // Else
+        if let Some(else_body) = else_body {
+            self.builder.position_at_end(normal_exit_block);
+            self.compile_codeblock(else_body);
+            self.build_branch_if_open(exit_block);
+        }
 
-        // Continue
-        self.builder.position_at_end(continue_block);
+        // Exit
+        self.builder.position_at_end(exit_block);
+    }
+
+    /// Runs the body `count` times using a hidden counter, a negative count runs it zero times
+    fn compile_repeat(&mut self, count: &Expression, body: &ast::CodeBody) {
+        let i32_type = self.context.i32_type();
+
+        let count = self.compile_expression(count).into_int_value();
+        let counter_pointer = self.build_entry_alloca(i32_type, "Repeat_Counter");
+        self.builder.build_store(counter_pointer, i32_type.const_zero());
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let header_block = self
+            .context
+            .insert_basic_block_after(current_block, "Repeat_Header");
+        let body_block = self
+            .context
+            .insert_basic_block_after(header_block, "Repeat_Body");
+        let next_block = self
+            .context
+            .insert_basic_block_after(body_block, "Repeat_Next");
+        let exit_block = self
+            .context
+            .insert_basic_block_after(next_block, "Repeat_Exit");
+
+        self.builder.build_unconditional_branch(header_block);
+
+        // Header
+        self.builder.position_at_end(header_block);
+        let counter = self
+            .builder
+            .build_load(counter_pointer, "Counter")
+            .into_int_value();
+        let keep_going =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::SLT, counter, count, "Keep_Going");
+        self.builder
+            .build_conditional_branch(keep_going, body_block, exit_block);
+
+        // Body
+        self.builder.position_at_end(body_block);
+        self.compile_loop_body(body, exit_block);
+        self.build_branch_if_open(next_block);
+
+        // Next
+        self.builder.position_at_end(next_block);
+        let next_counter =
+            self.builder
+                .build_int_add(counter, i32_type.const_int(1, false), "Next_Counter");
+        self.builder.build_store(counter_pointer, next_counter);
+        self.builder.build_unconditional_branch(header_block);
+
+        // Exit
+        self.builder.position_at_end(exit_block);
+    }
+
+    /// Like `compile_while`, but the body comes before the condition
+    fn compile_do_while(&mut self, body: &ast::CodeBody, condition: &Expression) {
+        let current_block = self.builder.get_insert_block().unwrap();
+        let body_block = self
+            .context
+            .insert_basic_block_after(current_block, "Do_Body");
+        let condition_block = self
+            .context
+            .insert_basic_block_after(body_block, "Do_Condition");
+        let exit_block = self
+            .context
+            .insert_basic_block_after(condition_block, "Do_Exit");
+
+        self.builder.build_unconditional_branch(body_block);
+
+        // Body
+        self.builder.position_at_end(body_block);
+        self.compile_loop_body(body, exit_block);
+        self.build_branch_if_open(condition_block);
+
+        // Condition
+        self.builder.position_at_end(condition_block);
+        let condition_result = self.compile_expression(condition);
+        self.builder.build_conditional_branch(
+            condition_result.into_int_value(),
+            body_block,
+            exit_block,
+        );
+
+        // Exit
+        self.builder.position_at_end(exit_block);
+    }
+
+    /// `var` is set to each element of the array in turn before the body runs
+    fn compile_for_each(&mut self, var: &str, array: &Expression, body: &ast::CodeBody) {
+        let i32_type = self.context.i32_type();
+        let number = TypeInformation::Number;
+
+        // The array is evaluated once, and kept on the stack so it can be indexed.
+        // The rest parameter is the number of arguments, which are read with `va_arg`
+        let (size, element, array_pointer) = match array.type_info() {
+            TypeInformation::Array { element, size } => {
+                let array_value = self.compile_expression(array);
+                let array_pointer = self.build_entry_alloca(array_value.get_type(), "For_Array");
+                self.builder.build_store(array_pointer, array_value);
+                let size = i32_type.const_int(u64::try_from(*size).unwrap(), false);
+                (size, element.as_ref(), array_pointer)
+            }
+            TypeInformation::VarArgs => {
+                let count = self.compile_expression(array).into_int_value();
+                (count, &number, self.build_va_start())
+            }
+            _ => unreachable!("Checked by the type analyzer"),
+        };
+        let is_rest = *array.type_info() == TypeInformation::VarArgs;
+
+        let index_pointer = self.build_entry_alloca(i32_type, "For_Index");
+        self.builder.build_store(index_pointer, i32_type.const_zero());
+
+        let current_block = self.builder.get_insert_block().unwrap();
+        let header_block = self
+            .context
+            .insert_basic_block_after(current_block, "For_Header");
+        let body_block = self
+            .context
+            .insert_basic_block_after(header_block, "For_Body");
+        let next_block = self
+            .context
+            .insert_basic_block_after(body_block, "For_Next");
+        let exit_block = self
+            .context
+            .insert_basic_block_after(next_block, "For_Exit");
+
+        self.builder.build_unconditional_branch(header_block);
+
+        // Header
+        self.builder.position_at_end(header_block);
+        let index = self
+            .builder
+            .build_load(index_pointer, "Index")
+            .into_int_value();
+        let in_bounds =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::SLT, index, size, "In_Bounds");
+        self.builder
+            .build_conditional_branch(in_bounds, body_block, exit_block);
+
+        // Body
+        self.builder.position_at_end(body_block);
+        let element_value = if is_rest {
+            self.builder
+                .build_va_arg(array_pointer, i32_type, "Rest_Argument")
+        } else {
+            let element_pointer = unsafe {
+                self.builder.build_in_bounds_gep(
+                    array_pointer,
+                    &[i32_type.const_zero(), index],
+                    "Element_Pointer",
+                )
+            };
+            self.builder.build_load(element_pointer, "Element")
+        };
+
+        // The loop variable lives in the same scope as the body
+        let function_context = self.function_context.as_mut().unwrap();
+        let outer_scopes = function_context.scopes.len();
+        function_context.loop_exits.push((exit_block, outer_scopes));
+        self.enter_scope();
+        self.declare_variable(var);
+        self.store_variable(var, element_value, element);
+        self.compile_codeblock(body);
+        self.exit_scope();
+        self.function_context.as_mut().unwrap().loop_exits.pop();
+        self.build_branch_if_open(next_block);
+
+        // Next
+        self.builder.position_at_end(next_block);
+        let next_index =
+            self.builder
+                .build_int_add(index, i32_type.const_int(1, false), "Next_Index");
+        self.builder.build_store(index_pointer, next_index);
+        self.builder.build_unconditional_branch(header_block);
+
+        // Exit
+        self.builder.position_at_end(exit_block);
+        if is_rest {
+            let va_end = self.module.get_function("llvm.va_end").unwrap();
+            self.builder
+                .build_call(va_end, &[array_pointer.into()], "Rest_End");
+        }
+    }
+
+    /// A `va_list` pointing at the first rest argument of the current function.
+    /// Its layout depends on the target, 32 bytes fits the ones of x86-64 and AArch64
+    fn build_va_start(&self) -> PointerValue<'ctx> {
+        let i8_type = self.context.i8_type();
+        let list = self.build_entry_alloca(i8_type.array_type(32), "Rest_Arguments");
+        list.as_instruction()
+            .unwrap()
+            .set_alignment(16)
+            .unwrap();
+        let list = self.builder.build_pointer_cast(
+            list,
+            i8_type.ptr_type(AddressSpace::Generic),
+            "Rest_List",
+        );
+
+        let va_start = self.module.get_function("llvm.va_start").unwrap();
+        self.builder.build_call(va_start, &[list.into()], "Rest_Start");
+        list
+    }
+
+    fn compile_break(&self) {
+        let function_context = self.function_context.as_ref().unwrap();
+        let (exit_block, outer_scopes) = *function_context.loop_exits.last().unwrap();
+        // The scopes inside the loop are left without reaching their end
+        for scope in &function_context.scopes[outer_scopes..] {
+            self.free_strings(scope);
+        }
+        self.builder.build_unconditional_branch(exit_block);
+
+        // Anything after the break is dead code, but it still needs a block to live in
+        let current_block = self.builder.get_insert_block().unwrap();
+        let dead_block = self
+            .context
+            .insert_basic_block_after(current_block, "After_Break");
+        self.builder.position_at_end(dead_block);
     }
 
     fn compile_statement(&mut self, stmt: &ast::Statement) {
+        self.compile_statement_inner(stmt);
+
+        if self.track_locations {
+            if let Some(location) = stmt.location() {
+                self.tag_instructions(location);
+            }
+        }
+    }
+
+    /// Attaches the location to every instruction of the current function that has none yet.
+    /// Statements nested in this one are compiled first, so they keep their own locations.
+    /// Only the instructions after the last tagged one are visited, the builder only moves
+    /// forward through the function, apart from the allocas at the top of the entry block.
+    fn tag_instructions(&mut self, location: SourceLocation) {
+        let kind_id = self.context.get_kind_id(LOCATION_METADATA_KIND);
+        let i64_type = self.context.i64_type();
+        let values = [
+            location.line_start,
+            location.line_end,
+            location.char_start,
+            location.char_end,
+        ]
+        .map(|value| BasicMetadataValueEnum::from(i64_type.const_int(value as u64, false)));
+        let node = self.context.metadata_node(&values);
+
+        let function_context = self.function_context.as_mut().unwrap();
+        let mut instruction = match function_context.last_tagged {
+            Some(last_tagged) => following_instruction(last_tagged),
+            None => {
+                let current_block = self.builder.get_insert_block().unwrap();
+                first_instruction_from(current_block.get_parent().unwrap().get_first_basic_block())
+            }
+        };
+        while let Some(current) = instruction {
+            if current.get_metadata(kind_id).is_none() {
+                current.set_metadata(node, kind_id).unwrap();
+            }
+            function_context.last_tagged = Some(current);
+            instruction = following_instruction(current);
+        }
+    }
+
+    fn compile_statement_inner(&mut self, stmt: &ast::Statement) {
         match stmt {
-            ast::Statement::Print(expr) => self.compile_print(expr),
+            ast::Statement::Print(expressions) => {
+                self.compile_print_values(Output::Stdout, expressions);
+            }
+            ast::Statement::PrintErr(expressions) => {
+                self.compile_print_values(Output::Stderr, expressions);
+            }
 
-            ast::Statement::Assert(expr) => self.compile_assert(expr),
+            ast::Statement::Assert(expr, message) => self.compile_assert(expr, message.as_deref()),
+            ast::Statement::AssertEq {
+                expected,
+                actual,
+                message,
+                location,
+            } => self.compile_assert_eq(expected, actual, message.as_deref(), location.line_start),
             ast::Statement::Assignment {
                 expression_location: _,
                 var_name: name,
                 expression: exp,
             } => self.compile_assignment(name, exp),
-            ast::Statement::Return(expr) => self.compile_return(expr),
+            ast::Statement::Return(expr, _) => self.compile_return(expr.as_ref()),
             ast::Statement::Test(name, expr) => self.compile_test(name, expr),
+            ast::Statement::Bench {
+                name,
+                iterations,
+                body,
+            } => self.compile_bench(name, iterations, body),
             ast::Statement::If {
                 condition,
                 then,
+                else_if_chains,
                 otherwise,
-            } => self.compile_if(condition, then, otherwise),
+            } => self.compile_if(condition, then, else_if_chains, otherwise),
+            ast::Statement::While {
+                condition,
+                body,
+                else_body,
+            } => self.compile_while(None, condition, body, None, else_body.as_ref()),
+            ast::Statement::WhileAssign {
+                assignment,
+                condition,
+                body,
+            } => self.compile_while(Some(assignment.as_ref()), condition, body, None, None),
+            ast::Statement::Switch {
+                scrutinee,
+                arms,
+                default,
+            } => self.compile_switch(scrutinee, arms, default.as_ref()),
+            ast::Statement::Repeat { count, body } => self.compile_repeat(count, body),
+            ast::Statement::DoWhile { body, condition } => self.compile_do_while(body, condition),
+            ast::Statement::CStyleFor {
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                self.compile_statement(init);
+                self.compile_while(None, condition, body, Some(update.as_ref()), None);
+            }
+            ast::Statement::ForEach { var, array, body } => self.compile_for_each(var, array, body),
+            ast::Statement::Break => self.compile_break(),
+            ast::Statement::Defer { body, .. } => {
+                let function_context = self.function_context.as_mut().unwrap();
+                function_context.deferred.push(body.as_ref().clone());
+            }
+            ast::Statement::Discard(expr) => {
+                let value = self.compile_expression(expr);
+                self.free_if_needed(value, expr.type_info());
+            }
+            // Only checked by the type analyzer
+            ast::Statement::AssertType { .. } => {}
+        }
+    }
+
+    fn compile_function_definition(
+        &mut self,
+        name: &str,
+        is_public: bool,
+        meta: &ast::FunctionMetadata,
+    ) {
+        let function_type = self.get_function_type(meta);
+
+        // main has to be visible to the C runtime even if it is not marked pub
+        let linkage = if is_public || name == "main" {
+            None
+        } else {
+            Some(Linkage::Internal)
+        };
+
+        let function = self.module.add_function(name, function_type, linkage);
+        self.add_inlining_hint(function, meta.inlining_hint);
+        self.defined_functions.insert(name.to_string(), function_type);
+        if meta.is_variadic {
+            self.counted_variadics.insert(name.to_string());
         }
     }
 
-    fn compile_function_definition(&self, name: &str, meta: &ast::FunctionMetadata) {
-        let return_type = self.get_type_for(meta.return_type.unwrap());
-        let arguments = [];
+    fn add_inlining_hint(&self, function: FunctionValue<'ctx>, hint: ast::InliningHint) {
+        let attribute_name = match hint {
+            ast::InliningHint::None => return,
+            ast::InliningHint::Always => "alwaysinline",
+            ast::InliningHint::Never => "noinline",
+        };
+
+        let kind_id = Attribute::get_named_enum_kind_id(attribute_name);
+        function.add_attribute(
+            AttributeLoc::Function,
+            self.context.create_enum_attribute(kind_id, 0),
+        );
+    }
+
+    fn compile_extern_declaration(&mut self, name: &str, meta: &ast::FunctionMetadata) {
+        let function_type = self.get_function_type(meta);
+        if self.module.get_function(name).is_none() {
+            self.module.add_function(name, function_type, None);
+        }
+        self.defined_functions.insert(name.to_string(), function_type);
+    }
 
-        let function_type = return_type.fn_type(&arguments, false);
-        self.module.add_function(name, function_type, None);
+    fn get_function_type(&self, meta: &ast::FunctionMetadata) -> FunctionType<'ctx> {
+        let arguments: Vec<BasicMetadataTypeEnum> = meta
+            .parameter_types
+            .iter()
+            .map(|type_| self.get_type_for(type_).into())
+            .collect();
+        match meta.return_type.as_ref().unwrap() {
+            TypeInformation::Void => self
+                .context
+                .void_type()
+                .fn_type(&arguments, meta.is_variadic),
+            return_type => self
+                .get_type_for(return_type)
+                .fn_type(&arguments, meta.is_variadic),
+        }
     }
 
-    fn compile_function(&mut self, name: &str, code: &ast::CodeBody, meta: ast::FunctionMetadata) {
+    fn compile_function(
+        &mut self,
+        name: &str,
+        parameters: &[ast::Parameter],
+        code: &ast::CodeBody,
+        meta: ast::FunctionMetadata,
+    ) {
         let function = self.module.get_function(name).unwrap();
 
         let entry_block = self.context.append_basic_block(function, "entry");
@@ -728,13 +2693,73 @@ impl<'ctx> Compiler<'ctx> {
 
         self.function_context.replace(FunctionContext {
             var_types: meta.var_types,
-            var_pointers: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            loop_exits: Vec::new(),
+            deferred: Vec::new(),
+            last_tagged: None,
         });
 
-        self.compile_var_allocations();
+        for (parameter, value) in parameters.iter().zip(function.get_param_iter()) {
+            // Parameters are borrowed, so strings are copied into the variable
+            self.declare_variable(&parameter.name);
+            let type_ = &self.function_context.as_ref().unwrap().var_types[&parameter.name];
+            self.store_variable(&parameter.name, value, type_);
+        }
+        for (name, type_) in &meta.named_returns {
+            // Named return values start out as zero, or as an empty string
+            self.declare_variable(name);
+            let value = if let TypeInformation::String(_) = type_ {
+                let empty = unsafe { self.builder.build_global_string("", "Empty_String") };
+                empty.as_pointer_value().as_basic_value_enum()
+            } else {
+                self.get_type_for(type_).const_zero()
+            };
+            self.store_variable(name, value, &type_.clone().mark_borrowed());
+        }
+        if name == "main" && self.run_tests {
+            let run_tests = self.module.get_function("__run_tests").unwrap();
+            self.builder.build_call(run_tests, &[], "Run_Tests");
+        }
         self.compile_codeblock(code);
     }
 
+    /// Declares the test functions and `__run_tests`, which calls each of them in order
+    fn compile_test_runner(&self, code: &ast::File) {
+        let test_function_type = self.context.void_type().fn_type(&[], false);
+        let run_tests = self.module.add_function(
+            "__run_tests",
+            test_function_type,
+            Some(Linkage::Internal),
+        );
+        let entry_block = self.context.append_basic_block(run_tests, "entry");
+
+        for stmt in &code.0 {
+            if let ast::TopLevelStatement::TestFunction { name, metadata, .. } = stmt {
+                let function =
+                    self.module
+                        .add_function(name, test_function_type, Some(Linkage::Internal));
+                self.add_inlining_hint(function, metadata.inlining_hint);
+
+                self.builder.position_at_end(entry_block);
+                self.builder.build_call(function, &[], "Test_Call");
+                // A failing test aborts, so getting here means it passed
+                let label = self.test_label(name);
+                self.compile_printf(Output::Stdout, &format!("\x1b[32mOK {label}\x1b[0m\n"), &[]);
+            }
+        }
+
+        self.builder.position_at_end(entry_block);
+        self.builder.build_return(None);
+    }
+
+    /// Branches to `target`, unless the current block already ended with a `return`
+    fn build_branch_if_open(&self, target: BasicBlock<'ctx>) {
+        let current_block = self.builder.get_insert_block().unwrap();
+        if current_block.get_terminator().is_none() {
+            self.builder.build_unconditional_branch(target);
+        }
+    }
+
     fn compile_codeblock(&mut self, code: &ast::CodeBody) {
         for stmt in &code.0 {
             self.compile_statement(stmt);
@@ -745,36 +2770,110 @@ impl<'ctx> Compiler<'ctx> {
         match stmt {
             ast::TopLevelStatement::FunctionDefinition {
                 function_name: name,
+                parameters,
                 body,
                 metadata: meta,
                 ..
-            } => self.compile_function(&name, &body, meta),
+            } => {
+                let returns_void = meta.return_type == Some(TypeInformation::Void);
+                self.compile_function(&name, &parameters, &body, meta);
+
+                let current_block = self.builder.get_insert_block().unwrap();
+                if current_block.get_terminator().is_none() {
+                    if returns_void {
+                        self.compile_deferred();
+                        self.free_used_vars();
+                        self.builder.build_return(None);
+                    } else {
+                        // The return check makes sure every path returned, so this block is dead
+                        self.builder.build_unreachable();
+                    }
+                }
+            }
+            ast::TopLevelStatement::TestFunction {
+                name, body, metadata: meta, ..
+            } => {
+                if !self.run_tests {
+                    return;
+                }
+                self.compile_function(&name, &[], &body, meta);
+
+                let current_block = self.builder.get_insert_block().unwrap();
+                if current_block.get_terminator().is_none() {
+                    self.compile_deferred();
+                    self.free_used_vars();
+                    self.builder.build_return(None);
+                }
+            }
+            ast::TopLevelStatement::ExternFunction { .. }
+            | ast::TopLevelStatement::BitStruct { .. }
+            | ast::TopLevelStatement::TypeAlias { .. } => {}
         }
     }
 
-    pub fn compile_code(&mut self, code: ast::File, optimize: bool) {
+    pub fn compile_code(&mut self, code: ast::File) {
         // Create clib functions
         self.compile_glibc_definitions();
+        self.compile_intrinsic_definitions();
+        self.compile_constant_strings();
 
         for stmt in &code.0 {
             match stmt {
                 ast::TopLevelStatement::FunctionDefinition {
+                    function_name: name,
+                    is_public,
+                    metadata: meta,
+                    ..
+                } => self.compile_function_definition(name, *is_public, meta),
+                ast::TopLevelStatement::ExternFunction {
                     function_name: name,
                     metadata: meta,
                     ..
-                } => self.compile_function_definition(name, meta),
+                } => self.compile_extern_declaration(name, meta),
+                // Tests are declared by the test runner, and aliases are resolved by the analyzers
+                ast::TopLevelStatement::TestFunction { .. }
+                | ast::TopLevelStatement::TypeAlias { .. } => {}
+                ast::TopLevelStatement::BitStruct { name, fields, .. } => {
+                    self.compile_bit_struct_constructor(name, fields);
+                }
             }
         }
+        if self.run_tests {
+            self.compile_test_runner(&code);
+        }
         for stmt in code.0 {
             self.compile_toplevel_statement(stmt);
         }
+    }
 
-        if optimize {
-            self.fpm.run_on(&self.module);
-        }
+    /// Checks the module is valid IR, returning what LLVM found wrong if it is not
+    pub fn verify(&self) -> Result<(), String> {
+        self.module.verify().map_err(|err| err.to_string())
     }
 
-    pub fn save_in(&self, path: &str) {
-        self.module.print_to_file(path).unwrap();
+    pub fn optimize(&self) {
+        self.fpm.run_on(&self.module);
+    }
+
+    pub fn ir_string(&self) -> String {
+        self.module.print_to_string().to_string()
+    }
+}
+
+/// The instruction after this one, in the same block or the blocks after it
+fn following_instruction(instruction: InstructionValue) -> Option<InstructionValue> {
+    instruction
+        .get_next_instruction()
+        .or_else(|| first_instruction_from(instruction.get_parent()?.get_next_basic_block()))
+}
+
+/// The first instruction in this block or the first non empty block after it
+fn first_instruction_from(mut block: Option<BasicBlock>) -> Option<InstructionValue> {
+    while let Some(current) = block {
+        if let Some(instruction) = current.get_first_instruction() {
+            return Some(instruction);
+        }
+        block = current.get_next_basic_block();
     }
+    None
 }
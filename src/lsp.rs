@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+
+use serde_json::{json, Value};
+
+use crate::{collect_warnings, type_at, CompilerConfig, SourceLocation};
+
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+/// JSON-RPC error code for requests the server does not know
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// Runs a language server on stdin and stdout until the client sends `exit`.
+/// Documents are always synced in full, and positions count characters instead of UTF-16 units.
+pub fn run_language_server() {
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout().lock();
+    let mut server = LanguageServer::default();
+
+    while let Some(message) = read_message(&mut input) {
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply);
+        }
+        if server.exited {
+            break;
+        }
+    }
+}
+
+#[derive(Default)]
+struct LanguageServer {
+    /// The code of every open document by its uri
+    documents: HashMap<String, String>,
+    exited: bool,
+}
+
+impl LanguageServer {
+    /// The responses and notifications to send back for a message from the client
+    fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let id = message.get("id");
+        let params = &message["params"];
+
+        match message["method"].as_str().unwrap_or_default() {
+            "initialize" => vec![response(
+                id,
+                json!({
+                    "capabilities": {"textDocumentSync": 1, "hoverProvider": true},
+                    "serverInfo": {"name": "viv_script", "version": env!("CARGO_PKG_VERSION")},
+                }),
+            )],
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let code = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri, code)
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let change = params["contentChanges"].as_array().and_then(|c| c.last());
+                let code = change.and_then(|change| change["text"].as_str());
+                code.map(|code| self.update(uri, code)).unwrap_or_default()
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, Vec::new())]
+            }
+            "textDocument/hover" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                vec![response(id, self.hover(uri, &params["position"]))]
+            }
+            "shutdown" => vec![response(id, Value::Null)],
+            "exit" => {
+                self.exited = true;
+                Vec::new()
+            }
+            method => match id {
+                Some(id) => vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("unknown method {method}"),
+                    },
+                })],
+                // Notifications like `initialized` need no answer
+                None => Vec::new(),
+            },
+        }
+    }
+
+    fn update(&mut self, uri: &str, code: &str) -> Vec<Value> {
+        self.documents.insert(uri.to_string(), code.to_string());
+        vec![publish_diagnostics(uri, diagnostics(code))]
+    }
+
+    /// The type of the expression under the cursor
+    fn hover(&self, uri: &str, position: &Value) -> Value {
+        let code = self.documents.get(uri);
+        let line = position["line"]
+            .as_u64()
+            .and_then(|line| usize::try_from(line).ok());
+        let column = position["character"]
+            .as_u64()
+            .and_then(|c| usize::try_from(c).ok());
+        let (Some(code), Some(line), Some(column)) = (code, line, column) else {
+            return Value::Null;
+        };
+
+        match type_at(code, line + 1, column + 1) {
+            Some((location, type_name)) => json!({
+                "contents": {"kind": "plaintext", "value": type_name},
+                "range": range(location),
+            }),
+            None => Value::Null,
+        }
+    }
+}
+
+/// The error or the warnings for the code, dead functions are left out since other files can
+/// call them
+fn diagnostics(code: &str) -> Vec<Value> {
+    let config = CompilerConfig {
+        warn_shadowing: true,
+        warn_dead_stores: true,
+        ..CompilerConfig::default()
+    };
+
+    match collect_warnings(code, &config) {
+        Ok(warnings) => warnings
+            .iter()
+            .map(|warning| diagnostic(warning.location(), WARNING, &warning.message()))
+            .collect(),
+        Err((location, message)) => vec![diagnostic(location, ERROR, &message)],
+    }
+}
+
+fn diagnostic(location: SourceLocation, severity: u8, message: &str) -> Value {
+    json!({
+        "range": range(location),
+        "severity": severity,
+        "source": "viv_script",
+        "message": message,
+    })
+}
+
+/// LSP positions are 0-indexed and the end is exclusive, `SourceLocation` is 1-indexed and
+/// includes its last character
+fn range(location: SourceLocation) -> Value {
+    json!({
+        "start": {
+            "line": location.line_start.saturating_sub(1),
+            "character": location.char_start.saturating_sub(1),
+        },
+        "end": {"line": location.line_end.saturating_sub(1), "character": location.char_end},
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": {"uri": uri, "diagnostics": Value::Array(diagnostics)},
+    })
+}
+
+fn response(id: Option<&Value>, result: Value) -> Value {
+    let mut response = json!({"jsonrpc": "2.0", "id": id});
+    response["result"] = result;
+    response
+}
+
+/// Reads a `Content-Length` framed message, `None` when the input is closed.
+/// A message that is not valid JSON is returned as `null`, which the server ignores.
+fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length?];
+    input.read_exact(&mut body).ok()?;
+    Some(serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    output.flush().unwrap();
+}
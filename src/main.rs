@@ -1,18 +1,71 @@
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
-use viv_script::{compile_to_exe, compile_to_ir, compile_to_obj, find_exe, report_error, run_exe};
+use viv_script::{
+    compile_to_exe, compile_to_ir, compile_to_obj, default_cache_dir, explain_error, find_exe,
+    generate_docs, report_error, run_exe, run_exe_capture, run_language_server, CompilerConfig,
+    ErrorCode, LinkConfig, PgoMode, VivConfig, ANALYSIS_PASSES,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, arg_required_else_help = true)]
 struct Args {
     #[arg(short, long)]
     no_optimize: bool,
 
+    /// Print how long each compiler phase takes
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Warn when a variable in a nested block shadows one from an outer block
+    #[arg(long)]
+    warn_shadowing: bool,
+
+    /// Warn about functions that are never called, `pub` functions are always seen as used
+    #[arg(long)]
+    warn_dead_functions: bool,
+
+    /// Warn about assignments that are overwritten before the variable is read
+    #[arg(long)]
+    warn_dead_stores: bool,
+
+    /// Store variables that are only assigned small number literals in 8 or 16 bits
+    #[arg(long)]
+    small_ints: bool,
+
+    /// Print the documentation for an error code, for example `E002`
+    #[arg(long, value_name = "CODE")]
+    explain: Option<String>,
+
+    /// Run code as the body of `main` instead of a subcommand, can be given multiple times
+    #[arg(short, long, value_name = "CODE")]
+    eval: Vec<String>,
+
+    /// Set a value for `#if` directives, can be given multiple times
+    #[arg(short = 'D', value_name = "KEY=VALUE", value_parser = parse_define)]
+    define: Vec<(String, String)>,
+
+    /// Print the AST to stderr before and after the analysis
+    #[arg(long, hide = true)]
+    print_ast: bool,
+
+    /// Print the AST to stderr after a single analysis pass
+    #[arg(long, hide = true, value_name = "PASS", value_parser = ANALYSIS_PASSES)]
+    print_ast_after_pass: Option<String>,
+
+    /// Always compile, without reading or writing the IR cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Folder to cache the IR of unchanged files in, defaults to `~/.cache/viv_script`
+    #[arg(long, value_name = "PATH")]
+    cache_dir: Option<PathBuf>,
+
     #[command(subcommand)]
-    command: Command,
+    command: Option<Command>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -20,59 +73,207 @@ struct Args {
 enum Command {
     Build {
         input_file: String,
-        output_file: String,
+        /// Defaults to the name of the input file without the extension
+        output_file: Option<String>,
+        /// Folder the output file is placed in, created if it does not exist
+        #[arg(long, default_value = ".")]
+        output_dir: PathBuf,
+        /// Target triple to compile for, for example `aarch64-linux-gnu`
+        #[arg(long)]
+        target: Option<String>,
+        /// Linker to use instead of searching for clang/gcc
+        #[arg(long)]
+        linker: Option<String>,
+        /// Build a shared library exporting all `pub` functions
+        #[arg(long)]
+        shared: bool,
+        /// Link the standard library statically, see the README for requirements
+        #[arg(long = "static")]
+        static_link: bool,
+        /// Root folder for headers and libraries, passed on to the linker
+        #[arg(long)]
+        sysroot: Option<String>,
+        /// Build an instrumented executable that writes a profile when it runs, needs clang
+        #[arg(long, conflicts_with = "pgo_use")]
+        pgo_generate: bool,
+        /// Optimize using a profile merged with `llvm-profdata`, needs clang
+        #[arg(long, value_name = "PROFILE")]
+        pgo_use: Option<PathBuf>,
+        /// Link time optimization, needs clang and on Linux also lld
+        #[arg(long)]
+        lto: bool,
     },
     Run {
         input_file: String,
+        /// Run the `#[test]` functions before `main`
+        #[arg(long)]
+        test: bool,
+        /// Run the `bench` statements, without this they are left out
+        #[arg(long)]
+        bench: bool,
     },
     Ir {
         input_file: String,
-        output_fie: String,
+        /// Defaults to the name of the input file with a `.ll` extension
+        output_fie: Option<String>,
+        /// Folder the output file is placed in, created if it does not exist
+        #[arg(long, default_value = ".")]
+        output_dir: PathBuf,
     },
     Test {
+        folder: Option<String>,
+    },
+    /// Remove build artifacts, for every `name.viv` this deletes `name.ll` and `name.o` next to it
+    Clean {
         folder: String,
+        /// Only list the files that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generate HTML documentation from the doc comments of the functions
+    Doc {
+        input_file: String,
+        /// Folder the pages are written to, created if it does not exist
+        #[arg(long, default_value = "doc")]
+        output_dir: PathBuf,
     },
+    /// Start a language server on stdin and stdout, for editor integration
+    Lsp,
+}
+
+fn parse_define(define: &str) -> Result<(String, String), String> {
+    define
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got {define}"))
+}
+
+/// Reads the source code, an input file of `-` means the code is read from stdin.
+fn read_source(input_file: &str) -> (&str, String) {
+    if input_file == "-" {
+        ("<stdin>", std::io::read_to_string(std::io::stdin()).unwrap())
+    } else {
+        (input_file, std::fs::read_to_string(input_file).unwrap())
+    }
 }
 
-fn ir(optimize: bool, input_file: &str, output_file: &str) {
-    let code = std::fs::read_to_string(input_file).unwrap();
-    if let Err(err) = compile_to_ir(input_file, &code, output_file, optimize) {
+/// Joins the output file onto the output folder, `src/main.viv` is `output_dir/main` by default
+fn output_path(
+    input_file: &str,
+    output_file: Option<String>,
+    output_dir: &Path,
+    extension: &str,
+) -> String {
+    let output_file = output_file.unwrap_or_else(|| {
+        let stem = if input_file == "-" {
+            "stdin".to_string()
+        } else {
+            Path::new(input_file).file_stem().unwrap().to_string_lossy().into_owned()
+        };
+        if extension.is_empty() {
+            stem
+        } else {
+            format!("{stem}.{extension}")
+        }
+    });
+    let path = output_dir.join(output_file);
+
+    if let Some(parent) = path.parent() {
+        if let Err(err) = fs::create_dir_all(parent) {
+            eprintln!("ERROR: could not create {}: {err}", parent.display());
+            exit(1);
+        }
+    }
+    path.to_str().unwrap().to_owned()
+}
+
+/// `include_str!` paths are relative to the folder of the input file
+fn with_source_dir(config: &CompilerConfig, input_file: &str) -> CompilerConfig {
+    let source_dir = Path::new(input_file).parent().filter(|_| input_file != "-");
+    CompilerConfig {
+        source_dir: source_dir.map(Path::to_path_buf),
+        ..config.clone()
+    }
+}
+
+fn ir(config: &CompilerConfig, input_file: &str, output_file: &str) {
+    let (name, code) = read_source(input_file);
+    let mut config = with_source_dir(config, input_file);
+    // Piped code is rarely compiled again, it would only fill up the cache
+    if input_file == "-" {
+        config.cache_dir = None;
+    }
+    if let Err(err) = compile_to_ir(name, &code, output_file, &config) {
         report_error(&code, &err);
         // This is not good error handling, but :P
         exit(1);
     }
 }
 
-fn build(optimize: bool, input_file: &str, output_file: &str) {
+fn build(
+    viv_config: &VivConfig,
+    config: &CompilerConfig,
+    linker: Option<&String>,
+    input_file: &str,
+    output_file: &str,
+) {
     let ir_file = temp_file::empty();
     let ir_file = ir_file.path().to_str().unwrap();
 
     let obj_file = temp_file::empty();
     let obj_file = obj_file.path().to_str().unwrap();
 
-    ir(optimize, input_file, ir_file);
+    ir(config, input_file, ir_file);
 
-    let llc = find_exe(&["llc-14", "llc"]).expect("llc binary not found");
-    let gcc = find_exe(&["clang", "gcc"]).expect("gcc/clang not found on system");
+    if config.compiles_ir_with_clang() {
+        let clang = linker.map_or_else(
+            || find_exe(&["clang-14", "clang"]).expect("PGO and LTO need clang"),
+            PathBuf::from,
+        );
+        compile_to_exe(clang, ir_file, output_file, config, &LinkConfig::from(config));
+        return;
+    }
+
+    let llc = viv_config.llc_path.as_ref().map_or_else(
+        || find_exe(&["llc-14", "llc"]).expect("llc binary not found"),
+        PathBuf::from,
+    );
+    let gcc = linker.or(viv_config.gcc_path.as_ref()).map_or_else(
+        || find_exe(&["clang", "gcc"]).expect("gcc/clang not found on system"),
+        PathBuf::from,
+    );
 
-    compile_to_obj(llc, ir_file, obj_file);
-    compile_to_exe(gcc, obj_file, output_file);
+    compile_to_obj(llc, ir_file, obj_file, config);
+    compile_to_exe(gcc, obj_file, output_file, config, &LinkConfig::from(config));
 }
 
-fn run(optimize: bool, input_file: &str) -> i32 {
+fn run(viv_config: &VivConfig, config: &CompilerConfig, input_file: &str) -> i32 {
     let exe_file = temp_file::empty();
     let exe_file = exe_file.path().to_str().unwrap();
 
-    build(optimize, input_file, exe_file);
+    build(viv_config, config, None, input_file, exe_file);
     run_exe(exe_file)
 }
 
-fn find_viv_files(folder: &str) -> Vec<String> {
+/// Runs the `-e` snippets in order as the body of `main`
+fn eval(viv_config: &VivConfig, config: &CompilerConfig, snippets: &[String]) -> i32 {
+    let code = format!("fn main() -> Num {{\n{}\nreturn 0;\n}}\n", snippets.join("\n"));
+    let file = temp_file::with_contents(code.as_bytes());
+    // Snippets are one-off code, like piped code they are not cached
+    let config = CompilerConfig {
+        cache_dir: None,
+        ..config.clone()
+    };
+    run(viv_config, &config, file.path().to_str().unwrap())
+}
+
+/// Every `.viv` file in the folder and its subfolders, sorted so the order is the same everywhere
+fn find_viv_files(folder: &str) -> std::io::Result<Vec<String>> {
     let mut file_paths = Vec::new();
-    let dir_contents = fs::read_dir(folder).unwrap();
+    let dir_contents = fs::read_dir(folder)?;
 
     for entry in dir_contents {
-        let entry = entry.unwrap();
+        let entry = entry?;
         let path = entry.path();
 
         if path.is_file() {
@@ -84,36 +285,238 @@ fn find_viv_files(folder: &str) -> Vec<String> {
         }
 
         if path.is_dir() {
-            let subfolder_paths = find_viv_files(path.to_str().unwrap());
+            let subfolder_paths = find_viv_files(path.to_str().unwrap())?;
             file_paths.extend(subfolder_paths);
         }
     }
 
-    file_paths
+    file_paths.sort();
+    Ok(file_paths)
+}
+
+fn find_viv_files_or_exit(folder: &str) -> Vec<String> {
+    find_viv_files(folder).unwrap_or_else(|err| {
+        eprintln!("ERROR: could not read {folder}: {err}");
+        exit(1);
+    })
 }
 
-fn test(folder: &str) {
-    for path in find_viv_files(folder) {
-        let exit_code = run(true, &path);
+/// Runs the file, comparing its output with `name.stdout` and `name.stderr` when they exist.
+/// The two are captured separately, so `eprint` output only matches `name.stderr`.
+fn run_test_file(viv_config: &VivConfig, config: &CompilerConfig, path: &str) -> i32 {
+    const STREAMS: [&str; 2] = ["stdout", "stderr"];
+
+    let expected_path = |stream: &str| Path::new(path).with_extension(stream);
+    if !STREAMS.into_iter().any(|stream| expected_path(stream).is_file()) {
+        return run(viv_config, config, path);
+    }
+
+    let exe_file = temp_file::empty();
+    let exe_file = exe_file.path().to_str().unwrap();
+    build(viv_config, config, None, path, exe_file);
+    let (exit_code, stdout, stderr) = run_exe_capture(exe_file, &[]);
+    print!("{stdout}");
+    eprint!("{stderr}");
+
+    for (stream, actual) in STREAMS.into_iter().zip([&stdout, &stderr]) {
+        let Ok(expected) = fs::read_to_string(expected_path(stream)) else {
+            continue;
+        };
+        if expected != *actual {
+            eprintln!("ERROR: {path} wrote a different {stream}");
+            eprintln!("expected:\n{expected}\ngot:\n{actual}");
+            return 1;
+        }
+    }
+
+    exit_code
+}
+
+fn test(viv_config: &VivConfig, config: &CompilerConfig, folder: &str) {
+    for path in find_viv_files_or_exit(folder) {
+        let exit_code = run_test_file(viv_config, config, &path);
         if exit_code != 0 {
             return;
         }
     }
 }
 
+fn clean(folder: &str, dry_run: bool) {
+    const ARTIFACT_EXTENSIONS: [&str; 2] = ["ll", "o"];
+
+    for path in find_viv_files_or_exit(folder) {
+        for extension in ARTIFACT_EXTENSIONS {
+            let artifact = Path::new(&path).with_extension(extension);
+            if !artifact.is_file() {
+                continue;
+            }
+
+            println!("{}", artifact.display());
+            if !dry_run {
+                fs::remove_file(&artifact).unwrap();
+            }
+        }
+    }
+}
+
+fn doc(config: &CompilerConfig, input_file: &str, output_dir: &Path) {
+    let (name, code) = read_source(input_file);
+    let config = with_source_dir(config, input_file);
+    let pages = generate_docs(name, &code, &config).unwrap_or_else(|err| {
+        report_error(&code, &err);
+        exit(1);
+    });
+
+    let written = fs::create_dir_all(output_dir).and_then(|()| {
+        pages
+            .iter()
+            .try_for_each(|(file_name, html)| fs::write(output_dir.join(file_name), html))
+    });
+    if let Err(err) = written {
+        eprintln!("ERROR: could not write to {}: {err}", output_dir.display());
+        exit(1);
+    }
+    println!("{}", output_dir.join("index.html").display());
+}
+
+fn explain(code: &str) {
+    match ErrorCode::from_code(code) {
+        Some(code) => explain_error(code),
+        None => {
+            eprintln!("ERROR: unknown error code {code}");
+            exit(1);
+        }
+    }
+}
+
 fn main() {
     let args = Args::parse();
 
-    match args.command {
+    if let Some(code) = args.explain {
+        explain(&code);
+        return;
+    }
+    if args.command.is_none() && args.eval.is_empty() {
+        Args::command()
+            .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+            .exit();
+    }
+    if args.command.is_some() && !args.eval.is_empty() {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, "-e can not be used with a subcommand")
+            .exit();
+    }
+
+    let viv_config = VivConfig::load().unwrap_or_else(|err| {
+        eprintln!("ERROR: invalid config file {}", err);
+        exit(1);
+    });
+    let config = CompilerConfig {
+        optimize: viv_config.optimize && !args.no_optimize,
+        verbose: args.verbose,
+        target: None,
+        shared: false,
+        static_link: false,
+        pgo: PgoMode::None,
+        lto: false,
+        sysroot: None,
+        warn_shadowing: args.warn_shadowing,
+        warn_dead_functions: args.warn_dead_functions,
+        warn_dead_stores: args.warn_dead_stores,
+        small_ints: args.small_ints,
+        test: false,
+        bench: false,
+        defines: CompilerConfig::default()
+            .defines
+            .into_iter()
+            .chain(args.define)
+            .collect(),
+        print_ast: args.print_ast,
+        print_ast_after_pass: args.print_ast_after_pass,
+        source_dir: None,
+        cache_dir: if args.no_cache {
+            None
+        } else {
+            args.cache_dir.or_else(default_cache_dir)
+        },
+    };
+
+    let Some(command) = args.command else {
+        exit(eval(&viv_config, &config, &args.eval));
+    };
+
+    match command {
         Command::Build {
             input_file,
             output_file,
-        } => build(!args.no_optimize, &input_file, &output_file),
-        Command::Run { input_file } => exit(run(!args.no_optimize, &input_file)),
+            output_dir,
+            target,
+            linker,
+            shared,
+            static_link,
+            sysroot,
+            pgo_generate,
+            pgo_use,
+            lto,
+        } => {
+            let pgo = match pgo_use {
+                Some(profile) => PgoMode::Use(profile),
+                None if pgo_generate => PgoMode::Generate,
+                None => PgoMode::None,
+            };
+            let config = CompilerConfig {
+                target,
+                shared,
+                static_link,
+                pgo,
+                lto,
+                sysroot,
+                ..config
+            };
+            let output_file = output_path(&input_file, output_file, &output_dir, "");
+            build(
+                &viv_config,
+                &config,
+                linker.as_ref(),
+                &input_file,
+                &output_file,
+            );
+        }
+        Command::Run {
+            input_file,
+            test,
+            bench,
+        } => {
+            let config = CompilerConfig {
+                test,
+                bench,
+                ..config
+            };
+            exit(run(&viv_config, &config, &input_file));
+        }
         Command::Ir {
             input_file,
             output_fie,
-        } => ir(!args.no_optimize, &input_file, &output_fie),
-        Command::Test { folder } => test(&folder),
+            output_dir,
+        } => {
+            let output_file = output_path(&input_file, output_fie, &output_dir, "ll");
+            ir(&config, &input_file, &output_file);
+        }
+        Command::Test { folder } => {
+            let folder = folder
+                .or_else(|| viv_config.test_folder.clone())
+                .expect("No test folder given and none set in viv.toml");
+            let config = CompilerConfig {
+                test: true,
+                ..config
+            };
+            test(&viv_config, &config, &folder);
+        }
+        Command::Clean { folder, dry_run } => clean(&folder, dry_run),
+        Command::Doc {
+            input_file,
+            output_dir,
+        } => doc(&config, &input_file, &output_dir),
+        Command::Lsp => run_language_server(),
     }
 }
@@ -3,6 +3,7 @@ use std::ops::ControlFlow;
 
 use super::source_location::SourceLocation;
 use super::tokens::{Token, TokenValue};
+use crate::diagnostics::ErrorCode;
 use crate::CompilerResult;
 
 pub struct Lexer {
@@ -12,6 +13,8 @@ pub struct Lexer {
     current_colum: usize,
 
     tokens: Vec<Token>,
+    /// Attached to the next token that is emitted
+    pending_doc_comment: Option<String>,
 }
 
 impl Lexer {
@@ -21,6 +24,7 @@ impl Lexer {
             current_line: 1,
             current_colum: 0,
             tokens: Vec::with_capacity(code.len() / 2),
+            pending_doc_comment: None,
         }
     }
 
@@ -71,18 +75,22 @@ impl Lexer {
     fn emit_token(&mut self, len: usize, value: TokenValue) {
         let location = SourceLocation::new(
             self.current_line,
-            self.current_colum - len + 1,
-            self.current_colum,
+            (self.current_colum + 1).saturating_sub(len).max(1),
+            self.current_colum.max(1),
         );
         self.tokens.push(Token {
             value,
             source_location: location,
+            doc_comment: self.pending_doc_comment.take(),
         });
     }
 
     fn parse_maybe_two(&mut self, next_char: char, if_not: TokenValue, if_is: TokenValue) {
         match self.peek() {
-            Some(c) if c == next_char => self.emit_token(2, if_is),
+            Some(c) if c == next_char => {
+                self.advance();
+                self.emit_token(2, if_is);
+            }
             _ => self.emit_token(1, if_not)
         }
     }
@@ -102,6 +110,10 @@ impl Lexer {
                             self.advance();
                             self.take_while(|c| c != '\n');
                         }
+                        Some('*') => {
+                            self.advance();
+                            self.consume_block_comment();
+                        }
                         _ => self.emit_token(1, TokenValue::ForwardSlash),
                     }
                 }
@@ -136,10 +148,21 @@ impl Lexer {
                     }
                 },
                 ',' => self.emit_token(1, TokenValue::Comma),
+                '.' if self.peek() == Some('.') && self.code.get(1) == Some(&'.') => {
+                    self.advance();
+                    self.advance();
+                    self.emit_token(3, TokenValue::DotDotDot);
+                }
+                '.' => self.emit_token(1, TokenValue::Dot),
+                '#' => self.emit_token(1, TokenValue::Hash),
+                ':' => self.parse_maybe_two(':', TokenValue::Colon, TokenValue::ColonColon),
+                '&' => self.parse_maybe_two('&', TokenValue::Ampersand, TokenValue::AndAnd),
                 '(' => self.emit_token(1, TokenValue::OpenParen),
                 ')' => self.emit_token(1, TokenValue::CloseParen),
                 '{' => self.emit_token(1, TokenValue::OpenBracket),
                 '}' => self.emit_token(1, TokenValue::CloseBracket),
+                '[' => self.emit_token(1, TokenValue::OpenSquare),
+                ']' => self.emit_token(1, TokenValue::CloseSquare),
                 char if char.is_ascii_digit() => {
                     let digits = char.to_string() + &self.take_while(|c| c.is_ascii_digit());
                     self.emit_token(digits.len(), TokenValue::Number(digits));
@@ -149,7 +172,11 @@ impl Lexer {
                         break;
                     }
                 }
-                char if char.is_alphabetic() || char == '_' => {
+                // Line continuation, newlines are whitespace anyway so this just skips the newline
+                '\\' if self.peek() == Some('\n') => {
+                    self.advance();
+                }
+                char if unicode_ident::is_xid_start(char) || char == '_' => {
                     self.consume_identifier(char);
                 }
                 _ => {
@@ -159,7 +186,7 @@ impl Lexer {
                             self.current_colum,
                             self.current_colum,
                         ),
-                        format!("invalid char {char}"),
+                        ErrorCode::InvalidCharacter.tag(format!("invalid char {char}")),
                     ));
                     break;
                 }
@@ -169,45 +196,160 @@ impl Lexer {
         }
 
         if error.is_ok() {
-            self.emit_token(1, TokenValue::EndOfFile);
+            // The end of the file is just after the last character
+            let column = self.current_colum + 1;
+            self.tokens.push(Token {
+                value: TokenValue::EndOfFile,
+                source_location: SourceLocation::new(self.current_line, column, column),
+                doc_comment: None,
+            });
         }
 
         error.map(|_| self.tokens.clone())
     }
 
+    /// Skips a `/* */` comment, the content of a `/** */` comment is kept as a doc comment.
+    /// A comment that is never closed runs to the end of the file.
+    fn consume_block_comment(&mut self) {
+        // `/**/` is an empty normal comment
+        let is_doc_comment = self.peek() == Some('*') && self.code.get(1) != Some(&'/');
+
+        let mut content = String::new();
+        while let Some(char) = self.advance() {
+            if char == '*' && self.peek() == Some('/') {
+                self.advance();
+                break;
+            }
+            content.push(char);
+        }
+
+        if is_doc_comment {
+            let lines: Vec<&str> = content[1..]
+                .lines()
+                .map(|line| line.trim().trim_start_matches('*').trim())
+                .collect();
+            self.pending_doc_comment = Some(lines.join("\n").trim().to_string());
+        }
+    }
+
     fn consume_identifier(&mut self, char: char) {
-        let word =
-            char.to_string() + &self.take_while(|c| c.is_alphabetic() || c == '_');
+        // Unicode identifiers, XID_Continue already includes `_` and the digits
+        let word = char.to_string() + &self.take_while(unicode_ident::is_xid_continue);
+        let word_length = word.chars().count();
         match word.as_str() {
             "print" => self.emit_token(5, TokenValue::Print),
+            "eprint" => self.emit_token(6, TokenValue::PrintErr),
             "assert" => self.emit_token(6, TokenValue::Assert),
+            "assert_type" => self.emit_token(11, TokenValue::AssertType),
+            "assert_eq" => self.emit_token(9, TokenValue::AssertEq),
             "fn" => self.emit_token(2, TokenValue::Fn),
+            "pub" => self.emit_token(3, TokenValue::Pub),
+            "extern" => self.emit_token(6, TokenValue::Extern),
+            "struct" => self.emit_token(6, TokenValue::Struct),
+            "type" => self.emit_token(4, TokenValue::Type),
             "return" => self.emit_token(6, TokenValue::Return),
             "true" => self.emit_token(4, TokenValue::True),
             "false" => self.emit_token(5, TokenValue::False),
             "test" => self.emit_token(4, TokenValue::Test),
+            "bench" => self.emit_token(5, TokenValue::Bench),
             "is" => self.emit_token(2, TokenValue::Is),
             "if" => self.emit_token(2, TokenValue::If),
             "else" => self.emit_token(4, TokenValue::Else),
-            _ => self.emit_token(word.len(), TokenValue::Identifier(word)),
+            "while" => self.emit_token(5, TokenValue::While),
+            "do" => self.emit_token(2, TokenValue::Do),
+            "repeat" => self.emit_token(6, TokenValue::Repeat),
+            "switch" => self.emit_token(6, TokenValue::Switch),
+            "case" => self.emit_token(4, TokenValue::Case),
+            "default" => self.emit_token(7, TokenValue::Default),
+            "for" => self.emit_token(3, TokenValue::For),
+            "break" => self.emit_token(5, TokenValue::Break),
+            "defer" => self.emit_token(5, TokenValue::Defer),
+            "in" => self.emit_token(2, TokenValue::In),
+            "not" => self.emit_token(3, TokenValue::Not),
+            _ => self.emit_token(word_length, TokenValue::Identifier(word)),
         }
     }
 
     fn consume_string(&mut self, error: &mut Result<(), (SourceLocation, String)>) -> ControlFlow<()> {
+        let start_line = self.current_line;
+        let start_column = self.current_colum;
+
         let string_content = self.take_while(|c| c != '"' && c != '\n');
+        let string_length = string_content.chars().count();
+
         let end = self.advance();
         if end != Some('"') {
+            // Point at the string itself, the newline would put us on the next line
             *error = Err((
-                SourceLocation::new(
-                    self.current_line,
-                    self.current_colum,
-                    self.current_colum,
-                ),
-                "Unclosed String".to_string(),
+                SourceLocation::new(start_line, start_column, start_column + string_length),
+                ErrorCode::UnclosedString.tag("Unclosed String"),
             ));
             return ControlFlow::Break(());
         }
-        self.emit_token(string_content.len() + 2, TokenValue::String(string_content));
+        self.emit_token(string_length + 2, TokenValue::String(string_content));
         ControlFlow::Continue(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_values(code: &str) -> Vec<TokenValue> {
+        Lexer::new(code)
+            .parse_file()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.value)
+            .collect()
+    }
+
+    #[test]
+    fn two_character_tokens_consume_both() {
+        assert_eq!(
+            token_values("->a::b&&c"),
+            vec![
+                TokenValue::Arrow,
+                TokenValue::Identifier("a".to_string()),
+                TokenValue::ColonColon,
+                TokenValue::Identifier("b".to_string()),
+                TokenValue::AndAnd,
+                TokenValue::Identifier("c".to_string()),
+                TokenValue::EndOfFile,
+            ]
+        );
+        assert_eq!(
+            token_values("- :"),
+            vec![TokenValue::Minus, TokenValue::Colon, TokenValue::EndOfFile]
+        );
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let tokens = Lexer::new("\"héllo\" ünï = 1").parse_file().unwrap();
+        let locations: Vec<SourceLocation> =
+            tokens.iter().map(|token| token.source_location).collect();
+        assert_eq!(
+            locations,
+            vec![
+                SourceLocation::new(1, 1, 7),
+                SourceLocation::new(1, 9, 11),
+                SourceLocation::new(1, 13, 13),
+                SourceLocation::new(1, 15, 15),
+                SourceLocation::new(1, 16, 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn number_overflow_is_an_error() {
+        assert_eq!(
+            token_values("99999999999"),
+            vec![TokenValue::Number("99999999999".to_string()), TokenValue::EndOfFile]
+        );
+
+        let (_, message) =
+            crate::parser::parse_file("fn main() -> Num { return 99999999999; }").unwrap_err();
+        assert!(message.contains("Number 99999999999 does not fit in a Num"));
+    }
+}
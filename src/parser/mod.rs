@@ -1,16 +1,58 @@
 mod syntax_parser;
 mod lexer;
+mod preprocessor;
 mod source_location;
 mod tokens;
 
 pub use source_location::SourceLocation;
+pub use tokens::{Token, TokenValue};
+
+use std::{fmt::Write, path::Path};
 
 use crate::CompilerResult;
 
-pub fn parse_file(code: &str) -> CompilerResult<crate::ast::File> {
+/// Applies the `#if` directives, see `PreProcessor`
+pub fn preprocess(
+    code: &str,
+    defines: &std::collections::HashMap<String, String>,
+) -> CompilerResult<String> {
+    preprocessor::PreProcessor::new(defines).process(code)
+}
+
+pub fn tokenize(code: &str) -> CompilerResult<Vec<Token>> {
     let mut lexer = lexer::Lexer::new(code);
-    let tokens = lexer.parse_file()?;
+    lexer.parse_file()
+}
 
-    let mut parser = syntax_parser::SyntaxParser::new(tokens);
+/// Runs the syntax parser on already lexed tokens, `include_str!` reads from `source_dir`.
+/// The parser recovers from errors, so all of them are returned in order.
+pub fn parse_tokens(
+    tokens: Vec<Token>,
+    source_dir: &Path,
+) -> Result<crate::ast::File, Vec<(SourceLocation, String)>> {
+    let mut parser = syntax_parser::SyntaxParser::new(tokens, source_dir.to_path_buf());
     parser.parse_file()
-}
\ No newline at end of file
+}
+
+pub fn parse_file(code: &str) -> CompilerResult<crate::ast::File> {
+    parse_tokens(tokenize(code)?, Path::new("")).map_err(|errors| combine_errors(code, errors))
+}
+
+/// Reports several errors as the first one, the others are added to its message with their
+/// own highlighted lines so they print the same way
+pub fn combine_errors(
+    code: &str,
+    errors: Vec<(SourceLocation, String)>,
+) -> (SourceLocation, String) {
+    let mut errors = errors.into_iter();
+    let (location, mut message) = errors.next().expect("There is at least one error");
+    for (other_location, other_message) in errors {
+        write!(
+            message,
+            "\n\n{}\nERROR: {other_message}",
+            other_location.get_line_highlights(code)
+        )
+        .unwrap();
+    }
+    (location, message)
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use super::SourceLocation;
+use crate::diagnostics::ErrorCode;
+use crate::CompilerResult;
+
+/// An `#if` that has not been closed yet
+struct OpenIf {
+    /// Whether the current branch of this `#if` is compiled, ignoring the ones around it
+    active: bool,
+    seen_else: bool,
+    location: SourceLocation,
+}
+
+/// Handles `#if`, `#else` and `#endif` lines before the code is lexed.
+/// Lines that are left out are replaced with empty lines, so line numbers do not change.
+pub struct PreProcessor<'a> {
+    defines: &'a HashMap<String, String>,
+}
+
+impl<'a> PreProcessor<'a> {
+    pub fn new(defines: &'a HashMap<String, String>) -> Self {
+        Self { defines }
+    }
+
+    pub fn process(&self, code: &str) -> CompilerResult<String> {
+        let mut open_ifs: Vec<OpenIf> = Vec::new();
+        let mut output = Vec::new();
+
+        for (index, line) in code.lines().enumerate() {
+            let trimmed = line.trim();
+            let location = SourceLocation::new(index + 1, 1, line.chars().count().max(1));
+
+            if let Some(condition) = trimmed.strip_prefix("#if ") {
+                open_ifs.push(OpenIf {
+                    active: self.evaluate(condition.trim(), location)?,
+                    seen_else: false,
+                    location,
+                });
+                output.push("");
+            } else if trimmed == "#else" {
+                match open_ifs.last_mut() {
+                    Some(open_if) if !open_if.seen_else => {
+                        open_if.active = !open_if.active;
+                        open_if.seen_else = true;
+                    }
+                    Some(_) => return Err(Self::error(location, "#else after #else")),
+                    None => return Err(Self::error(location, "#else without #if")),
+                }
+                output.push("");
+            } else if trimmed == "#endif" {
+                if open_ifs.pop().is_none() {
+                    return Err(Self::error(location, "#endif without #if"));
+                }
+                output.push("");
+            } else if open_ifs.iter().all(|open_if| open_if.active) {
+                output.push(line);
+            } else {
+                output.push("");
+            }
+        }
+
+        if let Some(open_if) = open_ifs.last() {
+            return Err(Self::error(
+                open_if.location,
+                "#if is never closed with #endif",
+            ));
+        }
+
+        // `lines` drops the newline at the end of the file
+        let mut output = output.join("\n");
+        if code.ends_with('\n') {
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    /// `KEY == "value"`, `KEY != "value"` or just `KEY` to check if it is defined
+    fn evaluate(&self, condition: &str, location: SourceLocation) -> CompilerResult<bool> {
+        let comparison = [("==", true), ("!=", false)]
+            .into_iter()
+            .find_map(|(operator, equal)| {
+                condition
+                    .split_once(operator)
+                    .map(|(key, value)| (key, value, equal))
+            });
+
+        let Some((key, value, equal)) = comparison else {
+            Self::check_key(condition, location)?;
+            return Ok(self.defines.contains_key(condition));
+        };
+
+        let key = key.trim();
+        Self::check_key(key, location)?;
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+
+        Ok((self.defines.get(key).map(String::as_str) == Some(value)) == equal)
+    }
+
+    fn check_key(key: &str, location: SourceLocation) -> CompilerResult<()> {
+        if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(Self::error(
+                location,
+                format!("Invalid #if condition {key}"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn error(
+        location: SourceLocation,
+        message: impl std::fmt::Display,
+    ) -> (SourceLocation, String) {
+        (location, ErrorCode::UnexpectedToken.tag(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_final_newline() {
+        let defines = HashMap::new();
+        let preprocessor = PreProcessor::new(&defines);
+
+        assert_eq!(preprocessor.process("print 1;\n").unwrap(), "print 1;\n");
+        assert_eq!(preprocessor.process("print 1;").unwrap(), "print 1;");
+        assert_eq!(preprocessor.process("#if DEBUG\nprint 1;\n#endif\n").unwrap(), "\n\n\n");
+    }
+}
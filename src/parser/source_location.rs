@@ -4,6 +4,8 @@ pub struct SourceLocation {
     pub line_end: usize,
     pub char_start: usize,
     pub char_end: usize,
+    /// The index of the file in `compile_multiple_to_ir`, always 0 for a single file
+    pub file: usize,
 }
 
 impl SourceLocation {
@@ -14,6 +16,7 @@ impl SourceLocation {
             line_end: line,
             char_start,
             char_end,
+            file: 0,
         }
     }
 
@@ -23,6 +26,7 @@ impl SourceLocation {
             line_end: usize::max(a.line_end, b.line_end),
             char_start: usize::min(a.char_start, b.char_start),
             char_end: usize::max(a.char_end, b.char_end),
+            file: a.file,
         }
     }
 
@@ -43,9 +47,10 @@ impl SourceLocation {
             .map(|lin_num| lin_num.to_string().len())
             .max()
             .unwrap();
-        let pointer_padding = max_line_number_width + " | ".len() + self.char_start - 1;
-        let pointers =
-            " ".repeat(pointer_padding) + &"^".repeat(self.char_end - self.char_start + 1);
+        let pointer_padding =
+            max_line_number_width + " | ".len() + self.char_start.saturating_sub(1);
+        let pointers = " ".repeat(pointer_padding)
+            + &"^".repeat((self.char_end + 1).saturating_sub(self.char_start));
 
         format!("{lines}\n{pointers}")
     }
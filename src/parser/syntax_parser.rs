@@ -1,28 +1,107 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, path::PathBuf};
 
 use super::{
     tokens::{Token, TokenValue},
     SourceLocation,
 };
-use crate::{ast, CompilerResult};
+use crate::{ast, diagnostics::ErrorCode, CompilerResult};
 
 pub struct SyntaxParser {
     tokens: VecDeque<Token>,
+    loop_depth: usize,
+    /// How many code blocks we are inside, the function body is 1
+    block_depth: usize,
+    /// Where we pretend the EndOfFile token is once we run out of tokens
+    end_location: SourceLocation,
+    /// Folder `include_str!` paths are relative to
+    source_dir: PathBuf,
+    /// The errors recovered from so far, parsing continues after each of them
+    errors: Vec<(SourceLocation, String)>,
+    /// The last token `advance` returned, an error on a `;` already ended its statement
+    previous: TokenValue,
 }
 
 impl SyntaxParser {
-    pub fn new(tokens: Vec<Token>) -> Self {
+    pub fn new(tokens: Vec<Token>, source_dir: PathBuf) -> Self {
+        let end_location = tokens
+            .last()
+            .map_or(SourceLocation::new(1, 1, 1), |token| token.source_location);
+
         Self {
             tokens: tokens.into_iter().collect(),
+            loop_depth: 0,
+            block_depth: 0,
+            end_location,
+            source_dir,
+            errors: Vec::new(),
+            previous: TokenValue::EndOfFile,
         }
     }
 
     fn advance(&mut self) -> Token {
-        self.tokens.pop_front().unwrap()
+        let token = self.tokens.pop_front().unwrap_or(Token {
+            value: TokenValue::EndOfFile,
+            source_location: self.end_location,
+            doc_comment: None,
+        });
+        self.previous = token.value.clone();
+        token
+    }
+
+    /// Skips the rest of a statement that failed to parse, up to and including its `;` or the
+    /// block it ends with. The `}` closing the surrounding block is left for the block to parse.
+    fn skip_statement(&mut self) {
+        if self.previous == TokenValue::Semicolon {
+            return;
+        }
+
+        let mut depth = 0_usize;
+        loop {
+            match self.peek() {
+                TokenValue::EndOfFile => return,
+                TokenValue::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenValue::OpenBracket => depth += 1,
+                TokenValue::CloseBracket if depth == 0 => return,
+                TokenValue::CloseBracket => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Skips to the start of the next top level definition
+    fn skip_toplevel_statement(&mut self) {
+        while !matches!(
+            self.peek(),
+            TokenValue::Fn
+                | TokenValue::Pub
+                | TokenValue::Hash
+                | TokenValue::Extern
+                | TokenValue::Struct
+                | TokenValue::Type
+                | TokenValue::EndOfFile
+        ) {
+            self.advance();
+        }
     }
 
     fn peek(&self) -> TokenValue {
-        self.tokens[0].clone().value
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> TokenValue {
+        self.tokens
+            .get(n)
+            .map_or(TokenValue::EndOfFile, |token| token.value.clone())
     }
 
     fn expect(&mut self, expected_token: &TokenValue) -> CompilerResult<()> {
@@ -32,7 +111,9 @@ impl SyntaxParser {
         } else {
             Err((
                 token.source_location,
-                format!("expected {:?} found {:?}", expected_token, token.value),
+                ErrorCode::UnexpectedToken.tag(format!(
+                    "expected {:?} found {:?}", expected_token, token.value
+                )),
             ))
         }
     }
@@ -41,31 +122,54 @@ impl SyntaxParser {
         let token = self.advance();
         let literal = match token.value {
             TokenValue::String(content) => ast::LiteralType::String(content),
-            TokenValue::Number(digits) => ast::LiteralType::Number(digits.parse().unwrap()),
+            TokenValue::Number(digits) => {
+                ast::LiteralType::Number(Self::parse_number(&digits, token.source_location)?)
+            }
             TokenValue::False => ast::LiteralType::Boolean(false),
             TokenValue::True => ast::LiteralType::Boolean(true),
             TokenValue::Minus => {
-                let digits = self.advance();
-                match digits.value {
-                    TokenValue::Number(digits) => {
-                        ast::LiteralType::Number(-digits.parse::<i32>().unwrap())
-                    }
-                    _ => {
+                let digits_token = self.advance();
+                match digits_token.value {
+                    TokenValue::Number(digits) => ast::LiteralType::Number(Self::parse_number(
+                        &format!("-{digits}"),
+                        SourceLocation::combine(&token.source_location, &digits_token.source_location),
+                    )?),
+                    value => {
                         return Err((
-                            digits.source_location,
-                            format!("Expected Number(_) got {:?}", digits.value),
+                            digits_token.source_location,
+                            ErrorCode::UnexpectedToken.tag(format!(
+                                "Expected Number(_) got {:?}", value
+                            )),
                         ))
                     }
                 }
             }
+            TokenValue::OpenSquare => return self.parse_array(token.source_location),
             // Lets just special case this since this is a convenient place to parse this
             TokenValue::Identifier(name) => {
-                return Ok(ast::Expression::Var(token.source_location.into(), name))
+                if name == "include_str" && self.peek() == TokenValue::Bang {
+                    return self.parse_include_str(token.source_location);
+                }
+                if let TokenValue::OpenParen = self.peek() {
+                    return self.parse_function_call(name, token.source_location);
+                }
+                // `Num::MAX`, looked up like a variable
+                if let TokenValue::ColonColon = self.peek() {
+                    self.advance();
+                    let (constant, constant_location) = self.parse_name()?;
+                    return Ok(ast::Expression::Var(
+                        SourceLocation::combine(&token.source_location, &constant_location).into(),
+                        format!("{name}::{constant}"),
+                    ));
+                }
+                return Ok(ast::Expression::Var(token.source_location.into(), name));
             }
             value => {
                 return Err((
                     token.source_location,
-                    format!("Expected String(_), Number(_) or Minus got {:?}", value),
+                    ErrorCode::UnexpectedToken.tag(format!(
+                        "Expected String(_), Number(_) or Minus got {:?}", value
+                    )),
                 ))
             }
         };
@@ -76,22 +180,184 @@ impl SyntaxParser {
         ))
     }
 
+    /// Parses the rest of `include_str!("path")` into a literal with the contents of the file
+    fn parse_include_str(
+        &mut self,
+        name_location: SourceLocation,
+    ) -> CompilerResult<ast::Expression> {
+        self.expect(&TokenValue::Bang)?;
+        self.expect(&TokenValue::OpenParen)?;
+        let path_token = self.advance();
+        let path = match path_token.value {
+            TokenValue::String(path) => path,
+            value => {
+                return Err((
+                    path_token.source_location,
+                    ErrorCode::UnexpectedToken.tag(format!(
+                        "include_str! expects a String path got {:?}", value
+                    )),
+                ))
+            }
+        };
+        self.expect(&TokenValue::CloseParen)?;
+        let location = SourceLocation::combine(&name_location, &path_token.source_location);
+
+        let contents = std::fs::read_to_string(self.source_dir.join(&path)).map_err(|err| {
+            (
+                location,
+                ErrorCode::FileNotFound.tag(format!("Could not include {path}: {err}")),
+            )
+        })?;
+        // The literal ends up as a C string
+        if contents.contains('\0') {
+            return Err((
+                location,
+                ErrorCode::FileNotFound
+                    .tag(format!("Could not include {path}, it contains a NUL byte")),
+            ));
+        }
+
+        Ok(ast::Expression::Literal(
+            location.into(),
+            ast::LiteralType::String(contents),
+        ))
+    }
+
+    fn parse_number(digits: &str, location: SourceLocation) -> CompilerResult<i32> {
+        digits.parse().map_err(|_| {
+            (
+                location,
+                ErrorCode::TypeMismatch.tag(format!("Number {} does not fit in a Num", digits)),
+            )
+        })
+    }
+
+    fn parse_function_call(
+        &mut self,
+        name: String,
+        name_location: SourceLocation,
+    ) -> CompilerResult<ast::Expression> {
+        self.expect(&TokenValue::OpenParen)?;
+
+        let mut arguments = Vec::new();
+        while self.peek() != TokenValue::CloseParen {
+            arguments.push(self.parse_expression()?);
+
+            if self.peek() != TokenValue::CloseParen {
+                self.expect(&TokenValue::Comma)?;
+            }
+        }
+        let close_paren = self.advance();
+
+        Ok(ast::Expression::FunctionCall {
+            name,
+            arguments,
+            metadata: SourceLocation::combine(&name_location, &close_paren.source_location)
+                .into(),
+            is_tail_call: false,
+        })
+    }
+
+    /// Parses the rest of `[expr, expr, ...]`
+    fn parse_array(&mut self, open_location: SourceLocation) -> CompilerResult<ast::Expression> {
+        let mut elements = Vec::new();
+        while self.peek() != TokenValue::CloseSquare {
+            elements.push(self.parse_expression()?);
+
+            if self.peek() != TokenValue::CloseSquare {
+                self.expect(&TokenValue::Comma)?;
+            }
+        }
+        let close_square = self.advance();
+
+        Ok(ast::Expression::Array(
+            SourceLocation::combine(&open_location, &close_square.source_location).into(),
+            elements,
+        ))
+    }
+
+    /// `(expr)` or the tuple `(expr, expr, ...)`
     fn parse_group(&mut self) -> CompilerResult<ast::Expression> {
         match self.peek() {
             TokenValue::OpenParen => {
-                self.advance();
+                let open_location = self.advance().source_location;
                 let expression = self.parse_expression()?;
-                self.expect(&TokenValue::CloseParen)?;
-                Ok(expression)
+                if self.peek() != TokenValue::Comma {
+                    self.expect(&TokenValue::CloseParen)?;
+                    return Ok(expression);
+                }
+
+                let mut elements = vec![expression];
+                while self.peek() == TokenValue::Comma {
+                    self.advance();
+                    elements.push(self.parse_expression()?);
+                }
+                let close_paren = self.advance();
+                if close_paren.value != TokenValue::CloseParen {
+                    return Err((
+                        close_paren.source_location,
+                        ErrorCode::UnexpectedToken.tag(format!(
+                            "Expected CloseParen got {:?}", close_paren.value
+                        )),
+                    ));
+                }
+
+                Ok(ast::Expression::Tuple(
+                    SourceLocation::combine(&open_location, &close_paren.source_location).into(),
+                    elements,
+                ))
             }
             _ => self.parse_literal(),
         }
     }
 
+    /// `expr.0.1`
+    fn parse_tuple_index(&mut self) -> CompilerResult<ast::Expression> {
+        let mut expression = self.parse_group()?;
+
+        while self.peek() == TokenValue::Dot {
+            self.advance();
+            let index_token = self.advance();
+            let index = match index_token.value {
+                TokenValue::Number(ref digits) => digits.parse().map_err(|_| {
+                    (
+                        index_token.source_location,
+                        ErrorCode::TypeMismatch.tag(format!("Tuple index {digits} is too large")),
+                    )
+                })?,
+                value => {
+                    return Err((
+                        index_token.source_location,
+                        ErrorCode::UnexpectedToken.tag(format!(
+                            "Expected Number(_) got {:?}", value
+                        )),
+                    ))
+                }
+            };
+
+            let location =
+                SourceLocation::combine(expression.location(), &index_token.source_location);
+            expression = ast::Expression::TupleIndex {
+                tuple: Box::new(expression),
+                index,
+                metadata: location.into(),
+            };
+        }
+
+        Ok(expression)
+    }
+
     fn parse_prefix(&mut self) -> CompilerResult<ast::Expression> {
         let op = match self.peek() {
             TokenValue::Bang => ast::PrefixOprator::Not,
-            _ => return self.parse_group(),
+            TokenValue::Ampersand => ast::PrefixOprator::AddressOf,
+            TokenValue::Star => ast::PrefixOprator::Deref,
+            TokenValue::Minus => match self.tokens.get(1).map(|token| &token.value) {
+                // Negative number literals are folded directly into the literal
+                Some(TokenValue::Number(_)) => return self.parse_tuple_index(),
+                _ => ast::PrefixOprator::Negate,
+            },
+            _ => return self.parse_tuple_index(),
         };
         let location = self.advance().source_location;
 
@@ -160,6 +426,19 @@ impl SyntaxParser {
                 TokenValue::LessThanEqual => ast::Comparison::LessThanEqual,
                 TokenValue::GreaterThan => ast::Comparison::GreaterThan,
                 TokenValue::GreaterThanEqual => ast::Comparison::GreaterThanEqual,
+                TokenValue::In => ast::Comparison::Contains,
+                TokenValue::Not => {
+                    self.advance();
+                    if self.peek() != TokenValue::In {
+                        let token = self.advance();
+                        return Err((
+                            token.source_location,
+                            ErrorCode::UnexpectedToken
+                                .tag(format!("expected In after Not, got {:?}", token.value)),
+                        ));
+                    }
+                    ast::Comparison::NotContains
+                }
                 _ => break,
             };
             self.advance();
@@ -187,14 +466,30 @@ impl SyntaxParser {
         self.parse_comparison()
     }
 
+    /// `print a, b;` or `eprint a, b;`
     fn parse_print(&mut self) -> CompilerResult<ast::Statement> {
-        self.advance(); // we assume this is only called once we know we have a print
-        let expression = self.parse_expression()?;
+        // we assume this is only called once we know we have a print
+        let keyword = self.advance().value;
+        let mut expressions = vec![self.parse_expression()?];
+        while self.peek() == TokenValue::Comma {
+            self.advance();
+            expressions.push(self.parse_expression()?);
+        }
         self.expect(&TokenValue::Semicolon)?;
-        Ok(ast::Statement::Print(expression))
+        if keyword == TokenValue::PrintErr {
+            Ok(ast::Statement::PrintErr(expressions))
+        } else {
+            Ok(ast::Statement::Print(expressions))
+        }
     }
 
     fn parse_assignment(&mut self) -> CompilerResult<ast::Statement> {
+        let assignment = self.parse_assignment_without_semicolon()?;
+        self.expect(&TokenValue::Semicolon)?;
+        Ok(assignment)
+    }
+
+    fn parse_assignment_without_semicolon(&mut self) -> CompilerResult<ast::Statement> {
         let name_token = self.advance();
         let var_name = match name_token.value {
             TokenValue::Identifier(name) => name,
@@ -203,7 +498,6 @@ impl SyntaxParser {
 
         self.expect(&TokenValue::Equal)?;
         let expression = self.parse_expression()?;
-        self.expect(&TokenValue::Semicolon)?;
 
         Ok(ast::Statement::Assignment {
             expression_location: name_token.source_location,
@@ -212,18 +506,96 @@ impl SyntaxParser {
         })
     }
 
-    fn parse_return(&mut self) -> CompilerResult<ast::Statement> {
+    /// `_ = expression;`
+    fn parse_discard(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
         self.advance();
+        self.parse_expression_statement()
+    }
+
+    fn parse_expression_statement(&mut self) -> CompilerResult<ast::Statement> {
+        let expression = self.parse_expression()?;
+        self.expect(&TokenValue::Semicolon)?;
+        Ok(ast::Statement::Discard(expression))
+    }
+
+    fn parse_return(&mut self) -> CompilerResult<ast::Statement> {
+        let location = self.advance().source_location;
+        if let TokenValue::Semicolon = self.peek() {
+            self.advance();
+            return Ok(ast::Statement::Return(None, location));
+        }
+
         let expression = self.parse_expression()?;
         self.expect(&TokenValue::Semicolon)?;
-        Ok(ast::Statement::Return(expression))
+        Ok(ast::Statement::Return(Some(expression), location))
     }
 
     fn parse_assert(&mut self) -> CompilerResult<ast::Statement> {
         self.advance();
         let expression = self.parse_expression()?;
+        let message = self.parse_assert_message()?;
+
+        self.expect(&TokenValue::Semicolon)?;
+        Ok(ast::Statement::Assert(expression, message))
+    }
+
+    /// The `, "message"` at the end of an assert, if there is one
+    fn parse_assert_message(&mut self) -> CompilerResult<Option<String>> {
+        if TokenValue::Comma != self.peek() {
+            return Ok(None);
+        }
+
+        self.advance();
+        let message_token = self.advance();
+        match message_token.value {
+            TokenValue::String(message) => Ok(Some(message)),
+            _ => Err((
+                message_token.source_location,
+                ErrorCode::UnexpectedToken
+                    .tag(format!("expected String(_), got {:?}", message_token.value)),
+            )),
+        }
+    }
+
+    fn parse_assert_type(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+        self.expect(&TokenValue::Bang)?;
+        self.expect(&TokenValue::OpenParen)?;
+        let expression = self.parse_expression()?;
+        self.expect(&TokenValue::Comma)?;
+
+        let (expected_type, location) = self.parse_type_name()?;
+
+        self.expect(&TokenValue::CloseParen)?;
+        self.expect(&TokenValue::Semicolon)?;
+
+        Ok(ast::Statement::AssertType {
+            expression,
+            expected_type,
+            location,
+        })
+    }
+
+    /// `assert_eq!(expected, actual, "message");`
+    fn parse_assert_eq(&mut self) -> CompilerResult<ast::Statement> {
+        let location = self.advance().source_location;
+        self.expect(&TokenValue::Bang)?;
+        self.expect(&TokenValue::OpenParen)?;
+        let expected = self.parse_expression()?;
+        self.expect(&TokenValue::Comma)?;
+        let actual = self.parse_expression()?;
+        let message = self.parse_assert_message()?;
+
+        self.expect(&TokenValue::CloseParen)?;
         self.expect(&TokenValue::Semicolon)?;
-        Ok(ast::Statement::Assert(expression))
+
+        Ok(ast::Statement::AssertEq {
+            expected,
+            actual,
+            message,
+            location,
+        })
     }
 
     fn parse_test(&mut self) -> CompilerResult<ast::Statement> {
@@ -234,7 +606,7 @@ impl SyntaxParser {
             _ => {
                 return Err((
                     name.source_location,
-                    "Expected String for name of test.".to_string(),
+                    ErrorCode::UnexpectedToken.tag("Expected String for name of test."),
                 ))
             }
         };
@@ -246,118 +618,814 @@ impl SyntaxParser {
         Ok(ast::Statement::Test(name, left))
     }
 
+    /// `bench "name" iterations -> expression;`
+    fn parse_bench(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+        let name = self.advance();
+        let name = match name.value {
+            TokenValue::String(value) => value,
+            _ => {
+                return Err((
+                    name.source_location,
+                    ErrorCode::UnexpectedToken.tag("Expected String for name of bench."),
+                ))
+            }
+        };
+
+        let iterations = self.parse_expression()?;
+        self.expect(&TokenValue::Arrow)?;
+        let body = self.parse_expression()?;
+        self.expect(&TokenValue::Semicolon)?;
+
+        Ok(ast::Statement::Bench {
+            name,
+            iterations,
+            body,
+        })
+    }
+
     fn parse_if(&mut self) -> CompilerResult<ast::Statement> {
         self.advance();
 
         let condition = self.parse_expression()?;
         let then = self.parse_codeblock()?;
 
-        let otherwise = if let TokenValue::Else = self.peek() {
+        let mut else_if_chains = Vec::new();
+        let mut otherwise = ast::CodeBody(Vec::new());
+
+        while let TokenValue::Else = self.peek() {
             self.advance();
-            match self.peek() {
-                TokenValue::If => ast::CodeBody(vec![self.parse_if()?]),
-                _ => self.parse_codeblock()?,
+
+            if let TokenValue::If = self.peek() {
+                self.advance();
+                let condition = self.parse_expression()?;
+                let body = self.parse_codeblock()?;
+                else_if_chains.push((condition, body));
+            } else {
+                otherwise = self.parse_codeblock()?;
+                break;
             }
-        } else {
-            ast::CodeBody(Vec::new())
-        };
+        }
 
         Ok(ast::Statement::If {
             condition,
             then,
+            else_if_chains,
             otherwise,
         })
     }
 
+    fn parse_switch(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+        let scrutinee = self.parse_expression()?;
+        self.expect(&TokenValue::OpenBracket)?;
+
+        let mut arms = Vec::new();
+        while let TokenValue::Case = self.peek() {
+            self.advance();
+            let (label, label_location) = match self.parse_literal()? {
+                ast::Expression::Literal(metadata, value) => (value, metadata.location),
+                label => {
+                    return Err((
+                        *label.location(),
+                        ErrorCode::UnexpectedToken.tag("Case labels have to be literals"),
+                    ))
+                }
+            };
+            self.expect(&TokenValue::Colon)?;
+            let body = self.parse_codeblock()?;
+            arms.push((label, label_location, body));
+        }
+
+        let default = if let TokenValue::Default = self.peek() {
+            self.advance();
+            self.expect(&TokenValue::Colon)?;
+            Some(self.parse_codeblock()?)
+        } else {
+            None
+        };
+
+        self.expect(&TokenValue::CloseBracket)?;
+        Ok(ast::Statement::Switch {
+            scrutinee,
+            arms,
+            default,
+        })
+    }
+
+    fn parse_while(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+
+        if let (TokenValue::Identifier(_), TokenValue::Equal) = (self.peek(), self.peek_nth(1)) {
+            return self.parse_while_assign();
+        }
+
+        let condition = self.parse_expression()?;
+
+        self.loop_depth += 1;
+        let body = self.parse_codeblock();
+        self.loop_depth -= 1;
+        let body = body?;
+
+        let else_body = if let TokenValue::Else = self.peek() {
+            self.advance();
+            Some(self.parse_codeblock()?)
+        } else {
+            None
+        };
+
+        Ok(ast::Statement::While {
+            condition,
+            body,
+            else_body,
+        })
+    }
+
+    /// `repeat count { ... }`
+    fn parse_repeat(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+        let count = self.parse_expression()?;
+
+        self.loop_depth += 1;
+        let body = self.parse_codeblock();
+        self.loop_depth -= 1;
+
+        Ok(ast::Statement::Repeat { count, body: body? })
+    }
+
+    /// `do { ... } while condition;`
+    fn parse_do_while(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+
+        self.loop_depth += 1;
+        let body = self.parse_codeblock();
+        self.loop_depth -= 1;
+        let body = body?;
+
+        self.expect(&TokenValue::While)?;
+        let condition = self.parse_expression()?;
+        self.expect(&TokenValue::Semicolon)?;
+
+        Ok(ast::Statement::DoWhile { body, condition })
+    }
+
+    /// `for element in array { ... }`
+    fn parse_for_each(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+        let var = self.parse_name()?.0;
+        self.expect(&TokenValue::In)?;
+        let array = self.parse_expression()?;
+
+        self.loop_depth += 1;
+        let body = self.parse_codeblock();
+        self.loop_depth -= 1;
+
+        Ok(ast::Statement::ForEach {
+            var,
+            array,
+            body: body?,
+        })
+    }
+
+    /// `for (i = 0; i < 10; i = i + 1) { ... }`
+    fn parse_c_style_for(&mut self) -> CompilerResult<ast::Statement> {
+        self.advance();
+        self.expect(&TokenValue::OpenParen)?;
+        let init = self.parse_loop_assignment("init")?;
+        self.expect(&TokenValue::Semicolon)?;
+        let condition = self.parse_expression()?;
+        self.expect(&TokenValue::Semicolon)?;
+        let update = self.parse_loop_assignment("update")?;
+        self.expect(&TokenValue::CloseParen)?;
+
+        self.loop_depth += 1;
+        let body = self.parse_codeblock();
+        self.loop_depth -= 1;
+
+        Ok(ast::Statement::CStyleFor {
+            init: Box::new(init),
+            condition,
+            update: Box::new(update),
+            body: body?,
+        })
+    }
+
+    /// The `init` or `update` of a C style for loop, which have to be assignments
+    fn parse_loop_assignment(&mut self, part: &str) -> CompilerResult<ast::Statement> {
+        if let (TokenValue::Identifier(_), TokenValue::Equal) = (self.peek(), self.peek_nth(1)) {
+            return self.parse_assignment_without_semicolon();
+        }
+
+        let token = self.advance();
+        Err((
+            token.source_location,
+            ErrorCode::UnexpectedToken.tag(format!(
+                "expected an assignment as the {part} of the for loop, got {:?}",
+                token.value
+            )),
+        ))
+    }
+
+    /// Parses the rest of `while x = expr, condition { ... }`
+    fn parse_while_assign(&mut self) -> CompilerResult<ast::Statement> {
+        let assignment = self.parse_assignment_without_semicolon()?;
+        self.expect(&TokenValue::Comma)?;
+        let condition = self.parse_expression()?;
+
+        self.loop_depth += 1;
+        let body = self.parse_codeblock();
+        self.loop_depth -= 1;
+
+        Ok(ast::Statement::WhileAssign {
+            assignment: Box::new(assignment),
+            condition,
+            body: body?,
+        })
+    }
+
+    /// `defer statement;`, runs the statement when the function returns
+    fn parse_defer(&mut self) -> CompilerResult<ast::Statement> {
+        let location = self.advance().source_location;
+        // Deferring inside a branch or loop would need to track at runtime if it was reached
+        if self.block_depth != 1 {
+            return Err((
+                location,
+                ErrorCode::UnexpectedToken
+                    .tag("defer can only be used directly in a function body"),
+            ));
+        }
+
+        match self.parse_statement()? {
+            Some(ast::Statement::Return(..) | ast::Statement::Defer { .. }) => Err((
+                location,
+                ErrorCode::UnexpectedToken.tag("return and defer can not be deferred"),
+            )),
+            Some(body) => Ok(ast::Statement::Defer {
+                body: Box::new(body),
+                location,
+            }),
+            None => {
+                let token = self.advance();
+                Err((
+                    token.source_location,
+                    ErrorCode::UnexpectedToken
+                        .tag(format!("expected a statement after defer, found {:?}", token.value)),
+                ))
+            }
+        }
+    }
+
+    fn parse_break(&mut self) -> CompilerResult<ast::Statement> {
+        let token = self.advance();
+        if self.loop_depth == 0 {
+            return Err((
+                token.source_location,
+                ErrorCode::BreakOutsideLoop.tag("break outside of loop"),
+            ));
+        }
+
+        self.expect(&TokenValue::Semicolon)?;
+        Ok(ast::Statement::Break)
+    }
+
     fn parse_statement(&mut self) -> CompilerResult<Option<ast::Statement>> {
         match self.peek() {
-            TokenValue::Print => self.parse_print().map(Some),
+            TokenValue::Print | TokenValue::PrintErr => self.parse_print().map(Some),
             TokenValue::Assert => self.parse_assert().map(Some),
-            TokenValue::Identifier(_) => self.parse_assignment().map(Some),
+            TokenValue::AssertType => self.parse_assert_type().map(Some),
+            TokenValue::AssertEq => self.parse_assert_eq().map(Some),
+            TokenValue::Identifier(name) if self.peek_nth(1) == TokenValue::Equal => {
+                if name == "_" {
+                    self.parse_discard().map(Some)
+                } else {
+                    self.parse_assignment().map(Some)
+                }
+            }
+            TokenValue::Identifier(_)
+            | TokenValue::Number(_)
+            | TokenValue::String(_)
+            | TokenValue::True
+            | TokenValue::False
+            | TokenValue::OpenParen
+            | TokenValue::OpenSquare
+            | TokenValue::Minus
+            | TokenValue::Star
+            | TokenValue::Bang => self.parse_expression_statement().map(Some),
             TokenValue::Return => self.parse_return().map(Some),
             TokenValue::Test => self.parse_test().map(Some),
+            TokenValue::Bench => self.parse_bench().map(Some),
             TokenValue::If => self.parse_if().map(Some),
+            TokenValue::While => self.parse_while().map(Some),
+            TokenValue::Switch => self.parse_switch().map(Some),
+            TokenValue::Do => self.parse_do_while().map(Some),
+            TokenValue::Repeat => self.parse_repeat().map(Some),
+            TokenValue::For => {
+                if let TokenValue::OpenParen = self.peek_nth(1) {
+                    self.parse_c_style_for().map(Some)
+                } else {
+                    self.parse_for_each().map(Some)
+                }
+            }
+            TokenValue::Break => self.parse_break().map(Some),
+            TokenValue::Defer => self.parse_defer().map(Some),
             _ => Ok(None),
         }
     }
 
     fn parse_codeblock(&mut self) -> CompilerResult<ast::CodeBody> {
+        self.parse_codeblock_with_end().map(|(body, _)| body)
+    }
+
+    /// Also returns the location of the closing `}`
+    fn parse_codeblock_with_end(&mut self) -> CompilerResult<(ast::CodeBody, SourceLocation)> {
         self.expect(&TokenValue::OpenBracket)?;
 
+        self.block_depth += 1;
         let mut statements = Vec::new();
-        while let Some(statement) = self.parse_statement()? {
-            statements.push(statement);
+        loop {
+            match self.parse_statement() {
+                Ok(Some(statement)) => statements.push(statement),
+                Ok(None) => break,
+                Err(err) => {
+                    self.errors.push(err);
+                    self.skip_statement();
+                }
+            }
         }
+        self.block_depth -= 1;
 
+        let end_location = self
+            .tokens
+            .front()
+            .map_or(self.end_location, |token| token.source_location);
         self.expect(&TokenValue::CloseBracket)?;
-        Ok(ast::CodeBody(statements))
+        Ok((ast::CodeBody(statements), end_location))
     }
 
-    fn parse_function_definition(&mut self) -> CompilerResult<ast::TopLevelStatement> {
-        self.expect(&TokenValue::Fn)?;
+    /// Parses `(a: Type, b: Type)`, a last `rest: ...` parameter takes any number of arguments
+    fn parse_parameters(&mut self) -> CompilerResult<Vec<ast::Parameter>> {
+        self.expect(&TokenValue::OpenParen)?;
 
-        let function_name_token = self.advance();
-        let function_name = match function_name_token.value {
-            TokenValue::Identifier(name) => name,
-            _ => {
-                return Err((
-                    function_name_token.source_location,
-                    format!("expected name, got {:?}", function_name_token.value),
-                ))
+        let mut parameters = Vec::new();
+        while self.peek() != TokenValue::CloseParen {
+            let name = self.parse_name()?.0;
+            self.expect(&TokenValue::Colon)?;
+            let (type_name, type_location) = if self.peek() == TokenValue::DotDotDot {
+                let location = self.advance().source_location;
+                if self.peek() != TokenValue::CloseParen {
+                    return Err((
+                        location,
+                        ErrorCode::UnexpectedToken
+                            .tag("The rest parameter has to be the last parameter"),
+                    ));
+                }
+                ("...".to_string(), location)
+            } else {
+                self.parse_type_name()?
+            };
+
+            parameters.push(ast::Parameter {
+                name,
+                type_name,
+                type_location,
+            });
+
+            if self.peek() != TokenValue::CloseParen {
+                self.expect(&TokenValue::Comma)?;
+            }
+        }
+
+        self.advance();
+        Ok(parameters)
+    }
+
+    fn parse_name(&mut self) -> CompilerResult<(String, SourceLocation)> {
+        let token = self.advance();
+        match token.value {
+            TokenValue::Identifier(name) => Ok((name, token.source_location)),
+            _ => Err((
+                token.source_location,
+                ErrorCode::UnexpectedToken.tag(format!("expected name, got {:?}", token.value)),
+            )),
+        }
+    }
+
+    /// A type name, pointer types are written as `&Type`
+    fn parse_type_name(&mut self) -> CompilerResult<(String, SourceLocation)> {
+        if self.peek() == TokenValue::OpenParen {
+            return self.parse_tuple_type_name();
+        }
+        if self.peek() == TokenValue::OpenSquare {
+            return self.parse_array_type_name();
+        }
+        if self.peek() != TokenValue::Ampersand {
+            return self.parse_name();
+        }
+
+        let location = self.advance().source_location;
+        let (inner, inner_location) = self.parse_type_name()?;
+        Ok((
+            format!("&{inner}"),
+            SourceLocation::combine(&location, &inner_location),
+        ))
+    }
+
+    /// `(Num, Bool)`, kept as a string like every other type name
+    fn parse_tuple_type_name(&mut self) -> CompilerResult<(String, SourceLocation)> {
+        let open_location = self.advance().source_location;
+
+        let mut element_names = Vec::new();
+        while self.peek() != TokenValue::CloseParen {
+            element_names.push(self.parse_type_name()?.0);
+
+            if self.peek() != TokenValue::CloseParen {
+                self.expect(&TokenValue::Comma)?;
+            }
+        }
+        let close_paren = self.advance();
+
+        Ok((
+            format!("({})", element_names.join(", ")),
+            SourceLocation::combine(&open_location, &close_paren.source_location),
+        ))
+    }
+
+    /// `[Num; 3]`
+    fn parse_array_type_name(&mut self) -> CompilerResult<(String, SourceLocation)> {
+        let open_location = self.advance().source_location;
+        let (element_name, _) = self.parse_type_name()?;
+        self.expect(&TokenValue::Semicolon)?;
+
+        let size_token = self.advance();
+        let TokenValue::Number(ref size) = size_token.value else {
+            return Err((
+                size_token.source_location,
+                ErrorCode::UnexpectedToken
+                    .tag(format!("Expected Number(_) got {:?}", size_token.value)),
+            ));
+        };
+        self.expect(&TokenValue::CloseSquare)?;
+
+        Ok((
+            format!("[{element_name}; {size}]"),
+            SourceLocation::combine(&open_location, &size_token.source_location),
+        ))
+    }
+
+    /// `type Name = Type;`
+    fn parse_type_alias(&mut self) -> CompilerResult<ast::TopLevelStatement> {
+        self.expect(&TokenValue::Type)?;
+        let (name, name_location) = self.parse_name()?;
+        self.expect(&TokenValue::Equal)?;
+        let (target_type, target_location) = self.parse_type_name()?;
+        self.expect(&TokenValue::Semicolon)?;
+
+        Ok(ast::TopLevelStatement::TypeAlias {
+            name,
+            name_location,
+            target_type,
+            target_location,
+        })
+    }
+
+    /// `struct Name { field: Type[width], ... }`
+    fn parse_bit_struct(&mut self) -> CompilerResult<ast::TopLevelStatement> {
+        self.expect(&TokenValue::Struct)?;
+        let (name, name_location) = self.parse_name()?;
+        self.expect(&TokenValue::OpenBracket)?;
+
+        let mut fields = Vec::new();
+        while self.peek() != TokenValue::CloseBracket {
+            let (field_name, _) = self.parse_name()?;
+            self.expect(&TokenValue::Colon)?;
+            let (type_name, type_location) = self.parse_name()?;
+
+            self.expect(&TokenValue::OpenSquare)?;
+            let width_token = self.advance();
+            let width = match width_token.value {
+                TokenValue::Number(ref digits) => digits.parse().map_err(|_| {
+                    (
+                        width_token.source_location,
+                        ErrorCode::InvalidType.tag(format!("Field width {digits} is too large")),
+                    )
+                })?,
+                value => {
+                    return Err((
+                        width_token.source_location,
+                        ErrorCode::UnexpectedToken.tag(format!(
+                            "Expected Number(_) got {:?}", value
+                        )),
+                    ))
+                }
+            };
+            self.expect(&TokenValue::CloseSquare)?;
+
+            fields.push(ast::BitField {
+                name: field_name,
+                type_name,
+                type_location,
+                width,
+            });
+
+            if self.peek() != TokenValue::CloseBracket {
+                self.expect(&TokenValue::Comma)?;
             }
+        }
+        self.advance();
+
+        Ok(ast::TopLevelStatement::BitStruct {
+            name,
+            name_location,
+            fields,
+        })
+    }
+
+    /// `extern fn name(args) -> Type;`
+    fn parse_extern_function(&mut self) -> CompilerResult<ast::TopLevelStatement> {
+        self.expect(&TokenValue::Extern)?;
+        self.expect(&TokenValue::Fn)?;
+
+        let (function_name, function_name_location) = self.parse_name()?;
+        let parameters = self.parse_parameters()?;
+        let (return_type_name, return_type_location) =
+            self.parse_return_type(function_name_location)?;
+
+        self.expect(&TokenValue::Semicolon)?;
+
+        Ok(ast::TopLevelStatement::ExternFunction {
+            function_name,
+            function_name_location,
+            parameters,
+            return_type_name,
+            return_type_location,
+            metadata: ast::FunctionMetadata::default(),
+        })
+    }
+
+    /// `-> Type`, functions without it return Void
+    fn parse_return_type(
+        &mut self,
+        name_location: SourceLocation,
+    ) -> CompilerResult<(String, SourceLocation)> {
+        if let TokenValue::Arrow = self.peek() {
+            self.advance();
+            self.parse_type_name()
+        } else {
+            Ok(("Void".to_string(), name_location))
+        }
+    }
+
+    /// `-> (quot: Num, rem: Num)` names the returned values, the return type is then the tuple of
+    /// their types, or the type itself when there is only one
+    fn parse_named_returns(
+        &mut self,
+        parameters: &[ast::Parameter],
+        name_location: SourceLocation,
+    ) -> CompilerResult<(Vec<ast::Parameter>, String, SourceLocation)> {
+        let is_named = self.peek() == TokenValue::Arrow
+            && self.peek_nth(1) == TokenValue::OpenParen
+            && matches!(self.peek_nth(2), TokenValue::Identifier(_))
+            && self.peek_nth(3) == TokenValue::Colon;
+        if !is_named {
+            let (return_type_name, return_type_location) = self.parse_return_type(name_location)?;
+            return Ok((Vec::new(), return_type_name, return_type_location));
+        }
+
+        self.advance();
+        let open_location = self.tokens.front().unwrap().source_location;
+        let named_returns = self.parse_parameters()?;
+        let last_location = named_returns.last().unwrap().type_location;
+        let return_type_location = SourceLocation::combine(&open_location, &last_location);
+
+        for (index, named) in named_returns.iter().enumerate() {
+            let message = if named.type_name == "..." {
+                "A named return value can not be a rest parameter".to_string()
+            } else if parameters.iter().any(|parameter| parameter.name == named.name) {
+                format!("{} is already a parameter", named.name)
+            } else if named_returns[..index].iter().any(|other| other.name == named.name) {
+                format!("{} is already a named return value", named.name)
+            } else {
+                continue;
+            };
+            return Err((named.type_location, ErrorCode::UnexpectedToken.tag(message)));
+        }
+
+        let type_names: Vec<&str> = named_returns
+            .iter()
+            .map(|named| named.type_name.as_str())
+            .collect();
+        let return_type_name = match type_names.as_slice() {
+            [type_name] => (*type_name).to_string(),
+            type_names => format!("({})", type_names.join(", ")),
         };
 
+        Ok((named_returns, return_type_name, return_type_location))
+    }
+
+    /// `#[name]` or `#[name("argument")]` attributes in front of a function
+    fn parse_attributes(
+        &mut self,
+    ) -> CompilerResult<Vec<(String, Option<String>, SourceLocation)>> {
+        let mut attributes = Vec::new();
+        while self.peek() == TokenValue::Hash {
+            let hash_location = self.advance().source_location;
+            self.expect(&TokenValue::OpenSquare)?;
+
+            let name_token = self.advance();
+            let name = match name_token.value {
+                TokenValue::Identifier(name) => name,
+                // `test` is a keyword because of test statements
+                TokenValue::Test => "test".to_string(),
+                value => {
+                    return Err((
+                        name_token.source_location,
+                        ErrorCode::UnexpectedToken.tag(format!(
+                            "Expected attribute name got {:?}", value
+                        )),
+                    ))
+                }
+            };
+            let argument = if self.peek() == TokenValue::OpenParen {
+                self.advance();
+                let argument_token = self.advance();
+                let TokenValue::String(argument) = argument_token.value else {
+                    return Err((
+                        argument_token.source_location,
+                        ErrorCode::UnexpectedToken
+                            .tag("Expected String for the argument of an attribute"),
+                    ));
+                };
+                self.expect(&TokenValue::CloseParen)?;
+                Some(argument)
+            } else {
+                None
+            };
+            self.expect(&TokenValue::CloseSquare)?;
+
+            attributes.push((
+                name,
+                argument,
+                SourceLocation::combine(&hash_location, &name_token.source_location),
+            ));
+        }
+
+        Ok(attributes)
+    }
+
+    /// The `fn name() { ... }` after `#[test]`, tests take no arguments and return nothing
+    fn parse_test_function(
+        &mut self,
+        inlining_hint: ast::InliningHint,
+    ) -> CompilerResult<ast::TopLevelStatement> {
+        self.expect(&TokenValue::Fn)?;
+
+        let (name, name_location) = self.parse_name()?;
         self.expect(&TokenValue::OpenParen)?;
         self.expect(&TokenValue::CloseParen)?;
 
-        self.expect(&TokenValue::Arrow)?;
+        let body = self.parse_codeblock()?;
 
-        let return_type_token = self.advance();
-        let return_type_name = match return_type_token.value {
-            TokenValue::Identifier(name) => name,
-            _ => {
+        Ok(ast::TopLevelStatement::TestFunction {
+            name,
+            name_location,
+            body,
+            metadata: ast::FunctionMetadata {
+                inlining_hint,
+                ..ast::FunctionMetadata::default()
+            },
+        })
+    }
+
+    fn parse_function_definition(&mut self) -> CompilerResult<ast::TopLevelStatement> {
+        // The doc comment is attached to the first token of the definition, `#`, `pub` or `fn`
+        let doc_comment = self
+            .tokens
+            .front()
+            .and_then(|token| token.doc_comment.clone());
+
+        let mut is_test = false;
+        let mut inlining_hint = ast::InliningHint::None;
+        let mut deprecated = None;
+        for (attribute, argument, location) in self.parse_attributes()? {
+            if argument.is_some() && attribute != "deprecated" {
                 return Err((
-                    return_type_token.source_location,
-                    format!("expected name, got {:?}", return_type_token.value),
-                ))
+                    location,
+                    ErrorCode::UnexpectedToken
+                        .tag(format!("Attribute {attribute} does not take an argument")),
+                ));
+            }
+
+            let hint = match attribute.as_str() {
+                "test" => {
+                    is_test = true;
+                    continue;
+                }
+                "deprecated" => {
+                    deprecated = Some(argument.unwrap_or_default());
+                    continue;
+                }
+                "inline" => ast::InliningHint::Always,
+                "noinline" => ast::InliningHint::Never,
+                _ => {
+                    return Err((
+                        location,
+                        ErrorCode::UnexpectedToken.tag(format!("Unknown attribute {attribute}")),
+                    ))
+                }
+            };
+            if inlining_hint != ast::InliningHint::None && inlining_hint != hint {
+                return Err((
+                    location,
+                    ErrorCode::UnexpectedToken.tag("A function can not be both inline and noinline"),
+                ));
+            }
+            inlining_hint = hint;
+        }
+
+        let is_public = if let TokenValue::Pub = self.peek() {
+            let location = self.advance().source_location;
+            if is_test {
+                return Err((
+                    location,
+                    ErrorCode::UnexpectedToken.tag("Test functions can not be pub"),
+                ));
             }
+            true
+        } else {
+            false
         };
+        if is_test {
+            return self.parse_test_function(inlining_hint);
+        }
+        self.expect(&TokenValue::Fn)?;
 
-        let body = self.parse_codeblock()?;
+        let (function_name, function_name_location) = self.parse_name()?;
+        let parameters = self.parse_parameters()?;
+        let (named_returns, return_type_name, return_type_location) =
+            self.parse_named_returns(&parameters, function_name_location)?;
+
+        let (body, body_end_location) = self.parse_codeblock_with_end()?;
 
         Ok(ast::TopLevelStatement::FunctionDefinition {
             function_name,
+            function_name_location,
+            is_public,
+            parameters,
+            named_returns,
             body,
+            body_end_location,
             return_type_name,
-            return_type_location: return_type_token.source_location,
-            metadata: ast::FunctionMetadata::default(),
+            return_type_location,
+            metadata: ast::FunctionMetadata {
+                doc_comment,
+                inlining_hint,
+                deprecated,
+                ..ast::FunctionMetadata::default()
+            },
         })
     }
 
     fn parse_toplevel_statement(&mut self) -> CompilerResult<Option<ast::TopLevelStatement>> {
         match self.peek() {
-            TokenValue::Fn => self.parse_function_definition().map(Some),
+            TokenValue::Fn | TokenValue::Pub | TokenValue::Hash => {
+                self.parse_function_definition().map(Some)
+            }
+            TokenValue::Extern => self.parse_extern_function().map(Some),
+            TokenValue::Struct => self.parse_bit_struct().map(Some),
+            TokenValue::Type => self.parse_type_alias().map(Some),
             TokenValue::EndOfFile => Ok(None),
             _ => {
                 let token = self.advance();
                 Err((
                     token.source_location,
-                    format!(
+                    ErrorCode::UnexpectedToken.tag(format!(
                         "expected start of top level definition or end of file, got {:?}",
                         token.value
-                    ),
+                    )),
                 ))
             }
         }
     }
 
-    pub fn parse_file(&mut self) -> CompilerResult<ast::File> {
+    /// Keeps parsing after an error, so every error in the file is returned in order
+    pub fn parse_file(&mut self) -> Result<ast::File, Vec<(SourceLocation, String)>> {
         let mut statements = Vec::new();
 
-        while let Some(statement) = self.parse_toplevel_statement()? {
-            statements.push(statement);
+        loop {
+            match self.parse_toplevel_statement() {
+                Ok(Some(statement)) => statements.push(statement),
+                Ok(None) => break,
+                Err(err) => {
+                    self.errors.push(err);
+                    self.skip_toplevel_statement();
+                }
+            }
         }
 
-        Ok(ast::File(statements))
+        if self.errors.is_empty() {
+            Ok(ast::File(statements))
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
 }
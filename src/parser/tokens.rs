@@ -1,6 +1,7 @@
 use super::source_location::SourceLocation;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum TokenValue {
     // LITERALS
     Number(String),
@@ -11,19 +12,41 @@ pub enum TokenValue {
     
     // KEYWORDS
     Print,
+    PrintErr,
     Assert,
+    AssertType,
+    AssertEq,
     Test,
+    Bench,
     Is,
     If,
     Else,
+    While,
+    Do,
+    Repeat,
+    Switch,
+    Case,
+    Default,
+    For,
+    Break,
+    Defer,
+    Type,
+    In,
+    Not,
     
     // SYMBOLS
     Semicolon,
     Minus,
     Plus,
     Star,
+    Ampersand,
     ForwardSlash,
     Comma,
+    Dot,
+    DotDotDot,
+    Hash,
+    Colon,
+    ColonColon,
     Bang,
 
     Equal,
@@ -39,9 +62,14 @@ pub enum TokenValue {
     CloseParen,
     OpenBracket,
     CloseBracket,
+    OpenSquare,
+    CloseSquare,
 
     Arrow,
     Fn,
+    Pub,
+    Extern,
+    Struct,
     Return,
 
     EndOfFile
@@ -51,5 +79,7 @@ pub enum TokenValue {
 pub struct Token {
     pub value: TokenValue,
     pub source_location: SourceLocation,
+    /// The `/** */` comment right before this token, if there was one
+    pub doc_comment: Option<String>,
 }
 
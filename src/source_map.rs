@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::SourceLocation;
+
+/// The metadata kind the code generator attaches to instructions, pointing at a node with the
+/// `line_start`, `line_end`, `char_start` and `char_end` of the statement they belong to
+pub const LOCATION_METADATA_KIND: &str = "viv.loc";
+
+/// Maps lines of the generated LLVM IR back to the statements they were compiled from
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    pub entries: Vec<SourceMapEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceMapEntry {
+    /// 1-indexed, like the lines in `SourceLocation`
+    pub ir_line: usize,
+    pub location: SourceLocation,
+}
+
+impl SourceMap {
+    /// Reads the `!viv.loc` metadata from the printed IR.
+    /// A new entry starts at every line whose location differs from the line before it.
+    #[must_use]
+    pub fn from_ir(ir: &str) -> Self {
+        let marker = format!("!{LOCATION_METADATA_KIND} !");
+        let nodes: HashMap<&str, SourceLocation> = ir.lines().filter_map(parse_node).collect();
+
+        let mut entries: Vec<SourceMapEntry> = Vec::new();
+        for (index, line) in ir.lines().enumerate() {
+            let Some((_, node)) = line.split_once(&marker) else {
+                continue;
+            };
+            let node = node.split(|c: char| !c.is_ascii_digit()).next().unwrap();
+            let Some(&location) = nodes.get(node) else {
+                continue;
+            };
+
+            if entries.last().map(|entry| entry.location) != Some(location) {
+                entries.push(SourceMapEntry {
+                    ir_line: index + 1,
+                    location,
+                });
+            }
+        }
+
+        Self { entries }
+    }
+
+    /// The location of the statement the given IR line was compiled from
+    #[must_use]
+    pub fn lookup(&self, ir_line: usize) -> Option<SourceLocation> {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.ir_line <= ir_line);
+        index
+            .checked_sub(1)
+            .map(|index| self.entries[index].location)
+    }
+
+    /// `{"version": 1, "file": ..., "mappings": [{"ir_line": ..., "line_start": ..., ...}]}`
+    #[must_use]
+    pub fn to_json(&self, source_file: &str) -> String {
+        let mappings: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{{\"ir_line\": {}, \"line_start\": {}, \"line_end\": {}, \
+                     \"char_start\": {}, \"char_end\": {}}}",
+                    entry.ir_line,
+                    entry.location.line_start,
+                    entry.location.line_end,
+                    entry.location.char_start,
+                    entry.location.char_end
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"version\": 1, \"file\": \"{}\", \"mappings\": [{}]}}",
+            escape_json(source_file),
+            mappings.join(", ")
+        )
+    }
+}
+
+/// `!3 = !{i64 1, i64 1, i64 5, i64 9}`
+fn parse_node(line: &str) -> Option<(&str, SourceLocation)> {
+    let (name, values) = line.strip_prefix('!')?.split_once(" = !{")?;
+    let values: Vec<usize> = values
+        .strip_suffix('}')?
+        .split(", ")
+        .map(|value| value.strip_prefix("i64 ")?.parse().ok())
+        .collect::<Option<_>>()?;
+
+    match values[..] {
+        [line_start, line_end, char_start, char_end] => Some((
+            name,
+            SourceLocation {
+                line_start,
+                line_end,
+                char_start,
+                char_end,
+                file: 0,
+            },
+        )),
+        _ => None,
+    }
+}
+
+pub fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,33 +1,146 @@
+/// Whether a string has to be freed by whoever holds it
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Ownership {
+    Owned,
+    Borrowed,
+}
+
+impl From<bool> for Ownership {
+    fn from(owned: bool) -> Self {
+        if owned {
+            Self::Owned
+        } else {
+            Self::Borrowed
+        }
+    }
+}
+
+impl Ownership {
+    pub fn is_owned(self) -> bool {
+        self == Self::Owned
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TypeInformation {
     Number,
+    // Storage for variables that only ever hold small literals with `--small-ints`,
+    // reading them gives a Number
+    Number8,
+    Number16,
     Boolean,
 
-    // Bool indicates wether it is owned or not
-    String(bool),
+    String(Ownership),
+    // A non owning view into a string, stored as a pointer and a length
+    StringSlice,
+    // `&T`, the address of a value of type T
+    Pointer(Box<TypeInformation>),
+    // A fixed size array, `size` is known at compile time
+    Array {
+        element: Box<TypeInformation>,
+        size: usize,
+    },
+    // `(Num, Bool)`, a fixed group of values that can have different types
+    Tuple(Vec<TypeInformation>),
+    // Named fields of a given bit width packed into one integer, the first field is the lowest bits
+    BitStruct {
+        name: String,
+        fields: Vec<(String, TypeInformation, usize)>,
+    },
+    // The result of a function that does not return a value, like `exit`
+    Void,
+    // The `name: ...` rest parameter of a variadic function, holds how many arguments it got.
+    // Looping over it reads each argument as a Num
+    VarArgs,
 }
 
 impl TypeInformation {
     pub fn mark_owned(self) -> Self {
         match self {
-            Self::String(false) => Self::String(true),
+            Self::String(Ownership::Borrowed) => Self::String(Ownership::Owned),
             _ => self,
         }
     }
 
     pub fn mark_borrowed(self) -> Self {
         match self {
-            Self::String(true) => Self::String(false),
+            Self::String(Ownership::Owned) => Self::String(Ownership::Borrowed),
             _ => self,
         }
     }
 
-    pub fn same_type(a: Self, b: Self) -> bool {
-        matches!(
-            (a, b),
-            (Self::Number, Self::Number)
-                | (Self::Boolean, Self::Boolean)
-                | (Self::String(_), Self::String(_))
-        )
+    /// The type a value has once it is read, small numbers are widened to Number
+    pub fn widened(self) -> Self {
+        match self {
+            Self::Number8 | Self::Number16 => Self::Number,
+            _ => self,
+        }
+    }
+
+    /// The type as it is written in the code, arrays have no syntax so they are `[T; size]`
+    pub fn name(&self) -> String {
+        match self {
+            Self::Number => "Num".to_string(),
+            Self::Number8 => "Num8".to_string(),
+            Self::Number16 => "Num16".to_string(),
+            Self::Boolean => "Bool".to_string(),
+            Self::String(_) => "String".to_string(),
+            Self::StringSlice => "Str".to_string(),
+            Self::Pointer(pointee) => format!("&{}", pointee.name()),
+            Self::Array { element, size } => format!("[{}; {size}]", element.name()),
+            Self::Tuple(elements) => {
+                let names: Vec<String> = elements.iter().map(Self::name).collect();
+                format!("({})", names.join(", "))
+            }
+            Self::BitStruct { name, .. } => name.clone(),
+            Self::Void => "Void".to_string(),
+            Self::VarArgs => "...".to_string(),
+        }
+    }
+
+    /// The bit offset, type and width of a field of a bit struct
+    pub fn bit_field(&self, field_name: &str) -> Option<(usize, &TypeInformation, usize)> {
+        let Self::BitStruct { fields, .. } = self else {
+            return None;
+        };
+
+        let mut offset = 0;
+        for (name, type_, width) in fields {
+            if name == field_name {
+                return Some((offset, type_, *width));
+            }
+            offset += width;
+        }
+        None
+    }
+
+    pub fn same_type(a: &Self, b: &Self) -> bool {
+        match (a, b) {
+            (Self::Pointer(a), Self::Pointer(b)) => Self::same_type(a, b),
+            (
+                Self::Array { element, size },
+                Self::Array {
+                    element: other_element,
+                    size: other_size,
+                },
+            ) => size == other_size && Self::same_type(element, other_element),
+            (Self::BitStruct { name, .. }, Self::BitStruct { name: other_name, .. }) => {
+                name == other_name
+            }
+            (Self::Tuple(a), Self::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Self::same_type(a, b))
+            }
+            _ => matches!(
+                (a, b),
+                (Self::Number, Self::Number)
+                    | (Self::Number8, Self::Number8)
+                    | (Self::Number16, Self::Number16)
+                    | (Self::Boolean, Self::Boolean)
+                    | (Self::String(_), Self::String(_))
+                    | (Self::StringSlice, Self::StringSlice)
+                    | (Self::Void, Self::Void)
+                    | (Self::VarArgs, Self::VarArgs)
+            ),
+        }
     }
 }
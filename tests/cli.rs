@@ -0,0 +1,521 @@
+use std::path::PathBuf;
+
+/// A path in the temp folder that no other test, or other run of the tests, uses
+fn unique_temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("viv_test_{name}_{}", std::process::id()))
+}
+
+#[test]
+fn test_run_stdin() {
+    const CODE: &str = "
+    fn main() -> Num {
+        print \"Hello Stdin\";
+
+        return 0;
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", "-"])
+        .stdin(CODE)
+        .stdout().contains("Hello Stdin")
+        .unwrap();
+}
+
+#[test]
+fn test_explain() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--explain", "E002"])
+        .stdout().contains("Erroneous code example")
+        .unwrap();
+}
+
+#[test]
+fn test_explain_unknown_code() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--explain", "E999"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn test_exit_code() {
+    const CODE: &str = "
+    fn stop(code: Num) -> Num {
+        exit(code);
+        return 0;
+    }
+
+    fn main() -> Num {
+        print \"before\";
+        _ = stop(3);
+        print \"after\";
+
+        return 0;
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", "-"])
+        .stdin(CODE)
+        .fails_with(3)
+        .stdout().contains("before")
+        .stdout().doesnt_contain("after")
+        .unwrap();
+}
+
+#[test]
+fn test_test_functions() {
+    const CODE: &str = "
+    #[test]
+    fn adds_numbers() {
+        assert 1 + 1 == 2;
+    }
+
+    fn main() -> Num {
+        print \"main\";
+        return 0;
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", "-", "--test"])
+        .stdin(CODE)
+        .stdout().contains("OK adds_numbers")
+        .stdout().contains("main")
+        .unwrap();
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", "-"])
+        .stdin(CODE)
+        .stdout().doesnt_contain("adds_numbers")
+        .unwrap();
+}
+
+#[test]
+fn test_eval_print() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["-e", "print 40 + 2;"])
+        .stdout().contains("42")
+        .unwrap();
+}
+
+#[test]
+fn test_eval_with_subcommand() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["-e", "print 1;", "run", "-"])
+        .stdin("fn main() -> Num { return 0; }")
+        .fails_with(2)
+        .stderr().contains("-e can not be used with a subcommand")
+        .unwrap();
+}
+
+#[test]
+fn test_eval_multiple() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["-e", "x = 20;", "--eval", "print x * 2;"])
+        .stdout().contains("40")
+        .unwrap();
+}
+
+#[test]
+fn test_eval_expression() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["-e", "exit(4);"])
+        .fails_with(4)
+        .unwrap();
+}
+
+#[test]
+fn test_print_ast_after_pass() {
+    const CODE: &str = "
+    fn main() -> Num {
+        x = 1;
+        return x;
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["--print-ast-after-pass", "types", "run", "-"])
+        .stdin(CODE)
+        .fails_with(1)
+        .stderr().contains("AST after types")
+        .stderr().contains("type_information: Some(")
+        .unwrap();
+}
+
+#[test]
+fn test_tail_call_marked() {
+    const CODE: &str = "
+    fn countdown(n: Num) -> Num {
+        if n == 0 {
+            return 0;
+        }
+        return countdown(n - 1);
+    }
+
+    fn main() -> Num {
+        return countdown(3);
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["--print-ast-after-pass", "return_check", "run", "-"])
+        .stdin(CODE)
+        .stderr().contains("is_tail_call: true")
+        .stderr().contains("is_tail_call: false")
+        .unwrap();
+}
+
+#[test]
+fn test_simplify_boolean_chain() {
+    const CODE: &str = "
+    fn main() -> Num {
+        x = 5;
+        y = 3;
+        print x == x >= x;
+        print x > y > x;
+        return 0;
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["--print-ast-after-pass", "simplify", "run", "-"])
+        .stdin(CODE)
+        .stderr().contains("AST after simplify")
+        .stderr().contains("Boolean(\n")
+        .stderr().doesnt_contain("ComparisonChain")
+        .stdout().contains("true\nfalse")
+        .unwrap();
+}
+
+#[test]
+fn test_print_ast_unknown_pass() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--print-ast-after-pass", "parse", "-e", "print 1;"])
+        .fails()
+        .unwrap();
+}
+
+#[test]
+fn test_define() {
+    const CODE: &str = "
+    fn main() -> Num {
+    #if MODE == \"debug\"
+        print \"debug build\";
+    #else
+        print \"release build\";
+    #endif
+        return 0;
+    }
+    ";
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["-D", "MODE=debug", "run", "-"])
+        .stdin(CODE)
+        .stdout().contains("debug build")
+        .unwrap();
+}
+
+#[test]
+fn test_invalid_target() {
+    let file = temp_file::with_contents(b"fn main() -> Num { return 0; }");
+    let output = unique_temp_path("invalid_target");
+
+    assert_cli::Assert::main_binary()
+        .with_args(&[
+            "build",
+            file.path().to_str().unwrap(),
+            output.to_str().unwrap(),
+            "--target",
+            "not-a-target",
+        ])
+        .fails()
+        .stderr().contains("[E020] Invalid target not-a-target")
+        .unwrap();
+}
+
+#[test]
+fn test_pgo_flags_conflict() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["build", "in.viv", "out", "--pgo-generate", "--pgo-use", "app.profdata"])
+        .fails()
+        .stderr().contains("cannot be used with")
+        .unwrap();
+}
+
+#[test]
+fn test_test_missing_folder() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["test", "does_not_exist"])
+        .fails()
+        .stderr().contains("could not read does_not_exist")
+        .unwrap();
+}
+
+#[test]
+fn test_test_expected_output() {
+    let folder = unique_temp_path("expected_output");
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(
+        folder.join("streams.viv"),
+        "fn main() -> Num { print \"out\"; eprint \"err\"; return 0; }",
+    )
+    .unwrap();
+    std::fs::write(folder.join("streams.stdout"), "out\n").unwrap();
+    std::fs::write(folder.join("streams.stderr"), "err\n").unwrap();
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["test", folder.to_str().unwrap()])
+        .stderr().doesnt_contain("wrote a different")
+        .unwrap();
+
+    // stderr output does not count towards stdout
+    std::fs::write(folder.join("streams.stdout"), "out\nerr\n").unwrap();
+    assert_cli::Assert::main_binary()
+        .with_args(&["test", folder.to_str().unwrap()])
+        .stderr().contains("wrote a different stdout")
+        .unwrap();
+
+    std::fs::remove_dir_all(folder).unwrap();
+}
+
+#[test]
+fn test_ir_output_dir() {
+    let file = temp_file::with_contents(b"fn main() -> Num { return 0; }");
+    let output_dir = unique_temp_path("ir_output_dir");
+    let stem = file.path().file_stem().unwrap().to_str().unwrap();
+
+    assert_cli::Assert::main_binary()
+        .with_args(&[
+            "ir",
+            file.path().to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ])
+        .unwrap();
+
+    let ir_file = output_dir.join(format!("{stem}.ll"));
+    assert!(ir_file.is_file());
+    std::fs::remove_dir_all(output_dir).unwrap();
+}
+
+#[test]
+fn test_ir_cache() {
+    let file = temp_file::with_contents(b"fn main() -> Num { return 0; }");
+    let cache_dir = unique_temp_path("ir_cache");
+    let output = unique_temp_path("ir_cache.ll");
+
+    for _ in 0..2 {
+        assert_cli::Assert::main_binary()
+            .with_args(&[
+                "--cache-dir",
+                cache_dir.to_str().unwrap(),
+                "ir",
+                file.path().to_str().unwrap(),
+                output.to_str().unwrap(),
+            ])
+            .unwrap();
+    }
+
+    let mut entries: Vec<_> = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        std::fs::read_to_string(&entries[1]).unwrap(),
+        std::fs::read_to_string(&output).unwrap()
+    );
+    let metadata = std::fs::read_to_string(&entries[0]).unwrap();
+    assert!(metadata.contains(file.path().to_str().unwrap()));
+    assert_eq!(
+        std::fs::read_to_string(&entries[2]).unwrap(),
+        "fn main() -> Num { return 0; }"
+    );
+
+    std::fs::remove_dir_all(cache_dir).unwrap();
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_ir_cache_other_file() {
+    const CODE: &[u8] = b"fn main() -> Num { return 0; }";
+    let first = temp_file::with_contents(CODE);
+    let second = temp_file::with_contents(CODE);
+    let cache_dir = unique_temp_path("ir_cache_other_file");
+    let output = unique_temp_path("ir_cache_other_file.ll");
+
+    // The same code shares one entry, but the IR still names the file it was compiled from
+    for file in [&first, &second] {
+        assert_cli::Assert::main_binary()
+            .with_args(&[
+                "--cache-dir",
+                cache_dir.to_str().unwrap(),
+                "ir",
+                file.path().to_str().unwrap(),
+                output.to_str().unwrap(),
+            ])
+            .unwrap();
+    }
+
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 3);
+    let ir = std::fs::read_to_string(&output).unwrap();
+    assert!(ir.contains(second.path().to_str().unwrap()));
+    assert!(!ir.contains(first.path().to_str().unwrap()));
+
+    std::fs::remove_dir_all(cache_dir).unwrap();
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_ir_cache_skips_stdin_and_eval() {
+    let cache_dir = unique_temp_path("ir_cache_stdin");
+    let output = unique_temp_path("ir_cache_stdin.ll");
+
+    assert_cli::Assert::main_binary()
+        .with_args(&[
+            "--cache-dir",
+            cache_dir.to_str().unwrap(),
+            "ir",
+            "-",
+            output.to_str().unwrap(),
+        ])
+        .stdin("fn main() -> Num { return 0; }")
+        .unwrap();
+    assert_cli::Assert::main_binary()
+        .with_args(&["--cache-dir", cache_dir.to_str().unwrap(), "-e", "print 1;"])
+        .stdout().contains("1")
+        .unwrap();
+
+    assert!(!cache_dir.exists());
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_ir_cache_warnings() {
+    const CODE: &str = "
+    #[deprecated]
+    fn old() -> Num { return 1; }
+
+    fn main() -> Num { return old(); }
+    ";
+    let file = temp_file::with_contents(CODE.as_bytes());
+    let cache_dir = unique_temp_path("ir_cache_warnings");
+    let output = unique_temp_path("ir_cache_warnings.ll");
+
+    // Code with warnings is not cached, so the second run warns again
+    for _ in 0..2 {
+        assert_cli::Assert::main_binary()
+            .with_args(&[
+                "--cache-dir",
+                cache_dir.to_str().unwrap(),
+                "ir",
+                file.path().to_str().unwrap(),
+                output.to_str().unwrap(),
+            ])
+            .stderr().contains("Function old is deprecated")
+            .unwrap();
+    }
+
+    assert!(!cache_dir.exists());
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_ir_cache_include_str() {
+    // The space before the bang is still an include, and the included file is not in the key
+    let text = temp_file::with_contents(b"included");
+    let text_name = text.path().file_name().unwrap().to_str().unwrap();
+    let code = format!("fn main() -> Num {{ print include_str !(\"{text_name}\"); return 0; }}");
+    let file = temp_file::with_contents(code.as_bytes());
+    let cache_dir = unique_temp_path("ir_cache_include");
+    let output = unique_temp_path("ir_cache_include.ll");
+
+    assert_cli::Assert::main_binary()
+        .with_args(&[
+            "--cache-dir",
+            cache_dir.to_str().unwrap(),
+            "ir",
+            file.path().to_str().unwrap(),
+            output.to_str().unwrap(),
+        ])
+        .unwrap();
+
+    assert!(!cache_dir.exists());
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_doc() {
+    const CODE: &str = "
+    /** Adds two numbers
+
+    Nothing special */
+    pub fn add(a: Num, b: Num) -> Num { return a + b; }
+
+    fn main() -> Num { return add(1, 2); }
+    ";
+    let file = temp_file::with_contents(CODE.as_bytes());
+    let output_dir = unique_temp_path("doc");
+
+    assert_cli::Assert::main_binary()
+        .with_args(&[
+            "doc",
+            file.path().to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ])
+        .unwrap();
+
+    let index = std::fs::read_to_string(output_dir.join("index.html")).unwrap();
+    assert!(index.contains("pub fn add(a: Num, b: Num) -&gt; Num</code></a> Adds two numbers"));
+    let page = std::fs::read_to_string(output_dir.join("fn.add.html")).unwrap();
+    assert!(page.contains("<p>Nothing special</p>"));
+    assert!(output_dir.join("fn.main.html").is_file());
+    std::fs::remove_dir_all(output_dir).unwrap();
+}
+
+#[test]
+fn test_config_in_parent_folder() {
+    let project = unique_temp_path("config_parent");
+    let nested = project.join("src").join("nested");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(project.join("viv.toml"), "test_folder = \"from_config\"\n").unwrap();
+
+    // The folder does not exist, so the error names the folder that was picked
+    assert_cli::Assert::main_binary()
+        .current_dir(&nested)
+        .with_args(&["test"])
+        .fails()
+        .stderr().contains("could not read from_config")
+        .unwrap();
+
+    // A flag on the command line takes precedence over the config file
+    assert_cli::Assert::main_binary()
+        .current_dir(&nested)
+        .with_args(&["test", "from_flag"])
+        .fails()
+        .stderr().contains("could not read from_flag")
+        .unwrap();
+
+    std::fs::remove_dir_all(project).unwrap();
+}
+
+#[test]
+fn test_malformed_config() {
+    let project = unique_temp_path("config_malformed");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("viv.toml"), "optimize = 5\n").unwrap();
+
+    assert_cli::Assert::main_binary()
+        .current_dir(&project)
+        .with_args(&["-e", "print 1;"])
+        .fails()
+        .stderr().contains("ERROR: invalid config file")
+        .stderr().contains("viv.toml")
+        .unwrap();
+
+    std::fs::remove_dir_all(project).unwrap();
+}
@@ -0,0 +1,1268 @@
+use viv_script::{check, collect_warnings, CompilerConfig, CompilerWarning};
+
+fn error_message(code: &str) -> String {
+    check(code).unwrap_err().1
+}
+
+#[test]
+fn test_suggest_variable() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        my_var = 1;
+        print my_vr;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("did you mean 'my_var'?"));
+}
+
+#[test]
+fn test_suggest_function() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print subst(\"hello\", 0, 1);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("did you mean 'substr'?"));
+}
+
+#[test]
+fn test_no_suggestion_when_too_different() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        my_var = 1;
+        print something_else;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(!message.contains("did you mean"));
+}
+
+#[test]
+fn test_error_code() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        x = 1 + true;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_shadowing_warning() {
+    const CODE: &str = "
+    fn main() -> Num {
+        x = 1;
+        if true {
+            x = 2;
+            x = \"shadow\";
+        }
+        return 0;
+    }
+    ";
+
+    let config = CompilerConfig {
+        warn_shadowing: true,
+        ..CompilerConfig::default()
+    };
+    let warnings = collect_warnings(CODE, &config).unwrap();
+    assert!(matches!(
+        warnings.as_slice(),
+        [CompilerWarning::Shadowing { name, .. }] if name == "x"
+    ));
+
+    let warnings = collect_warnings(CODE, &CompilerConfig::default()).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_dead_function_warning() {
+    const CODE: &str = "
+    fn main() -> Num {
+        return 0;
+    }
+
+    fn unused() -> Num {
+        return 1;
+    }
+
+    pub fn exported() -> Num {
+        return 2;
+    }
+    ";
+
+    let config = CompilerConfig {
+        warn_dead_functions: true,
+        ..CompilerConfig::default()
+    };
+    let warnings = collect_warnings(CODE, &config).unwrap();
+    assert!(matches!(
+        warnings.as_slice(),
+        [CompilerWarning::UnusedFunction { name, .. }] if name == "unused"
+    ));
+}
+
+#[test]
+fn test_dead_store_warning() {
+    const CODE: &str = "
+    fn main() -> Num {
+        x = 1;
+        x = 2;
+        y = 1;
+        y = y + 1;
+        z = 1;
+        if true {
+            print z;
+        }
+        z = 2;
+        p = 1;
+        pointer = &p;
+        p = 2;
+        print *pointer;
+        p = 3;
+        return x + y + z + p;
+    }
+    ";
+
+    let config = CompilerConfig {
+        warn_dead_stores: true,
+        ..CompilerConfig::default()
+    };
+    let warnings = collect_warnings(CODE, &config).unwrap();
+    assert!(matches!(
+        warnings.as_slice(),
+        [CompilerWarning::DeadStore { name, location }] if name == "x" && location.line_start == 3
+    ));
+}
+
+#[test]
+fn test_deprecated_warning() {
+    const CODE: &str = "
+    #[deprecated(\"Use add instead\")]
+    fn plus(a: Num, b: Num) -> Num {
+        return a + b;
+    }
+
+    #[deprecated]
+    fn old() -> Num {
+        return 0;
+    }
+
+    fn main() -> Num {
+        x = plus(1, 2);
+        return old() + x;
+    }
+    ";
+
+    let warnings = collect_warnings(CODE, &CompilerConfig::default()).unwrap();
+    let messages: Vec<String> = warnings.iter().map(CompilerWarning::message).collect();
+    assert_eq!(
+        messages,
+        [
+            "Function plus is deprecated: Use add instead",
+            "Function old is deprecated"
+        ]
+    );
+    assert_eq!(warnings[0].location().line_start, 13);
+}
+
+#[test]
+fn test_inline_takes_no_argument() {
+    let message = error_message(
+        "
+    #[inline(\"always\")]
+    fn main() -> Num {
+        return 0;
+    }
+    ",
+    );
+    assert!(message.contains("Attribute inline does not take an argument"));
+}
+
+#[test]
+fn test_bool_ordering_is_invalid() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print true < false;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E006]"));
+}
+
+#[test]
+fn test_contains_needs_strings() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print 1 in 2;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E006]"));
+}
+
+#[test]
+fn test_variables_are_scoped_to_blocks() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        if true {
+            inner = 1;
+        }
+        print inner;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E002]"));
+}
+
+#[test]
+fn test_variadic_warning() {
+    const CODE: &str = "
+    fn sum(first: Num, rest: ...) -> Num {
+        total = first;
+        for value in rest {
+            total = total + value;
+        }
+        return total;
+    }
+
+    fn main() -> Num {
+        return sum(1, 2, true) + sum(1, \"two\");
+    }
+    ";
+
+    let warnings = collect_warnings(CODE, &CompilerConfig::default()).unwrap();
+    let messages: Vec<String> = warnings.iter().map(CompilerWarning::message).collect();
+    assert_eq!(
+        messages,
+        ["String passed to the rest parameter of sum, these are not type checked"]
+    );
+
+    let message = error_message(
+        "
+    fn log(rest: ..., level: Num) {}
+
+    fn main() -> Num {
+        return 0;
+    }
+    ",
+    );
+    assert!(message.contains("The rest parameter has to be the last parameter"));
+}
+
+#[test]
+fn test_named_return_parameter() {
+    let message = error_message(
+        "
+    fn double(value: Num) -> (value: Num) {
+        return;
+    }
+
+    fn main() -> Num {
+        return double(2);
+    }
+    ",
+    );
+    assert!(message.contains("value is already a parameter"));
+}
+
+#[test]
+fn test_assert_type() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        assert_type!(1 + 2, Bool);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_wrong_argument_count() {
+    let message = error_message(
+        "
+    fn add(a: Num, b: Num) -> Num {
+        return a + b;
+    }
+
+    fn main() -> Num {
+        print add(1);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E008]"));
+    assert!(message.contains("add expects 2 arguments, got 1"));
+}
+
+#[test]
+fn test_wrong_argument_type() {
+    let message = error_message(
+        "
+    fn negate(value: Bool) -> Bool {
+        return !value;
+    }
+
+    fn main() -> Num {
+        print negate(1);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_deref_non_pointer() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        x = 1;
+        print *x;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E005]"));
+}
+
+#[test]
+fn test_pointer_type_mismatch() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        x = true;
+        assert_type!(&x, &Num);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_assign_void() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        x = exit(1);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("Void"));
+}
+
+#[test]
+fn test_for_each_over_non_array() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        for x in 5 {
+            print x;
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_mixed_array() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        numbers = [1, true];
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_loop_variable_scope() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        for x in [1, 2] {
+            print x;
+        }
+        print x;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E002]"));
+}
+
+#[test]
+fn test_len_of_non_array() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print len(5);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("expected an array"));
+}
+
+#[test]
+fn test_duplicate_function_in_file() {
+    let message = error_message(
+        "
+    fn helper() -> Num {
+        return 1;
+    }
+
+    fn helper() -> Num {
+        return 2;
+    }
+
+    fn main() -> Num {
+        return helper();
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E013]"));
+    assert!(message.contains("Function helper is already defined on line 2"));
+}
+
+#[test]
+fn test_extern_argument_type() {
+    let message = error_message(
+        "
+    extern fn abs(x: Num) -> Num;
+
+    fn main() -> Num {
+        return abs(true);
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_tuple_index_out_of_range() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        pair = (1, true);
+        return pair.2;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("has no element 2"));
+}
+
+#[test]
+fn test_unknown_attribute() {
+    let message = error_message(
+        "
+    #[fast]
+    fn main() -> Num {
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E012]"));
+    assert!(message.contains("Unknown attribute fast"));
+}
+
+#[test]
+fn test_conflicting_inline_hints() {
+    let message = error_message(
+        "
+    #[inline]
+    #[noinline]
+    fn helper() -> Num {
+        return 1;
+    }
+
+    fn main() -> Num {
+        return helper();
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E012]"));
+    assert!(message.contains("both inline and noinline"));
+}
+
+#[test]
+fn test_ragged_nested_array() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        grid = [[1, 2], [3]];
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("same type"));
+}
+
+#[test]
+fn test_format_without_arguments() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print format();
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("format expects at least 1 argument"));
+}
+
+#[test]
+fn test_format_specifiers() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print format(\"%s\", 5);
+        return 0;
+    }
+    ",
+    );
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("%s expects a String, got Number"));
+
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print format(\"%d and %d\", 5);
+        return 0;
+    }
+    ",
+    );
+    assert!(message.starts_with("[E008]"));
+    assert!(message.contains("the format string takes 2 values, got 1"));
+
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print format(\"%f\", 5);
+        return 0;
+    }
+    ",
+    );
+    assert!(message.contains("unsupported format specifier %f"));
+}
+
+#[test]
+fn test_unknown_bit_field() {
+    let message = error_message(
+        "
+    struct Flags {
+        ready: Bool[1],
+    }
+
+    fn main() -> Num {
+        flags = Flags();
+        _ = get_field(flags, \"redy\");
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("did you mean 'ready'?"));
+}
+
+#[test]
+fn test_bit_range() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        return get_bits(1, 0, 33);
+    }
+    ",
+    );
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("bit width 33 is not in 0 to 32"));
+
+    let message = error_message(
+        "
+    fn main() -> Num {
+        return set_bits(1, 30, 4, 0);
+    }
+    ",
+    );
+    assert!(message.contains("4 bits after bit 30 do not fit in a Num"));
+}
+
+#[test]
+fn test_bit_struct_too_wide() {
+    let message = error_message(
+        "
+    struct Wide {
+        low: Num[32],
+        high: Num[32],
+        extra: Bool[1],
+    }
+
+    fn main() -> Num {
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E003]"));
+    assert!(message.contains("65 bits wide"));
+}
+
+#[test]
+fn test_missing_return() {
+    const CODE: &str = "
+    fn sign(x: Num) -> Num {
+        if x > 0 {
+            return 1;
+        }
+    }
+
+    fn main() -> Num {
+        return sign(1);
+    }
+    ";
+    let (location, message) = check(CODE).unwrap_err();
+
+    assert!(message.starts_with("[E014]"));
+    assert!(message.contains("sign"));
+    // The closing brace of sign, where the return is missing
+    assert_eq!((location.line_start, location.char_start), (6, 5));
+}
+
+#[test]
+fn test_every_branch_returns() {
+    check(
+        "
+    fn sign(x: Num) -> Num {
+        if x > 0 {
+            return 1;
+        } else if x < 0 {
+            return -1;
+        } else {
+            return 0;
+        }
+    }
+
+    fn main() -> Num {
+        return sign(1);
+    }
+    ",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_unknown_constant() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print Num::MAXX;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E002]"));
+    assert!(message.contains("did you mean 'Num::MAX'?"));
+}
+
+#[test]
+fn test_for_variable_scope() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        for (i = 0; i < 3; i = i + 1) {
+            print i;
+        }
+        return i;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E002]"));
+}
+
+#[test]
+fn test_for_init_not_assignment() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        for (print 1; true; i = 1) {}
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E012]"));
+    assert!(message.contains("init"));
+}
+
+#[test]
+fn test_do_while_condition() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        do {
+            print 1;
+        } while 1;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E007]"));
+}
+
+#[test]
+fn test_duplicate_case() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        switch 1 {
+            case 1: {}
+            case 1: {}
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E015]"));
+}
+
+#[test]
+fn test_switch_on_bool() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        switch true {
+            case 1: {}
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_repeat_count_type() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        repeat true {
+            print 1;
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+}
+
+#[test]
+fn test_stray_backslash() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        x = 1 \\ 2;
+        return x;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E011]"));
+}
+
+#[test]
+fn test_continuation_keeps_lines() {
+    let (location, _) = check(
+        "
+    fn main() -> Num {
+        x = 1 + \\
+            y;
+        return x;
+    }
+    ",
+    )
+    .unwrap_err();
+
+    assert_eq!(location.line_start, 4);
+}
+
+#[test]
+fn test_keyword_location() {
+    // Keywords are lexed one character at a time, the location has to start at their first one
+    const CODE: &str = "fn main() -> Num {\nbreak;\nreturn 0;\n}\n";
+    let (location, message) = check(CODE).unwrap_err();
+
+    assert!(message.starts_with("[E009]"));
+    assert_eq!(
+        (location.line_start, location.char_start, location.char_end),
+        (2, 1, 5)
+    );
+    assert_eq!(location.get_line_highlights(CODE), "2 | break;\n    ^^^^^");
+
+    const INDENTED: &str = "fn main() -> Num {\n    x = 1; break;\n    return x;\n}\n";
+    let (location, _) = check(INDENTED).unwrap_err();
+    assert_eq!((location.char_start, location.char_end), (12, 16));
+}
+
+#[test]
+fn test_unicode_identifier_location() {
+    // Columns count characters, `é` is two bytes but one column
+    const CODE: &str = "fn main() -> Num {\ncafé = 1; break;\nreturn café;\n}\n";
+    let (location, _) = check(CODE).unwrap_err();
+    assert_eq!((location.char_start, location.char_end), (11, 15));
+
+    let message = error_message("fn main() -> Num {\n    return π;\n}\n");
+    assert!(message.contains("Name π not defined"));
+}
+
+#[test]
+fn test_unclosed_if_directive() {
+    let (location, message) = check(
+        "
+    fn main() -> Num {
+    #if PLATFORM == \"linux\"
+        return 0;
+    }
+    ",
+    )
+    .unwrap_err();
+
+    assert!(message.starts_with("[E012]"));
+    assert_eq!(location.line_start, 3);
+}
+
+#[test]
+fn test_skipped_lines_keep_numbers() {
+    let (location, _) = check(
+        "
+    fn main() -> Num {
+    #if NOT_DEFINED
+        x = 1;
+    #endif
+        return y;
+    }
+    ",
+    )
+    .unwrap_err();
+
+    assert_eq!(location.line_start, 6);
+}
+
+#[test]
+fn test_empty_return_in_num_function() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        return;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("got Void"));
+}
+
+#[test]
+fn test_void_function_value() {
+    let message = error_message(
+        "
+    fn nothing() {}
+
+    fn main() -> Num {
+        x = nothing();
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("Expected a value, got Void"));
+}
+
+#[test]
+fn test_number_condition_hint() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        count = 1;
+        if count {
+            print count;
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E007]"));
+    assert!(message.contains("got Num, did you mean `count != 0`?"));
+}
+
+#[test]
+fn test_len_argument() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print len(5);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("expected an array, a String or a rest parameter, got Number"));
+}
+
+#[test]
+fn test_assert_condition() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        assert 1;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E007]"));
+    assert!(message.contains("Expected Boolean, got Number"));
+}
+
+#[test]
+fn test_string_condition_hint() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        name = \"viv\";
+        while name {
+            break;
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("got String, did you mean `len(name) != 0`?"));
+}
+
+#[test]
+fn test_defer_in_branch() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        if true {
+            defer print 1;
+        }
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E012]"));
+    assert!(message.contains("directly in a function body"));
+}
+
+#[test]
+fn test_defer_checks_types() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        defer print missing;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E002]"));
+}
+
+#[test]
+fn test_cyclic_type_alias() {
+    let message = error_message(
+        "
+    type Meters = Distance;
+    type Distance = Meters;
+
+    fn main() -> Num {
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E017]"));
+    assert!(message.contains("Meters -> Distance -> Meters"));
+}
+
+#[test]
+fn test_multiple_syntax_errors() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        x = 1 +;
+        y = ;
+        return 0;
+    }
+
+    fn other() -> Num {
+        return (;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E012]"));
+    assert_eq!(message.matches("[E012]").count(), 3);
+    assert!(message.contains("return (;"));
+}
+
+#[test]
+fn test_set_char_literal() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        set_char(\"hello\", 0, 74);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("set_char can only change a String variable"));
+}
+
+#[test]
+fn test_substr_temporary() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print substr(format(\"%d\", 12345), 1, 2);
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("Can not slice a temporary String"));
+}
+
+#[test]
+fn test_return_slice_of_local() {
+    let message = error_message(
+        "
+    fn first(word: String) -> Str {
+        copy = word;
+        return substr(copy, 0, 1);
+    }
+
+    fn main() -> Num {
+        print first(\"hello\");
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("Can not return a Str pointing into copy"));
+}
+
+#[test]
+fn test_return_slice_of_parameter() {
+    let message = error_message(
+        "
+    fn first(word: String) -> Str {
+        part = substr(word, 0, 1);
+        return part;
+    }
+
+    fn main() -> Num {
+        print first(\"hello\");
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("Can not return a Str pointing into word"));
+}
+
+#[test]
+fn test_assign_sliced_string() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        word = \"hello\";
+        part = substr(word, 0, 2);
+        word = format(\"%d\", 12345);
+        print part;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("Can not assign to word while the Str part points into it"));
+}
+
+#[test]
+fn test_slice_outlives_block() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        part = substr(\"hello\", 0, 2);
+        repeat 2 {
+            word = format(\"%d\", 12345);
+            part = substr(word, 0, 2);
+        }
+        print part;
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("The Str part would outlive word"));
+}
+
+#[test]
+fn test_range_bounds() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        end = 3;
+        numbers = range(0, end);
+        return 0;
+    }
+    ",
+    );
+    assert!(message.starts_with("[E001]"));
+    assert!(message.contains("expected the bounds of range as number literals"));
+
+    let message = error_message(
+        "
+    fn main() -> Num {
+        numbers = range(5, 5);
+        return 0;
+    }
+    ",
+    );
+    assert!(message.contains("range(5, 5) is empty"));
+
+    let message = error_message(
+        "
+    fn main() -> Num {
+        numbers = range(0, 2147483647);
+        return 0;
+    }
+    ",
+    );
+    assert!(message.contains("range(0, 2147483647) has 2147483647 elements, at most 65536"));
+}
+
+#[test]
+fn test_include_missing_file() {
+    let message = error_message(
+        "
+    fn main() -> Num {
+        print include_str!(\"does_not_exist.txt\");
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E018]"));
+    assert!(message.contains("Could not include does_not_exist.txt"));
+}
+
+#[test]
+fn test_alias_of_builtin_name() {
+    let message = error_message(
+        "
+    type Num = Bool;
+
+    fn main() -> Num {
+        return 0;
+    }
+    ",
+    );
+
+    assert!(message.contains("Num is already a builtin type"));
+}
+
+#[test]
+fn test_runtime_function_name() {
+    let message = error_message(
+        "
+    fn free(value: Num) -> Num {
+        return value;
+    }
+
+    fn main() -> Num {
+        return free(0);
+    }
+    ",
+    );
+
+    assert!(message.starts_with("[E019]"));
+    assert!(message.contains("free is a C function used by the compiled code"));
+
+    // Declaring them is fine
+    check(
+        "
+    extern fn strlen(value: String) -> Num;
+
+    fn main() -> Num {
+        return strlen(\"abc\");
+    }
+    ",
+    )
+    .unwrap();
+}
@@ -0,0 +1,23 @@
+use viv_script::find_exe;
+
+#[test]
+fn test_absolute_path() {
+    let exe = std::env::current_exe().unwrap();
+
+    assert_eq!(find_exe(&[exe.to_str().unwrap()]), Some(exe));
+}
+
+#[test]
+fn test_absolute_path_missing() {
+    assert_eq!(find_exe(&["/this/path/does/not/exist"]), None);
+}
+
+#[test]
+fn test_absolute_path_fallback() {
+    let exe = std::env::current_exe().unwrap();
+
+    assert_eq!(
+        find_exe(&["/this/path/does/not/exist", exe.to_str().unwrap()]),
+        Some(exe)
+    );
+}
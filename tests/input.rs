@@ -0,0 +1,61 @@
+#[test]
+fn test_input() {
+    const CODE: &str = "
+    fn main() -> Num {
+        name = input();
+        print \"Hello \", name;
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdin("viv\n")
+        .stdout().contains("Hello viv")
+        .unwrap();
+}
+
+#[test]
+fn test_input_num() {
+    const CODE: &str = "
+    fn main() -> Num {
+        a = input_num();
+        b = input_num();
+        print a + b;
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdin("1 2\n")
+        .stdout().contains("3")
+        .unwrap();
+}
+
+#[test]
+fn test_input_at_end() {
+    const CODE: &str = "
+    fn main() -> Num {
+        a = input_num();
+        name = input();
+        print a, \"[\", name, \"]\";
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdin("")
+        .stdout().contains("0[]")
+        .unwrap();
+}
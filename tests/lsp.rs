@@ -0,0 +1,74 @@
+use serde_json::{json, Value};
+
+fn frame(messages: &[Value]) -> String {
+    messages
+        .iter()
+        .map(|message| {
+            let body = message.to_string();
+            format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+        })
+        .collect()
+}
+
+fn initialize() -> Value {
+    json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}})
+}
+
+fn open(code: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": "file:///main.viv", "text": code}},
+    })
+}
+
+fn exit() -> Value {
+    json!({"jsonrpc": "2.0", "method": "exit"})
+}
+
+#[test]
+fn test_lsp_initialize() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["lsp"])
+        .stdin(frame(&[initialize(), exit()]))
+        .stdout()
+        .contains(r#""hoverProvider":true"#)
+        .unwrap();
+}
+
+#[test]
+fn test_lsp_diagnostics() {
+    let open = open("fn main() -> Num {\n    return x;\n}");
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["lsp"])
+        .stdin(frame(&[initialize(), open, exit()]))
+        .stdout()
+        .contains("textDocument/publishDiagnostics")
+        .stdout()
+        .contains(r#""start":{"character":11,"line":1}"#)
+        .stdout()
+        .contains(r#""severity":1"#)
+        .unwrap();
+}
+
+#[test]
+fn test_lsp_hover() {
+    let open = open("fn main() -> Num {\n    x = 1;\n    return x;\n}");
+    let hover = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/hover",
+        "params": {
+            "textDocument": {"uri": "file:///main.viv"},
+            "position": {"line": 2, "character": 11},
+        },
+    });
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["lsp"])
+        .stdin(frame(&[initialize(), open, hover, exit()]))
+        .stdout()
+        .contains(r#""value":"Num""#)
+        .unwrap();
+}
@@ -0,0 +1,110 @@
+use viv_script::{compile_multiple_to_ir, CompilerConfig};
+
+#[test]
+fn test_merge_files() {
+    const MAIN: &str = "
+    fn main() -> Num {
+        return 0;
+    }
+    ";
+    const OTHER: &str = "
+    pub fn other() -> Num {
+        return 1;
+    }
+    ";
+
+    let output = temp_file::empty();
+    let output_path = output.path().to_str().unwrap();
+
+    compile_multiple_to_ir(
+        "test",
+        &[("main.viv", MAIN), ("other.viv", OTHER)],
+        output_path,
+        &CompilerConfig::default(),
+    )
+    .unwrap();
+
+    let ir = std::fs::read_to_string(output_path).unwrap();
+    assert!(ir.contains("@main"));
+    assert!(ir.contains("@other"));
+}
+
+#[test]
+fn test_duplicate_function() {
+    const MAIN: &str = "
+    fn main() -> Num {
+        return 0;
+    }
+    ";
+
+    let output = temp_file::empty();
+
+    let (_, message) = compile_multiple_to_ir(
+        "test",
+        &[("main.viv", MAIN), ("copy.viv", MAIN)],
+        output.path().to_str().unwrap(),
+        &CompilerConfig::default(),
+    )
+    .unwrap_err();
+
+    assert!(message.contains("already defined in main.viv"));
+}
+
+#[test]
+fn test_call_across_files() {
+    const MAIN: &str = "
+    extern fn abs(value: Num) -> Num;
+
+    fn main() -> Num {
+        return abs(other());
+    }
+    ";
+    const OTHER: &str = "
+    extern fn abs(value: Num) -> Num;
+
+    pub fn other() -> Num {
+        return abs(-1);
+    }
+    ";
+
+    let output = temp_file::empty();
+    let output_path = output.path().to_str().unwrap();
+
+    compile_multiple_to_ir(
+        "test",
+        &[("main.viv", MAIN), ("other.viv", OTHER)],
+        output_path,
+        &CompilerConfig::default(),
+    )
+    .unwrap();
+
+    let ir = std::fs::read_to_string(output_path).unwrap();
+    assert!(ir.contains("@other"));
+}
+
+#[test]
+fn test_error_in_second_file() {
+    const MAIN: &str = "
+    fn main() -> Num {
+        return 0;
+    }
+    ";
+    const OTHER: &str = "
+    pub fn other() -> Num {
+        return missing;
+    }
+    ";
+
+    let output = temp_file::empty();
+
+    let (location, message) = compile_multiple_to_ir(
+        "test",
+        &[("main.viv", MAIN), ("other.viv", OTHER)],
+        output.path().to_str().unwrap(),
+        &CompilerConfig::default(),
+    )
+    .unwrap_err();
+
+    assert!(message.starts_with("other.viv: [E002]"));
+    assert_eq!((location.file, location.line_start), (1, 3));
+}
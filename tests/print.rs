@@ -69,4 +69,454 @@ fn test_bool_false() {
         .with_args(&["run", file.path().to_str().unwrap()])
         .stdout().contains("false")
         .unwrap();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_bool_many() {
+    const CODE: &str = "
+    fn main() -> Num {
+        x = 3;
+        print true, \" \", x < 2, \" \", x == 3;
+        eprint false;
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("true false true")
+        .stderr().contains("false")
+        .unwrap();
+}
+
+#[test]
+fn test_string_slice() {
+    const CODE: &str = "
+    fn main() -> Num {
+        print substr(\"Hello World\", 6, 5);
+        print \"[\", substr(\"Hello\", 3, 100), \"]\";
+        print \"[\", substr(\"Hello\", 50, 2), \"]\";
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("World\n[lo]\n[]\n")
+        .unwrap();
+}
+
+#[test]
+fn test_multiple_values() {
+    const CODE: &str = "
+    fn main() -> Num {
+        x = 1;
+        print x, \",\", true, \" \", substr(\"abc\", 1, 1);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("1,true b\n")
+        .unwrap();
+}
+
+#[test]
+fn test_reassigned_string() {
+    const CODE: &str = "
+    fn main() -> Num {
+        text = \"a longer string\";
+        text = \"short\";
+        print text, \"|\";
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("short|\n")
+        .unwrap();
+}
+
+#[test]
+fn test_format() {
+    const CODE: &str = "
+    fn main() -> Num {
+        name = \"viv\";
+        print format(\"%s is %d, %d\", name, 3, true);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("viv is 3, 1")
+        .unwrap();
+}
+
+#[test]
+fn test_void_function() {
+    const CODE: &str = "
+    fn report(x: Num) {
+        if x < 0 {
+            print \"negative\";
+            return;
+        }
+        print \"checked \", x;
+    }
+
+    fn main() -> Num {
+        report(-1);
+        report(7);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("negative\nchecked 7")
+        .unwrap();
+}
+
+#[test]
+fn test_defer_order() {
+    const CODE: &str = "
+    fn work(fail: Bool) -> Num {
+        print \"open\";
+        defer print \"first\";
+        defer print \"second\";
+        if fail {
+            return 1;
+        }
+        print \"done\";
+        return 0;
+    }
+
+    fn main() -> Num {
+        _ = work(true);
+        _ = work(false);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("open\nsecond\nfirst\nopen\ndone\nsecond\nfirst")
+        .unwrap();
+}
+
+#[test]
+fn test_defer_before_return_value() {
+    const CODE: &str = "
+    fn bump() -> Num {
+        count = 1;
+        defer count = count + 1;
+        return count * 10;
+    }
+
+    fn main() -> Num {
+        print bump();
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("20")
+        .unwrap();
+}
+
+#[test]
+fn test_defer_in_void_function() {
+    const CODE: &str = "
+    fn greet() {
+        defer print \"bye\";
+        print \"hi\";
+    }
+
+    fn main() -> Num {
+        greet();
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("hi\nbye")
+        .unwrap();
+}
+
+#[test]
+fn test_eprint() {
+    const CODE: &str = "
+    fn main() -> Num {
+        print \"normal\";
+        eprint \"went wrong: \", 42;
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("normal")
+        .stdout().doesnt_contain("went wrong")
+        .stderr().contains("went wrong: 42")
+        .unwrap();
+}
+
+#[test]
+fn test_bench() {
+    const CODE: &str = "
+    fn square(x: Num) -> Num {
+        return x * x;
+    }
+
+    fn main() -> Num {
+        bench \"square\" 1000 -> square(7);
+        print \"done\";
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap(), "--bench"])
+        .stdout().contains("square: ")
+        .stdout().contains("ns per iteration\ndone")
+        .unwrap();
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().doesnt_contain("square: ")
+        .unwrap();
+}
+
+#[test]
+fn test_array() {
+    const CODE: &str = "
+    fn main() -> Num {
+        numbers = [1, 2, 3];
+        print numbers;
+        print \"grid: \", [[1, 2], [3, 4]], \"!\";
+        print [true], [false, true];
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("[1, 2, 3]\ngrid: [[1, 2], [3, 4]]!\n[true][false, true]")
+        .unwrap();
+}
+
+#[test]
+fn test_assert_eq() {
+    const CODE: &str = "
+    fn main() -> Num {
+        assert_eq!(4, 2 + 2);
+        assert_eq!(5, 2 + 2, \"math broke\");
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .fails()
+        .stderr().contains("Assertion failed on line 4: math broke, expected 5 but got 4")
+        .unwrap();
+}
+
+#[test]
+fn test_char_index_bounds() {
+    const CODE: &str = "
+    fn main() -> Num {
+        word = \"hello\";
+        print char_at(word, 4);
+        print char_at(word, 5);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .fails()
+        .stderr().contains("char_at on line 5: index 5 is out of bounds for length 5")
+        .unwrap();
+
+    const SET_CODE: &str = "
+    fn main() -> Num {
+        word = \"hello\";
+        set_char(word, -1, 65);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(SET_CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .fails()
+        .stderr().contains("set_char on line 4: index -1 is out of bounds for length 5")
+        .unwrap();
+}
+
+#[test]
+fn test_small_ints() {
+    const CODE: &str = "
+    fn main() -> Num {
+        small = 100;
+        medium = 1000;
+        negative = -128;
+        counter = 0;
+        counter = counter + 300;
+        numbers = [1, -2, 127];
+
+        print small + medium + negative, \" \", counter;
+        print numbers;
+        for number in numbers {
+            print number * 2;
+        }
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["--small-ints", "run", file.path().to_str().unwrap()])
+        .stdout().contains("972 300\n[1, -2, 127]\n2\n-4\n254\n")
+        .unwrap();
+}
+
+#[test]
+fn test_small_ints_escaping() {
+    // Arrays used as values and variables with their address taken keep their Num type
+    const CODE: &str = "
+    fn sum(numbers: [Num; 3]) -> Num {
+        total = 0;
+        for number in numbers {
+            total = total + number;
+        }
+        return total;
+    }
+
+    fn plus_one(pointer: &Num) -> Num {
+        return *pointer + 1;
+    }
+
+    fn main() -> Num {
+        numbers = [1, 2, 3];
+        value = 5;
+
+        print sum(numbers), \" \", plus_one(&value);
+        print typeof_str(numbers), \" \", typeof_str(&value);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["--small-ints", "run", file.path().to_str().unwrap()])
+        .stdout().contains("6 6\n[Num; 3] &Num\n")
+        .unwrap();
+}
+
+#[test]
+fn test_variadic() {
+    const CODE: &str = "
+    extern fn printf(format: String, values: ...) -> Num;
+
+    fn sum(rest: ...) -> Num {
+        total = 0;
+        for value in rest {
+            total = total + value;
+        }
+        return total;
+    }
+
+    fn main() -> Num {
+        print sum(), \" \", sum(1, 2, 3), \" \", sum(40, true, true);
+        _ = printf(\"%d arguments\\n\", 2);
+
+        return 0;
+    }
+    ";
+
+    let file = temp_file::with_contents(CODE.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("0 6 42\n2 arguments")
+        .unwrap();
+}
+
+#[test]
+fn test_include_str() {
+    // Both temporary files are in the same folder
+    let text = temp_file::with_contents(b"Hello from a file, 100% \\n raw");
+    let text_name = text.path().file_name().unwrap().to_str().unwrap();
+    let code = format!(
+        "
+    fn main() -> Num {{
+        message = include_str!(\"{text_name}\");
+        print message;
+        print len(message);
+
+        return 0;
+    }}
+    "
+    );
+
+    let file = temp_file::with_contents(code.as_bytes());
+
+    assert_cli::Assert::main_binary()
+        .with_args(&["run", file.path().to_str().unwrap()])
+        .stdout().contains("Hello from a file, 100% \\n raw\n30\n")
+        .unwrap();
+}
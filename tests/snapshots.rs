@@ -0,0 +1,70 @@
+use std::fs;
+
+use viv_script::{compile_to_ir_string, CompilerConfig};
+
+/// Compiles every `.viv` file in `tests/snapshots` and compares the IR to the `.ll` file next to it.
+/// A missing or different snapshot fails, run with `UPDATE_SNAPSHOTS=1` to write them instead.
+#[test]
+fn test_ir_snapshots() {
+    let update = std::env::var("UPDATE_SNAPSHOTS").map_or(false, |value| value == "1");
+    // Unoptimized, so the snapshots show what the code generator itself produces
+    let config = CompilerConfig {
+        optimize: false,
+        ..CompilerConfig::default()
+    };
+
+    let mut failures = Vec::new();
+    for entry in fs::read_dir("tests/snapshots").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(true, |ext| ext != "viv") {
+            continue;
+        }
+
+        let code = fs::read_to_string(&path).unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let ir = compile_to_ir_string(name, &code, &config)
+            .unwrap_or_else(|(_, err)| panic!("{name} failed to compile: {err}"));
+
+        let snapshot_path = path.with_extension("ll");
+        if update {
+            fs::write(&snapshot_path, ir).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&snapshot_path) {
+            Ok(expected) => {
+                if let Some(difference) = first_difference(&expected, &ir) {
+                    failures.push(format!("{}: {difference}", snapshot_path.display()));
+                }
+            }
+            Err(_) => failures.push(format!("{}: missing snapshot", snapshot_path.display())),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "IR snapshots differ, run with UPDATE_SNAPSHOTS=1 if this is expected\n{}",
+        failures.join("\n")
+    );
+}
+
+fn first_difference(expected: &str, actual: &str) -> Option<String> {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+
+    for line_number in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (expected, actual) if expected != actual => {
+                return Some(format!(
+                    "line {line_number}\n  - {}\n  + {}",
+                    expected.unwrap_or("<end of file>"),
+                    actual.unwrap_or("<end of file>")
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    unreachable!()
+}
+
@@ -0,0 +1,41 @@
+use viv_script::{compile_to_ir_with_sourcemap, CompilerConfig};
+
+const CODE: &str = "fn main() -> Num {
+    x = 1 + 2;
+    print x;
+    return 0;
+}";
+
+#[test]
+fn test_ir_lines_map_to_statements() {
+    let config = CompilerConfig {
+        optimize: false,
+        ..CompilerConfig::default()
+    };
+    let (ir, source_map) = compile_to_ir_with_sourcemap("test", CODE, &config).unwrap();
+
+    let print_line = ir
+        .lines()
+        .position(|line| line.contains("call") && line.contains("@printf"))
+        .unwrap()
+        + 1;
+    let location = source_map.lookup(print_line).unwrap();
+    assert_eq!(location.line_start, 3);
+
+    let lines: Vec<usize> = source_map
+        .entries
+        .iter()
+        .map(|entry| entry.location.line_start)
+        .collect();
+    assert_eq!(lines, [2, 3, 4]);
+}
+
+#[test]
+fn test_source_map_json() {
+    let (_, source_map) =
+        compile_to_ir_with_sourcemap("test", CODE, &CompilerConfig::default()).unwrap();
+
+    let json = source_map.to_json("main.viv");
+    assert!(json.starts_with("{\"version\": 1, \"file\": \"main.viv\", \"mappings\": [{"));
+    assert!(json.contains("\"line_start\": 4"));
+}
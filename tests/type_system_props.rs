@@ -0,0 +1,111 @@
+use proptest::prelude::*;
+use viv_script::check;
+
+fn in_main(body: &str) -> String {
+    format!(
+        "
+    fn main() -> Num {{
+        num_var = 1;
+        bool_var = true;
+        {body}
+        return 0;
+    }}
+    "
+    )
+}
+
+fn num_expression() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        any::<i16>().prop_map(|value| value.to_string()),
+        Just("num_var".to_string()),
+    ];
+
+    leaf.prop_recursive(4, 32, 2, |inner| {
+        prop_oneof![
+            (inner.clone(), arithmetic_operator(), inner.clone())
+                .prop_map(|(left, op, right)| format!("({left} {op} {right})")),
+            inner.prop_map(|expression| format!("-({expression})")),
+        ]
+    })
+}
+
+fn bool_expression() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        Just("true".to_string()),
+        Just("false".to_string()),
+        Just("bool_var".to_string()),
+    ];
+
+    leaf.prop_recursive(4, 32, 1, |inner| {
+        inner.prop_map(|expression| format!("!({expression})"))
+    })
+}
+
+fn arithmetic_operator() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just("+"), Just("-"), Just("*"), Just("/")]
+}
+
+fn comparison_operator() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        Just("=="),
+        Just("!="),
+        Just(">"),
+        Just(">="),
+        Just("<"),
+        Just("<=")
+    ]
+}
+
+proptest! {
+    #[test]
+    fn arithmetic_keeps_num_type(
+        left in num_expression(),
+        op in arithmetic_operator(),
+        right in num_expression(),
+    ) {
+        // Reassigning a Num only works if the first assignment was a Num as well
+        let code = in_main(&format!("result = {left} {op} {right}; result = 0;"));
+        prop_assert!(check(&code).is_ok(), "{}", code);
+    }
+
+    #[test]
+    fn arithmetic_rejects_mixed_types(
+        left in num_expression(),
+        op in arithmetic_operator(),
+        right in bool_expression(),
+    ) {
+        let code = in_main(&format!("result = {left} {op} {right};"));
+        prop_assert!(check(&code).is_err(), "{}", code);
+    }
+
+    #[test]
+    fn comparison_chain_is_bool(
+        first in num_expression(),
+        rest in prop::collection::vec((comparison_operator(), num_expression()), 1..5),
+    ) {
+        let chain = rest
+            .iter()
+            .fold(first, |chain, (op, expression)| format!("{chain} {op} {expression}"));
+
+        let code = in_main(&format!("if {chain} {{}}"));
+        prop_assert!(check(&code).is_ok(), "{}", code);
+    }
+
+    #[test]
+    fn not_is_bool(expression in bool_expression()) {
+        let code = in_main(&format!("if !({expression}) {{}}"));
+        prop_assert!(check(&code).is_ok(), "{}", code);
+    }
+
+    #[test]
+    fn literals_are_typed(
+        literal in prop_oneof![
+            any::<i32>().prop_map(|value| value.to_string()),
+            any::<bool>().prop_map(|value| value.to_string()),
+            "[a-zA-Z0-9 ]*".prop_map(|value| format!("\"{value}\"")),
+        ]
+    ) {
+        let code = in_main(&format!("print {literal};"));
+        prop_assert!(check(&code).is_ok(), "{}", code);
+    }
+}